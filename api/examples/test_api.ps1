@@ -1,6 +1,8 @@
 # Example PowerShell script to test the SonarQube API
 
 $API_BASE_URL = "http://localhost:8080/api"
+# Create one with POST /api/api-keys
+$Headers = @{ Authorization = "Bearer $env:SONARCUTE_API_KEY" }
 
 Write-Host "=== Testing SonarQube Code Check Report API ===" -ForegroundColor Green
 Write-Host ""
@@ -18,7 +20,7 @@ $createProjectBody = @{
 } | ConvertTo-Json
 
 try {
-    $createResponse = Invoke-RestMethod -Uri "$API_BASE_URL/projects" -Method POST -Body $createProjectBody -ContentType "application/json"
+    $createResponse = Invoke-RestMethod -Uri "$API_BASE_URL/projects" -Method POST -Body $createProjectBody -ContentType "application/json" -Headers $Headers
     $createResponse | ConvertTo-Json -Depth 10
 } catch {
     Write-Host "Error creating project: $($_.Exception.Message)" -ForegroundColor Red
@@ -31,7 +33,7 @@ $scanProjectBody = @{
 } | ConvertTo-Json
 
 try {
-    $scanResponse = Invoke-RestMethod -Uri "$API_BASE_URL/scan" -Method POST -Body $scanProjectBody -ContentType "application/json"
+    $scanResponse = Invoke-RestMethod -Uri "$API_BASE_URL/scan" -Method POST -Body $scanProjectBody -ContentType "application/json" -Headers $Headers
     $scanResponse | ConvertTo-Json -Depth 10
 } catch {
     Write-Host "Error scanning project: $($_.Exception.Message)" -ForegroundColor Red
@@ -3,6 +3,8 @@
 # Example script to test the SonarQube API
 
 API_BASE_URL="http://localhost:8080/api"
+# Create one with POST /api/api-keys
+API_KEY="${SONARCUTE_API_KEY:?Set SONARCUTE_API_KEY}"
 
 echo "=== Testing SonarQube Code Check Report API ==="
 echo
@@ -11,6 +13,7 @@ echo
 echo "1. Creating a new project..."
 curl -X POST "$API_BASE_URL/projects" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "project_key": "test-project",
     "project_name": "Test Project",
@@ -25,6 +28,7 @@ echo
 echo "2. Scanning the project..."
 curl -X POST "$API_BASE_URL/scan" \
   -H "Content-Type: application/json" \
+  -H "Authorization: Bearer $API_KEY" \
   -d '{
     "project_path": "/path/to/test/project"
   }' | jq '.'
@@ -0,0 +1,2 @@
+-- Drop result_snapshots table
+DROP TABLE IF EXISTS result_snapshots;
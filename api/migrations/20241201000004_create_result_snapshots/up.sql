@@ -0,0 +1,20 @@
+-- Create result_snapshots table
+CREATE TABLE IF NOT EXISTS result_snapshots (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    analysis_key VARCHAR(255),
+    analysis_date TIMESTAMP,
+    quality_gate_status VARCHAR(20) NOT NULL,
+    quality_gate_conditions JSONB NOT NULL DEFAULT '[]',
+    issues_total INTEGER NOT NULL DEFAULT 0,
+    issues_by_severity JSONB NOT NULL DEFAULT '{}',
+    issues_by_type JSONB NOT NULL DEFAULT '{}',
+    measures JSONB NOT NULL DEFAULT '{}',
+    source VARCHAR(20) NOT NULL DEFAULT 'MANUAL',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, analysis_key)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_result_snapshots_project_id ON result_snapshots(project_id);
+CREATE INDEX IF NOT EXISTS idx_result_snapshots_created_at ON result_snapshots(created_at);
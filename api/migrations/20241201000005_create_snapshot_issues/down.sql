@@ -0,0 +1,2 @@
+-- Drop snapshot_issues table
+DROP TABLE IF EXISTS snapshot_issues;
@@ -0,0 +1,16 @@
+-- Create snapshot_issues table
+CREATE TABLE IF NOT EXISTS snapshot_issues (
+    id SERIAL PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL REFERENCES result_snapshots(id) ON DELETE CASCADE,
+    issue_key VARCHAR(255) NOT NULL,
+    hash VARCHAR(255),
+    rule VARCHAR(255) NOT NULL,
+    severity VARCHAR(20) NOT NULL,
+    issue_type VARCHAR(30) NOT NULL,
+    component VARCHAR(1000) NOT NULL,
+    line INTEGER,
+    message TEXT NOT NULL
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_snapshot_issues_snapshot_id ON snapshot_issues(snapshot_id);
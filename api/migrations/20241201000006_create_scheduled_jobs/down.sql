@@ -0,0 +1,2 @@
+-- Drop scheduled_jobs table
+DROP TABLE IF EXISTS scheduled_jobs;
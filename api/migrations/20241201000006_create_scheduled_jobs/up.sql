@@ -0,0 +1,25 @@
+-- Create scheduled_jobs table
+CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    job_type VARCHAR(50) NOT NULL,
+    cron_expression VARCHAR(100) NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMP,
+    last_run_at TIMESTAMP,
+    last_status VARCHAR(20),
+    last_message TEXT,
+    last_result JSONB,
+    last_duration_ms BIGINT,
+    locked_by VARCHAR(255),
+    locked_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Seed default jobs (cron expressions include seconds)
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('refresh-results', 'REFRESH_RESULTS', '0 0 * * * *'),
+    ('check-tokens', 'CHECK_TOKENS', '0 30 6 * * *'),
+    ('reconcile-projects', 'RECONCILE_PROJECTS', '0 0 7 * * *')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,2 @@
+-- Drop webhook_events table
+DROP TABLE IF EXISTS webhook_events;
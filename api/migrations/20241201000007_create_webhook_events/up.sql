@@ -0,0 +1,18 @@
+-- Create webhook_events table
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL,
+    project_key VARCHAR(255) NOT NULL,
+    task_id VARCHAR(255),
+    task_status VARCHAR(20) NOT NULL,
+    branch VARCHAR(255),
+    is_main_branch BOOLEAN NOT NULL DEFAULT TRUE,
+    quality_gate_status VARCHAR(20),
+    analysed_at TIMESTAMP,
+    payload JSONB NOT NULL,
+    received_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_webhook_events_project_key ON webhook_events(project_key);
+CREATE INDEX IF NOT EXISTS idx_webhook_events_received_at ON webhook_events(received_at);
@@ -0,0 +1,4 @@
+-- Drop notification tables
+DELETE FROM scheduled_jobs WHERE name = 'deliver-notifications';
+DROP TABLE IF EXISTS notification_deliveries;
+DROP TABLE IF EXISTS notification_channels;
@@ -0,0 +1,39 @@
+-- Create notification_channels table
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE,
+    channel_type VARCHAR(20) NOT NULL,
+    target TEXT NOT NULL,
+    template TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create notification_deliveries table
+CREATE TABLE IF NOT EXISTS notification_deliveries (
+    id SERIAL PRIMARY KEY,
+    channel_id INTEGER NOT NULL REFERENCES notification_channels(id) ON DELETE CASCADE,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    event_key VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING',
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    sent_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (channel_id, event_key)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_notification_channels_project_id ON notification_channels(project_id);
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_status ON notification_deliveries(status, next_attempt_at);
+
+-- Retry pending deliveries every minute
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('deliver-notifications', 'DELIVER_NOTIFICATIONS', '0 * * * * *')
+ON CONFLICT (name) DO NOTHING;
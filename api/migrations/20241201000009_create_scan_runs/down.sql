@@ -0,0 +1,2 @@
+-- Drop scan_runs table
+DROP TABLE IF EXISTS scan_runs;
@@ -0,0 +1,25 @@
+-- Create scan_runs table
+CREATE TABLE IF NOT EXISTS scan_runs (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'QUEUED',
+    command TEXT NOT NULL,
+    exit_code INTEGER,
+    error TEXT,
+    stdout TEXT NOT NULL DEFAULT '',
+    stderr TEXT NOT NULL DEFAULT '',
+    cancel_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    runner_id VARCHAR(255),
+    heartbeat_at TIMESTAMP,
+    queued_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP,
+    finished_at TIMESTAMP,
+    duration_ms BIGINT
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_scan_runs_project_id ON scan_runs(project_id);
+CREATE INDEX IF NOT EXISTS idx_scan_runs_status ON scan_runs(status);
+
+-- A project has at most one queued or running scan
+CREATE UNIQUE INDEX IF NOT EXISTS idx_scan_runs_active_project ON scan_runs(project_id) WHERE status IN ('QUEUED', 'RUNNING');
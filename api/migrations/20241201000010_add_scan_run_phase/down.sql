@@ -0,0 +1,3 @@
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS quality_gate_status;
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS ce_task_id;
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS phase;
@@ -0,0 +1,4 @@
+-- Track the phase of running scans and the Compute Engine task they submitted
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS phase VARCHAR(20);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS ce_task_id VARCHAR(64);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS quality_gate_status VARCHAR(20);
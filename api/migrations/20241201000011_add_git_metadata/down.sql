@@ -0,0 +1,6 @@
+ALTER TABLE result_snapshots DROP COLUMN IF EXISTS git_dirty;
+ALTER TABLE result_snapshots DROP COLUMN IF EXISTS git_commit;
+ALTER TABLE result_snapshots DROP COLUMN IF EXISTS git_branch;
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS git_dirty;
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS git_commit;
+ALTER TABLE scan_runs DROP COLUMN IF EXISTS git_branch;
@@ -0,0 +1,7 @@
+-- Record the git branch and commit that scans ran on and snapshots were analysed from
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_branch VARCHAR(255);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_commit VARCHAR(64);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_dirty BOOLEAN;
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_branch VARCHAR(255);
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_commit VARCHAR(64);
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_dirty BOOLEAN;
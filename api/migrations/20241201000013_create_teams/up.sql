@@ -0,0 +1,31 @@
+-- Role of a user on every project: VIEWER, DEVELOPER, MAINTAINER or ADMIN, or NULL for team access only.
+-- Users created before roles existed could do everything, so they start as ADMIN.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS global_role VARCHAR(20) DEFAULT 'ADMIN';
+ALTER TABLE users ALTER COLUMN global_role DROP DEFAULT;
+
+-- Create teams table
+CREATE TABLE IF NOT EXISTS teams (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create team_members table
+CREATE TABLE IF NOT EXISTS team_members (
+    id SERIAL PRIMARY KEY,
+    team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (team_id, user_id)
+);
+
+-- Owning team of a project; projects without a team are only visible to users with a global role
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS team_id INTEGER REFERENCES teams(id) ON DELETE SET NULL;
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_team_members_user_id ON team_members(user_id);
+CREATE INDEX IF NOT EXISTS idx_projects_team_id ON projects(team_id);
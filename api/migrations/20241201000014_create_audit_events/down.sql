@@ -0,0 +1,3 @@
+-- Drop audit_events table
+DELETE FROM scheduled_jobs WHERE name = 'purge-audit-events';
+DROP TABLE IF EXISTS audit_events;
@@ -0,0 +1,30 @@
+-- Create audit_events table
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    request_id VARCHAR(64) NOT NULL,
+    actor_user_id INTEGER,
+    actor_username VARCHAR(255),
+    auth_method VARCHAR(20),
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id VARCHAR(255),
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(1000) NOT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    status_code INTEGER NOT NULL,
+    error TEXT,
+    before JSONB,
+    after JSONB,
+    client_ip VARCHAR(100),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_audit_events_created_at ON audit_events(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor_user_id ON audit_events(actor_user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_events_target ON audit_events(target_type, target_id);
+
+-- Drop events older than AUDIT_RETENTION_DAYS once a day
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('purge-audit-events', 'PURGE_AUDIT_EVENTS', '0 15 3 * * *')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,71 @@
+use actix_web::{web, HttpResponse, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use crate::auth::permissions::{Access, Permission};
+use crate::database::audit_service::{AuditEventFilter, AuditService};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEventsQuery {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub outcome: Option<String>,
+    pub request_id: Option<String>,
+    pub since: Option<String>, // "YYYY-MM-DD" or "YYYY-MM-DDTHH:MM:SS", UTC
+    pub until: Option<String>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Parses a `since`/`until` bound given as a date or a UTC date and time.
+fn parse_time(name: &str, value: Option<&str>) -> std::result::Result<Option<NaiveDateTime>, HttpResponse> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+        .map(Some)
+        .ok_or_else(|| {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Invalid {} time {}", name, value),
+                "suggestion": "Use YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS in UTC"
+            }))
+        })
+}
+
+pub async fn get_audit_events(
+    query: web::Query<AuditEventsQuery>,
+    access: Access,
+    audit_service: web::Data<AuditService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let query = query.into_inner();
+    let (since, until) = match (parse_time("since", query.since.as_deref()), parse_time("until", query.until.as_deref())) {
+        (Ok(since), Ok(until)) => (since, until),
+        (Err(response), _) | (_, Err(response)) => return Ok(response),
+    };
+
+    let filter = AuditEventFilter {
+        actor: query.actor,
+        action: query.action,
+        target_type: query.target_type,
+        target_id: query.target_id,
+        outcome: query.outcome,
+        request_id: query.request_id,
+        since,
+        until,
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+
+    match audit_service.get_events(&filter, limit, query.offset.unwrap_or(0)).await {
+        Ok(events) => Ok(HttpResponse::Ok().json(events)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
@@ -0,0 +1,300 @@
+pub mod handlers;
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest};
+use crate::auth::{self, AuthUser};
+use crate::database::audit_service::{AuditService, NewAuditEvent};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use tracing::error;
+
+/// Header carrying the request id. An id sent by a proxy is kept, otherwise one is generated.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Fields whose values never reach the audit log, wherever they appear. Names ending in
+/// `_password` or `_secret` are redacted too.
+const REDACTED_FIELDS: &[&str] = &["password", "token", "token_value", "sonar_token", "api_key", "key_hash", "token_hash", "secret"];
+
+/// Top-level fields that are only secret in the payloads of some actions: the API key, shown once
+/// on creation, and notification channel targets, which may embed webhook secrets. Elsewhere
+/// `key` and `target` name projects, issues and the like.
+const REDACTED_PATHS: &[(&str, &str)] = &[
+    ("api_key.create", "key"),
+    ("notification_channel.create", "target"),
+    ("notification_channel.update", "target"),
+    ("notification_channel.delete", "target"),
+];
+
+const REDACTED: &str = "[REDACTED]";
+
+struct AuditedRoute {
+    method: &'static str,
+    pattern: &'static str,
+    action: &'static str,
+    target_type: &'static str,
+    /// Path parameter identifying the target, if the path has one
+    target_param: Option<&'static str>,
+}
+
+const fn route(method: &'static str, pattern: &'static str, action: &'static str, target_type: &'static str, target_param: Option<&'static str>) -> AuditedRoute {
+    AuditedRoute { method, pattern, action, target_type, target_param }
+}
+
+/// Administrative actions recorded in the audit log.
+const AUDITED_ROUTES: &[AuditedRoute] = &[
+    route("POST", "/api/admin-token", "admin_token.create", "admin_token", None),
+    route("POST", "/api/projects", "project.create", "project", None),
+    route("DELETE", "/api/projects", "project.delete", "project", None),
+    route("PUT", "/api/projects/{id}/team", "project.set_team", "project", Some("id")),
+    route("POST", "/api/projects/{id}/issues/{key}/transition", "issue.transition", "issue", Some("key")),
+    route("POST", "/api/quality-gates", "quality_gate.create", "quality_gate", None),
+    route("PUT", "/api/quality-gates", "quality_gate.update", "quality_gate", None),
+    route("DELETE", "/api/quality-gates", "quality_gate.delete", "quality_gate", None),
+    route("POST", "/api/quality-gates/set-default", "quality_gate.set_default", "quality_gate", None),
+    route("POST", "/api/quality-gates/assign", "quality_gate.assign", "project", None),
+    route("POST", "/api/notification-channels", "notification_channel.create", "notification_channel", None),
+    route("PUT", "/api/notification-channels/{id}", "notification_channel.update", "notification_channel", Some("id")),
+    route("DELETE", "/api/notification-channels/{id}", "notification_channel.delete", "notification_channel", Some("id")),
+    route("PUT", "/api/jobs/{name}", "job.update", "job", Some("name")),
+    route("POST", "/api/jobs/{name}/run", "job.run", "job", Some("name")),
+    route("POST", "/api/users", "user.create", "user", None),
+    route("PUT", "/api/users/{id}", "user.update", "user", Some("id")),
+    route("DELETE", "/api/users/{id}", "user.delete", "user", Some("id")),
+    route("PUT", "/api/users/{id}/role", "user.set_role", "user", Some("id")),
+    route("POST", "/api/teams", "team.create", "team", None),
+    route("PUT", "/api/teams/{id}", "team.update", "team", Some("id")),
+    route("DELETE", "/api/teams/{id}", "team.delete", "team", Some("id")),
+    route("PUT", "/api/teams/{id}/members/{user_id}", "team.set_member", "team", Some("id")),
+    route("DELETE", "/api/teams/{id}/members/{user_id}", "team.remove_member", "team", Some("id")),
+    route("POST", "/api/api-keys", "api_key.create", "api_key", None),
+    route("DELETE", "/api/api-keys/{id}", "api_key.revoke", "api_key", Some("id")),
+    route("PUT", "/api/log-level", "log_level.update", "log_level", None),
+];
+
+#[derive(Debug, Default)]
+struct AuditDetails {
+    target_id: Option<String>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+/// The audit event of the current request, for handlers to add what the middleware cannot see:
+/// targets named in the body and the state before the change. Without a recorded `after`,
+/// the response of a successful request is used.
+#[derive(Debug, Clone, Default)]
+pub struct Audit(Rc<RefCell<AuditDetails>>);
+
+impl Audit {
+    pub fn target(&self, id: impl ToString) {
+        self.0.borrow_mut().target_id = Some(id.to_string());
+    }
+
+    pub fn before(&self, value: &impl Serialize) {
+        self.0.borrow_mut().before = serde_json::to_value(value).ok();
+    }
+
+    pub fn after(&self, value: &impl Serialize) {
+        self.0.borrow_mut().after = serde_json::to_value(value).ok();
+    }
+}
+
+impl FromRequest for Audit {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // Outside the middleware the details go nowhere, which keeps handlers usable on their own
+        ready(Ok(req.extensions().get::<Audit>().cloned().unwrap_or_default()))
+    }
+}
+
+/// Tags every response with a request id and records the administrative actions of
+/// [`AUDITED_ROUTES`] with their actor, outcome and payloads. Requests rejected for missing
+/// credentials have no actor and are not recorded.
+pub async fn record(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| auth::random_string(16));
+    let audit = Audit::default();
+    req.extensions_mut().insert(audit.clone());
+    let audit_service = req.app_data::<web::Data<AuditService>>().cloned();
+
+    let mut res = next.call(req).await?;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+
+    let request = res.request();
+    let route = request.match_pattern().and_then(|pattern| {
+        AUDITED_ROUTES.iter().find(|route| route.method == request.method().as_str() && route.pattern == pattern)
+    });
+    let (Some(route), Some(audit_service)) = (route, audit_service) else {
+        return Ok(res.map_into_boxed_body());
+    };
+    let status = res.status();
+    if status.as_u16() == 401 {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    // Administrative responses are small JSON documents, so they are buffered to read the outcome
+    let (request, response) = res.into_parts();
+    let (response, response_body) = response.into_parts();
+    let bytes = body::to_bytes(response_body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+    let response_json: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+
+    let outcome = if status.is_success() {
+        "SUCCESS"
+    } else if status.as_u16() == 403 {
+        "DENIED"
+    } else {
+        "FAILED"
+    };
+
+    let details = std::mem::take(&mut *audit.0.borrow_mut());
+    let target_id = details
+        .target_id
+        .or_else(|| route.target_param.and_then(|param| request.match_info().get(param)).map(str::to_string))
+        .or_else(|| {
+            // Created resources are identified by the id in the response
+            let id = response_json.as_ref().filter(|_| status.is_success())?.get("id")?;
+            Some(id.as_str().map(str::to_string).unwrap_or_else(|| id.to_string()))
+        });
+    let after = details.after.or_else(|| response_json.clone().filter(|_| status.is_success()));
+    let error = response_json
+        .as_ref()
+        .filter(|_| !status.is_success())
+        .and_then(|json| json.get("error"))
+        .and_then(|error| error.as_str())
+        .map(str::to_string);
+    let user = request.extensions().get::<AuthUser>().cloned();
+
+    let event = NewAuditEvent {
+        request_id,
+        actor_user_id: user.as_ref().map(|user| user.id),
+        actor_username: user.as_ref().map(|user| user.username.clone()),
+        auth_method: user.as_ref().map(|user| user.auth_method.to_string()),
+        action: route.action.to_string(),
+        target_type: route.target_type.to_string(),
+        target_id,
+        method: route.method.to_string(),
+        path: request.path().to_string(),
+        outcome: outcome.to_string(),
+        status_code: status.as_u16() as i32,
+        error,
+        before: details.before.map(|before| redact(before, route.action)),
+        after: after.map(|after| redact(after, route.action)),
+        client_ip: request.connection_info().realip_remote_addr().map(str::to_string),
+    };
+    if let Err(e) = audit_service.record(event).await {
+        error!("Failed to record audit event {} on {}: {}", route.action, request.path(), e);
+    }
+
+    let response = response.set_body(bytes).map_into_boxed_body();
+    Ok(ServiceResponse::new(request, response))
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Replaces tokens, passwords and secrets, and the fields [`REDACTED_PATHS`] lists for `action`.
+fn redact(mut value: serde_json::Value, action: &str) -> serde_json::Value {
+    if let Some(fields) = value.as_object_mut() {
+        for (_, name) in REDACTED_PATHS.iter().filter(|(redacted_action, _)| *redacted_action == action) {
+            if let Some(field) = fields.get_mut(*name).filter(|field| !field.is_null()) {
+                *field = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+    }
+    redact_in_place(&mut value);
+    value
+}
+
+fn redact_in_place(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let name = name.to_ascii_lowercase();
+                let secret = REDACTED_FIELDS.contains(&name.as_str()) || name.ends_with("_password") || name.ends_with("_secret");
+                if secret && !field.is_null() {
+                    *field = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_in_place(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_in_place),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_secrets_at_any_depth() {
+        let mut value = json!({
+            "project_key": "demo",
+            "sonar_token": "squ_123",
+            "admin": { "token_value": "squ_456", "token_name": "ci", "smtp_password": "hunter2" },
+            "keys": [{ "key_hash": "abc", "api_key": "sck_123", "name": "ci" }],
+            "oidc": { "client_secret": "s3cret" },
+        });
+
+        redact_in_place(&mut value);
+
+        assert_eq!(value, json!({
+            "project_key": "demo",
+            "sonar_token": REDACTED,
+            "admin": { "token_value": REDACTED, "token_name": "ci", "smtp_password": REDACTED },
+            "keys": [{ "key_hash": REDACTED, "api_key": REDACTED, "name": "ci" }],
+            "oidc": { "client_secret": REDACTED },
+        }));
+    }
+
+    #[test]
+    fn keeps_project_and_issue_keys() {
+        let mut value = json!({
+            "key": "AYx-issue",
+            "project": { "key": "demo", "name": "Demo" },
+            "target": "project:demo",
+        });
+        let expected = value.clone();
+
+        redact_in_place(&mut value);
+
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn leaves_unset_secrets_null() {
+        let mut value = json!({ "password": null, "sonar_token": "" });
+        redact_in_place(&mut value);
+        assert_eq!(value, json!({ "password": null, "sonar_token": REDACTED }));
+    }
+
+    #[test]
+    fn redacts_action_specific_fields() {
+        let key = json!({ "id": 3, "key": "sck_secret", "key_prefix": "sck_abcdefgh" });
+        assert_eq!(redact(key.clone(), "api_key.create"), json!({ "id": 3, "key": REDACTED, "key_prefix": "sck_abcdefgh" }));
+        assert_eq!(redact(key, "issue.transition")["key"], "sck_secret");
+
+        let channel = json!({ "id": 1, "channel_type": "SLACK", "target": "https://hooks.slack.com/services/T0/B0/secret" });
+        assert_eq!(redact(channel.clone(), "notification_channel.update")["target"], REDACTED);
+        assert_eq!(redact(channel, "team.update")["target"], "https://hooks.slack.com/services/T0/B0/secret");
+    }
+}
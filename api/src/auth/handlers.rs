@@ -0,0 +1,661 @@
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use chrono::{Duration, NaiveDateTime, Utc};
+use crate::audit::Audit;
+use crate::auth::permissions::{Access, Permission, Role};
+use crate::auth::{self, AuthConfig, AuthUser};
+use crate::database::auth_service::{
+    AuthService, CreateApiKeyRequest, CreateUserRequest, NewUser, SetUserRoleRequest, UpdateUserRequest, UserChanges, UserResponse,
+};
+use crate::database::team_service::TeamService;
+use crate::database::user_entity::Model as UserModel;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+const OIDC_STATE_COOKIE: &str = "sonarcute_oidc";
+const OIDC_STATE_AUDIENCE: &str = "oidc-state";
+/// Time allowed between starting an OIDC sign-in and returning from the provider
+const OIDC_STATE_TTL_SECS: i64 = 600;
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginResponse {
+    pub token: String,
+    pub token_type: &'static str,
+    pub expires_at: NaiveDateTime,
+    pub user: UserResponse,
+}
+
+/// What the browser carries through the provider round trip, signed so it cannot be altered.
+#[derive(Debug, Serialize, Deserialize)]
+struct OidcState {
+    state: String,
+    nonce: String,
+    verifier: String,
+    iss: String,
+    aud: String,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Starts a session for `user`. Returns the response body for the sign-in.
+async fn start_session(config: &AuthConfig, auth_service: &AuthService, user: UserModel, auth_provider: &str) -> Result<LoginResponse, String> {
+    let expires_at = Utc::now().naive_utc() + config.session_ttl;
+    let session = auth_service
+        .create_session(user.id, auth_provider, expires_at)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    let token = config
+        .session_token(user.id, session.id, expires_at)
+        .map_err(|e| format!("Failed to sign session token: {}", e))?;
+    if let Err(e) = auth_service.record_login(user.id).await {
+        warn!("Failed to record sign-in of {}: {}", user.username, e);
+    }
+
+    Ok(LoginResponse {
+        token,
+        token_type: "Bearer",
+        expires_at,
+        user: UserResponse::from(user),
+    })
+}
+
+pub async fn get_auth_providers(config: web::Data<AuthConfig>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "enabled": config.enabled,
+        "local": true,
+        "oidc": config.oidc.as_ref().map(|oidc| serde_json::json!({
+            "name": oidc.name,
+            "login_url": "/api/auth/oidc/login"
+        }))
+    })))
+}
+
+pub async fn login(
+    request: web::Json<LoginRequest>,
+    config: web::Data<AuthConfig>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let request = request.into_inner();
+
+    let user = match auth_service.get_user_by_username(&request.username).await {
+        Ok(user) => user,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    // Argon2 is deliberately slow; unknown users are checked against a dummy hash all the same
+    let password_hash = user.as_ref().and_then(|u| u.password_hash.clone());
+    let valid = web::block(move || auth::verify_password(&request.password, password_hash.as_deref()))
+        .await
+        .unwrap_or(false);
+
+    let user = match user {
+        Some(user) if valid && user.active => user,
+        _ => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Invalid username or password"
+            })));
+        }
+    };
+
+    match start_session(&config, &auth_service, user, "LOCAL").await {
+        Ok(response) => Ok(HttpResponse::Ok().json(response)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e }))),
+    }
+}
+
+pub async fn logout(user: AuthUser, auth_service: web::Data<AuthService>) -> Result<HttpResponse> {
+    let Some(session_id) = user.session_id else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "API keys have no session to end",
+            "suggestion": "Revoke the key with DELETE /api/api-keys/{id} instead"
+        })));
+    };
+
+    match auth_service.revoke_session(session_id).await {
+        Ok(()) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_current_user(
+    user: AuthUser,
+    auth_service: web::Data<AuthService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let roles = match team_service.get_user_roles(user.id).await {
+        Ok(roles) => roles,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+    let teams = match team_service.get_teams_by_ids(roles.keys().copied().collect()).await {
+        Ok(teams) => teams,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+    let teams: Vec<serde_json::Value> = teams
+        .into_iter()
+        .map(|team| serde_json::json!({
+            "id": team.id,
+            "name": team.name,
+            "role": roles.get(&team.id).map(Role::as_str)
+        }))
+        .collect();
+
+    match auth_service.get_user_by_id(user.id).await {
+        Ok(Some(model)) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "user": UserResponse::from(model),
+            "auth_method": user.auth_method,
+            "teams": teams
+        }))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn start_oidc_login(config: web::Data<AuthConfig>) -> Result<HttpResponse> {
+    let Some(oidc) = config.oidc.clone() else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "OIDC sign-in is not configured",
+            "suggestion": "Set OIDC_ISSUER_URL, OIDC_CLIENT_ID and OIDC_REDIRECT_URL"
+        })));
+    };
+
+    let state = OidcState {
+        state: auth::random_string(32),
+        nonce: auth::random_string(32),
+        verifier: auth::random_string(64),
+        iss: auth::TOKEN_ISSUER.to_string(),
+        aud: OIDC_STATE_AUDIENCE.to_string(),
+        exp: (Utc::now() + Duration::seconds(OIDC_STATE_TTL_SECS)).timestamp(),
+    };
+
+    let (url, cookie_value) = match (oidc.authorization_url(&state.state, &state.nonce, &state.verifier).await, config.sign(&state)) {
+        (Ok(url), Ok(cookie_value)) => (url, cookie_value),
+        (Err(e), _) | (_, Err(e)) => {
+            return Ok(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to start OIDC sign-in: {}", e)
+            })));
+        }
+    };
+
+    let cookie = Cookie::build(OIDC_STATE_COOKIE, cookie_value)
+        .path("/api/auth/oidc")
+        .http_only(true)
+        .secure(oidc.secure_callback())
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(OIDC_STATE_TTL_SECS))
+        .finish();
+
+    Ok(HttpResponse::Found()
+        .insert_header((header::LOCATION, url))
+        .cookie(cookie)
+        .finish())
+}
+
+/// Completes an OIDC sign-in and sends the browser to `OIDC_POST_LOGIN_URL` with the session
+/// token, or the error, in the URL fragment, which browsers never send to servers.
+pub async fn complete_oidc_login(
+    req: HttpRequest,
+    query: web::Query<OidcCallbackQuery>,
+    config: web::Data<AuthConfig>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let Some(oidc) = config.oidc.clone() else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "OIDC sign-in is not configured"
+        })));
+    };
+    let query = query.into_inner();
+
+    let redirect = |fragment: String| {
+        let mut removal = Cookie::build(OIDC_STATE_COOKIE, "").path("/api/auth/oidc").finish();
+        removal.make_removal();
+        HttpResponse::Found()
+            .insert_header((header::LOCATION, format!("{}#{}", oidc.post_login_url, fragment)))
+            .cookie(removal)
+            .finish()
+    };
+    let fail = |message: String| {
+        warn!("OIDC sign-in failed: {}", message);
+        redirect(format!("error={}", urlencoding::encode(&message)))
+    };
+
+    if let Some(error) = query.error {
+        return Ok(fail(query.error_description.unwrap_or(error)));
+    }
+
+    let stored: Option<OidcState> = req
+        .cookie(OIDC_STATE_COOKIE)
+        .and_then(|cookie| config.verify(cookie.value(), OIDC_STATE_AUDIENCE).ok());
+    let (Some(stored), Some(code)) = (stored, query.code) else {
+        return Ok(fail("The sign-in request expired or was started in another browser".to_string()));
+    };
+    if query.state.as_deref() != Some(stored.state.as_str()) {
+        return Ok(fail("The sign-in response does not match the request".to_string()));
+    }
+
+    let identity = match oidc.exchange_code(&code, &stored.verifier, &stored.nonce).await {
+        Ok(identity) => identity,
+        Err(e) => return Ok(fail(e.to_string())),
+    };
+
+    let user = match auth_service.get_user_by_external_id("OIDC", &identity.subject).await {
+        Ok(Some(user)) => user,
+        Ok(None) if oidc.auto_create_users => {
+            let Some(username) = identity.username else {
+                return Ok(fail("The identity provider returned no username or email".to_string()));
+            };
+            // Local accounts are never taken over by a provider account with the same name
+            match auth_service.get_user_by_username(&username).await {
+                Ok(Some(_)) => return Ok(fail(format!("User {} already exists", username))),
+                Ok(None) => {}
+                Err(e) => return Ok(fail(format!("Database error: {}", e))),
+            }
+
+            let new_user = NewUser {
+                username,
+                display_name: identity.display_name,
+                email: identity.email,
+                password_hash: None,
+                auth_provider: "OIDC".to_string(),
+                external_id: Some(identity.subject),
+                global_role: oidc.default_role.map(|role| role.as_str().to_string()),
+            };
+            match auth_service.create_user(new_user).await {
+                Ok(user) => {
+                    info!("Created user {} from {}", user.username, oidc.name);
+                    user
+                }
+                Err(e) => return Ok(fail(format!("Database error: {}", e))),
+            }
+        }
+        Ok(None) => return Ok(fail("No SonarCute user is linked to this account".to_string())),
+        Err(e) => return Ok(fail(format!("Database error: {}", e))),
+    };
+
+    if !user.active {
+        return Ok(fail("User is disabled".to_string()));
+    }
+
+    match start_session(&config, &auth_service, user, "OIDC").await {
+        Ok(response) => Ok(redirect(format!(
+            "token={}&expires_at={}",
+            response.token,
+            response.expires_at.and_utc().timestamp()
+        ))),
+        Err(e) => Ok(fail(e)),
+    }
+}
+
+pub async fn get_users(access: Access, auth_service: web::Data<AuthService>) -> Result<HttpResponse> {
+    // Team admins list users to add them to their teams
+    if let Err(response) = access.require_any(Permission::ManageTeams) {
+        return Ok(response);
+    }
+
+    match auth_service.get_users().await {
+        Ok(users) => Ok(HttpResponse::Ok().json(users)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn create_user(
+    request: web::Json<CreateUserRequest>,
+    access: Access,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+    let request = request.into_inner();
+
+    let global_role = match request.global_role.as_deref().map(parse_role).transpose() {
+        Ok(role) => role,
+        Err(response) => return Ok(response),
+    };
+
+    let username = request.username.trim().to_string();
+    if username.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Username must not be empty"
+        })));
+    }
+    if request.password.len() < auth::MIN_PASSWORD_LENGTH {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Password must have at least {} characters", auth::MIN_PASSWORD_LENGTH)
+        })));
+    }
+
+    match auth_service.get_user_by_username(&username).await {
+        Ok(Some(_)) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("User {} already exists", username)
+            })));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    }
+
+    let password = request.password;
+    let password_hash = match web::block(move || auth::hash_password(&password)).await {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(e)) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e.to_string() })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e.to_string() })));
+        }
+    };
+
+    let user = NewUser {
+        username,
+        display_name: request.display_name,
+        email: request.email,
+        password_hash: Some(password_hash),
+        auth_provider: "LOCAL".to_string(),
+        external_id: None,
+        global_role: global_role.map(|role| role.as_str().to_string()),
+    };
+    match auth_service.create_user(user).await {
+        Ok(user) => Ok(HttpResponse::Created().json(UserResponse::from(user))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn update_user(
+    path: web::Path<i32>,
+    request: web::Json<UpdateUserRequest>,
+    user: AuthUser,
+    access: Access,
+    audit: Audit,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let user_id = path.into_inner();
+    let request = request.into_inner();
+
+    // Everyone may change their own name, email and password
+    if user_id != user.id
+        && let Err(response) = access.require_global(Permission::Administer)
+    {
+        return Ok(response);
+    }
+
+    let existing = match auth_service.get_user_by_id(user_id).await {
+        Ok(Some(existing)) => existing,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+    audit.before(&UserResponse::from(existing.clone()));
+
+    if request.active == Some(false) && user_id == user.id {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "You cannot disable your own user"
+        })));
+    }
+
+    // A stolen session or API key must not be enough to take over the account
+    if request.password.is_some() && user_id == user.id && existing.auth_provider == "LOCAL" {
+        let Some(current_password) = request.current_password else {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "current_password is required to change your own password"
+            })));
+        };
+        let password_hash = existing.password_hash.clone();
+        match web::block(move || auth::verify_password(&current_password, password_hash.as_deref())).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "current_password is incorrect"
+                })));
+            }
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e.to_string() })));
+            }
+        }
+    }
+
+    let password_hash = match request.password {
+        Some(_) if existing.auth_provider != "LOCAL" => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Users signing in through OIDC have no password",
+                "suggestion": "Change the password at the identity provider"
+            })));
+        }
+        Some(password) if password.len() < auth::MIN_PASSWORD_LENGTH => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Password must have at least {} characters", auth::MIN_PASSWORD_LENGTH)
+            })));
+        }
+        Some(password) => match web::block(move || auth::hash_password(&password)).await {
+            Ok(Ok(hash)) => Some(hash),
+            Ok(Err(e)) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e.to_string() })));
+            }
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({ "error": e.to_string() })));
+            }
+        },
+        None => None,
+    };
+
+    let changes = UserChanges {
+        display_name: request.display_name,
+        email: request.email,
+        password_hash,
+        active: request.active,
+        keep_session_id: user.session_id.filter(|_| user_id == user.id),
+        keep_api_key_id: user.api_key_id.filter(|_| user_id == user.id),
+    };
+    match auth_service.update_user(user_id, changes).await {
+        Ok(Some(user)) => Ok(HttpResponse::Ok().json(UserResponse::from(user))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn delete_user(
+    path: web::Path<i32>,
+    user: AuthUser,
+    access: Access,
+    audit: Audit,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let user_id = path.into_inner();
+    if user_id == user.id {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "You cannot delete your own user"
+        })));
+    }
+    if let Ok(Some(existing)) = auth_service.get_user_by_id(user_id).await {
+        audit.before(&UserResponse::from(existing));
+    }
+
+    match auth_service.delete_user(user_id).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn set_user_role(
+    path: web::Path<i32>,
+    request: web::Json<SetUserRoleRequest>,
+    user: AuthUser,
+    access: Access,
+    audit: Audit,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let user_id = path.into_inner();
+    // Keeps the last administrator from locking everyone out
+    if user_id == user.id {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "You cannot change your own role"
+        })));
+    }
+
+    let global_role = match request.into_inner().global_role.as_deref().map(parse_role).transpose() {
+        Ok(role) => role,
+        Err(response) => return Ok(response),
+    };
+    if let Ok(Some(existing)) = auth_service.get_user_by_id(user_id).await {
+        audit.before(&UserResponse::from(existing));
+    }
+
+    match auth_service.set_global_role(user_id, global_role.map(|role| role.as_str().to_string())).await {
+        Ok(Some(user)) => Ok(HttpResponse::Ok().json(UserResponse::from(user))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+/// Parses a role from a request body.
+pub fn parse_role(value: &str) -> std::result::Result<Role, HttpResponse> {
+    Role::parse(value).ok_or_else(|| {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unknown role {}", value),
+            "suggestion": format!("Use one of {}", Role::NAMES.join(", "))
+        }))
+    })
+}
+
+pub async fn get_api_keys(user: AuthUser, auth_service: web::Data<AuthService>) -> Result<HttpResponse> {
+    match auth_service.get_api_keys(user.id).await {
+        Ok(keys) => Ok(HttpResponse::Ok().json(keys)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn create_api_key(
+    request: web::Json<CreateApiKeyRequest>,
+    user: AuthUser,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    // A leaked key must not be able to mint replacements for itself
+    if user.api_key_id.is_some() {
+        return Ok(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": "API keys cannot create API keys",
+            "suggestion": "Sign in with POST /api/auth/login and use the session token"
+        })));
+    }
+
+    let request = request.into_inner();
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Name must not be empty"
+        })));
+    }
+    let expires_at = match request.expires_in_days {
+        Some(days) if days <= 0 => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "expires_in_days must be positive"
+            })));
+        }
+        Some(days) => match Duration::try_days(days).and_then(|duration| Utc::now().naive_utc().checked_add_signed(duration)) {
+            Some(expires_at) => Some(expires_at),
+            None => {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "error": "expires_in_days is too large",
+                    "suggestion": "Omit expires_in_days for a key that stays valid until revoked"
+                })));
+            }
+        },
+        None => None,
+    };
+
+    let (key, prefix, hash) = auth::generate_api_key();
+    match auth_service.create_api_key(user.id, name, prefix, hash, expires_at).await {
+        Ok(api_key) => {
+            let mut response = serde_json::to_value(api_key).unwrap_or_default();
+            response["key"] = serde_json::Value::String(key);
+            Ok(HttpResponse::Created().json(response))
+        }
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn revoke_api_key(
+    path: web::Path<i32>,
+    user: AuthUser,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    match auth_service.revoke_api_key(user.id, path.into_inner()).await {
+        Ok(Some(api_key)) => Ok(HttpResponse::Ok().json(api_key)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "API key not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
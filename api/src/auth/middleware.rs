@@ -0,0 +1,103 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use crate::auth::{self, AuthConfig, AuthError};
+use crate::database::auth_service::AuthService;
+use std::collections::HashMap;
+use tracing::error;
+
+/// Routes reachable without credentials. SonarQube cannot send a bearer token; its webhook
+/// deliveries are checked against `SONAR_WEBHOOK_SECRET` instead.
+const PUBLIC_ROUTES: &[&str] = &[
+    "/api/health",
+    "/api/auth/providers",
+    "/api/auth/login",
+    "/api/auth/oidc/login",
+    "/api/auth/oidc/callback",
+    "/api/webhooks/sonarqube",
+];
+
+/// Requires a valid `Authorization: Bearer` session token or API key on every other route, or a
+/// scan events token on the scan event stream,
+/// and makes the caller available to handlers as [`auth::AuthUser`].
+pub async fn require_auth(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let (Some(config), Some(auth_service)) = (req.app_data::<web::Data<AuthConfig>>().cloned(), req.app_data::<web::Data<AuthService>>().cloned()) else {
+        error!("Authentication middleware used without AuthConfig and AuthService");
+        let response = HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": "Authentication is not configured"
+        }));
+        return Ok(req.into_response(response).map_into_right_body());
+    };
+
+    if !config.enabled || PUBLIC_ROUTES.contains(&req.path()) {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    }
+
+    let bearer = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim().to_string());
+
+    let authenticated = match (bearer, scan_events_token(&req)) {
+        (Some(token), _) => auth::authenticate(&config, &auth_service, &token).await,
+        (None, Some((run_id, token))) => auth::authenticate_scan_events(&config, &auth_service, &token, run_id).await,
+        (None, None) => return Ok(unauthorized(req, "Authentication required")),
+    };
+
+    match authenticated {
+        Ok(user) => {
+            req.extensions_mut().insert(user);
+            next.call(req).await.map(ServiceResponse::map_into_left_body)
+        }
+        Err(AuthError::Rejected(reason)) => Ok(unauthorized(req, reason)),
+        Err(AuthError::Database(e)) => {
+            let response = HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }));
+            Ok(req.into_response(response).map_into_right_body())
+        }
+    }
+}
+
+/// The run and `token` query parameter of a `GET /api/scans/{id}/events` request. Browsers'
+/// `EventSource` cannot send headers, so the stream also accepts a token from
+/// `POST /api/scans/{id}/events/token`, which opens that one stream only.
+fn scan_events_token(req: &ServiceRequest) -> Option<(i32, String)> {
+    let run_id = req.path().strip_prefix("/api/scans/")?.strip_suffix("/events")?.parse().ok()?;
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string()).ok()?;
+    Some((run_id, query.get("token")?.clone()))
+}
+
+fn unauthorized<B>(req: ServiceRequest, reason: &str) -> ServiceResponse<actix_web::body::EitherBody<B>> {
+    let response = HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Bearer"))
+        .json(serde_json::json!({
+            "error": reason,
+            "suggestion": "Send a session token from POST /api/auth/login or an API key as Authorization: Bearer <token>"
+        }));
+    req.into_response(response).map_into_right_body()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn reads_the_token_of_scan_event_streams() {
+        let req = TestRequest::with_uri("/api/scans/12/events?last_event_id=4&token=abc").to_srv_request();
+        assert_eq!(scan_events_token(&req), Some((12, "abc".to_string())));
+    }
+
+    #[test]
+    fn ignores_query_tokens_elsewhere() {
+        for uri in ["/api/scans/12?token=abc", "/api/scans/12/events/token?token=abc", "/api/projects/12/events?token=abc", "/api/scans/12/events"] {
+            assert_eq!(scan_events_token(&TestRequest::with_uri(uri).to_srv_request()), None, "{}", uri);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+pub mod handlers;
+pub mod middleware;
+pub mod oidc;
+pub mod permissions;
+pub mod teams;
+
+use crate::database::api_key_entity::Model as ApiKeyModel;
+use crate::config::{AppConfig, AuthSettings};
+use crate::database::auth_service::{AuthService, NewUser};
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use anyhow::{anyhow, Result};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{Duration, NaiveDateTime, Utc};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sea_orm::DbErr;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::future::{ready, Ready};
+use std::sync::{Arc, OnceLock};
+use tracing::{info, warn};
+
+/// API keys start with this, which tells them apart from session tokens.
+pub const API_KEY_PREFIX: &str = "sck_";
+/// Random characters after the prefix, about 238 bits.
+const API_KEY_LENGTH: usize = 40;
+/// Characters of a key stored in clear to identify it in listings.
+const API_KEY_DISPLAY_LENGTH: usize = 12;
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+pub const TOKEN_ISSUER: &str = "sonarcute";
+const SESSION_AUDIENCE: &str = "session";
+const SCAN_EVENTS_AUDIENCE: &str = "scan-events";
+/// Lifetime of a scan events token. It only has to outlive opening the stream and its reconnects.
+const SCAN_EVENTS_TOKEN_TTL_SECS: i64 = 300;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionClaims {
+    sub: String,
+    sid: i32,
+    iss: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Opens the event stream of one scan run for the session or API key it was issued to.
+#[derive(Debug, Serialize, Deserialize)]
+struct ScanEventsClaims {
+    sub: String,
+    scan: i32,
+    sid: Option<i32>,
+    key: Option<i32>,
+    iss: String,
+    aud: String,
+    exp: i64,
+}
+
+/// Authentication settings, shared by the middleware and the auth endpoints.
+#[derive(Clone)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    pub session_ttl: Duration,
+    pub oidc: Option<Arc<oidc::OidcProvider>>,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+impl AuthConfig {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let enabled = config.auth.enabled;
+        if !enabled {
+            warn!("Authentication is disabled (AUTH_ENABLED=false); every API route is open");
+        }
+
+        let secret = match &config.auth.jwt_secret {
+            Some(secret) => secret.clone(),
+            None => {
+                warn!("AUTH_JWT_SECRET is not set; sessions end when the server restarts and are not shared between replicas");
+                random_string(64)
+            }
+        };
+
+        Self {
+            enabled,
+            session_ttl: Duration::seconds(config.auth.session_ttl_secs),
+            oidc: oidc::OidcProvider::from_config(&config.oidc).map(Arc::new),
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+
+    /// Signs `claims` with the server secret. `audience` keeps tokens made for one purpose from
+    /// being accepted for another.
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String> {
+        Ok(jsonwebtoken::encode(&Header::new(Algorithm::HS256), claims, &self.encoding_key)?)
+    }
+
+    pub fn verify<T: DeserializeOwned>(&self, token: &str, audience: &str) -> Result<T> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[TOKEN_ISSUER]);
+        validation.set_audience(&[audience]);
+        Ok(jsonwebtoken::decode::<T>(token, &self.decoding_key, &validation)?.claims)
+    }
+
+    /// The bearer token of a session.
+    pub fn session_token(&self, user_id: i32, session_id: i32, expires_at: NaiveDateTime) -> Result<String> {
+        self.sign(&SessionClaims {
+            sub: user_id.to_string(),
+            sid: session_id,
+            iss: TOKEN_ISSUER.to_string(),
+            aud: SESSION_AUDIENCE.to_string(),
+            iat: Utc::now().timestamp(),
+            exp: expires_at.and_utc().timestamp(),
+        })
+    }
+
+    /// A token for `GET /api/scans/{id}/events?token=`, since `EventSource` cannot send an
+    /// Authorization header. It is only valid for `run_id` and while `user`'s credentials are.
+    pub fn scan_events_token(&self, user: &AuthUser, run_id: i32) -> Result<(String, NaiveDateTime)> {
+        let expires_at = Utc::now().naive_utc() + Duration::seconds(SCAN_EVENTS_TOKEN_TTL_SECS);
+        let token = self.sign(&ScanEventsClaims {
+            sub: user.id.to_string(),
+            scan: run_id,
+            sid: user.session_id,
+            key: user.api_key_id,
+            iss: TOKEN_ISSUER.to_string(),
+            aud: SCAN_EVENTS_AUDIENCE.to_string(),
+            exp: expires_at.and_utc().timestamp(),
+        })?;
+        Ok((token, expires_at))
+    }
+}
+
+/// The caller of a request, set by the auth middleware. Handlers take it as an argument to
+/// require an authenticated caller.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthUser {
+    pub id: i32,
+    pub username: String,
+    pub auth_method: &'static str, // "SESSION" or "API_KEY"
+    #[serde(skip)]
+    pub global_role: Option<permissions::Role>,
+    #[serde(skip)]
+    pub session_id: Option<i32>,
+    #[serde(skip)]
+    pub api_key_id: Option<i32>,
+}
+
+impl FromRequest for AuthUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<AuthUser>().cloned().ok_or_else(|| {
+            let response = HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "This endpoint needs a signed in user",
+                "suggestion": "Sign in, or enable authentication with AUTH_ENABLED=true"
+            }));
+            InternalError::from_response("Unauthenticated", response).into()
+        }))
+    }
+}
+
+pub enum AuthError {
+    /// The credentials are missing, malformed, expired or revoked
+    Rejected(&'static str),
+    Database(DbErr),
+}
+
+impl From<DbErr> for AuthError {
+    fn from(e: DbErr) -> Self {
+        AuthError::Database(e)
+    }
+}
+
+/// Resolves a bearer token, either an API key or a session token, to its user.
+pub async fn authenticate(config: &AuthConfig, auth_service: &AuthService, token: &str) -> Result<AuthUser, AuthError> {
+    if token.starts_with(API_KEY_PREFIX) {
+        let key = auth_service.get_api_key_by_hash(&hash_api_key(token)).await?.ok_or(AuthError::Rejected("Unknown API key"))?;
+        let user = api_key_user(auth_service, key).await?;
+        if let Some(key_id) = user.api_key_id {
+            auth_service.touch_api_key(key_id).await?;
+        }
+        return Ok(user);
+    }
+
+    let claims: SessionClaims = config.verify(token, SESSION_AUDIENCE).map_err(|_| AuthError::Rejected("Invalid or expired session token"))?;
+    session_user(auth_service, claims.sid, &claims.sub).await
+}
+
+/// Resolves a scan events token to its user. The token must have been issued for `run_id`, and
+/// the session or API key it was issued to must still be valid.
+pub async fn authenticate_scan_events(config: &AuthConfig, auth_service: &AuthService, token: &str, run_id: i32) -> Result<AuthUser, AuthError> {
+    let claims: ScanEventsClaims = config
+        .verify(token, SCAN_EVENTS_AUDIENCE)
+        .map_err(|_| AuthError::Rejected("Invalid or expired scan events token"))?;
+    if claims.scan != run_id {
+        return Err(AuthError::Rejected("Scan events token was issued for another scan"));
+    }
+
+    match (claims.sid, claims.key) {
+        (Some(session_id), _) => session_user(auth_service, session_id, &claims.sub).await,
+        (None, Some(key_id)) => {
+            let key = auth_service
+                .get_api_key(key_id)
+                .await?
+                .filter(|key| key.user_id.to_string() == claims.sub)
+                .ok_or(AuthError::Rejected("Unknown API key"))?;
+            api_key_user(auth_service, key).await
+        }
+        (None, None) => Err(AuthError::Rejected("Invalid or expired scan events token")),
+    }
+}
+
+async fn api_key_user(auth_service: &AuthService, key: ApiKeyModel) -> Result<AuthUser, AuthError> {
+    if key.revoked_at.is_some() {
+        return Err(AuthError::Rejected("API key has been revoked"));
+    }
+    if key.expires_at.is_some_and(|expires_at| expires_at <= Utc::now().naive_utc()) {
+        return Err(AuthError::Rejected("API key has expired"));
+    }
+
+    let user = auth_service.get_user_by_id(key.user_id).await?.filter(|u| u.active).ok_or(AuthError::Rejected("User is disabled"))?;
+    Ok(AuthUser {
+        id: user.id,
+        username: user.username,
+        auth_method: "API_KEY",
+        global_role: user.global_role.as_deref().and_then(permissions::Role::parse),
+        session_id: None,
+        api_key_id: Some(key.id),
+    })
+}
+
+async fn session_user(auth_service: &AuthService, session_id: i32, user_id: &str) -> Result<AuthUser, AuthError> {
+    let session = auth_service.get_session(session_id).await?.ok_or(AuthError::Rejected("Session not found"))?;
+    if session.revoked_at.is_some() || session.expires_at <= Utc::now().naive_utc() || session.user_id.to_string() != user_id {
+        return Err(AuthError::Rejected("Session has ended"));
+    }
+
+    let user = auth_service.get_user_by_id(session.user_id).await?.filter(|u| u.active).ok_or(AuthError::Rejected("User is disabled"))?;
+    Ok(AuthUser {
+        id: user.id,
+        username: user.username,
+        auth_method: "SESSION",
+        global_role: user.global_role.as_deref().and_then(permissions::Role::parse),
+        session_id: Some(session.id),
+        api_key_id: None,
+    })
+}
+
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow!("Failed to hash password: {}", e))
+}
+
+/// Checks `password` against a stored hash. Without a hash (unknown user, or a user signing in
+/// through OIDC) a dummy hash is checked, so the response time does not reveal which usernames exist.
+pub fn verify_password(password: &str, hash: Option<&str>) -> bool {
+    static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+    let dummy = DUMMY_HASH.get_or_init(|| hash_password(&random_string(16)).unwrap_or_default());
+
+    let Ok(parsed) = PasswordHash::new(hash.unwrap_or(dummy)) else {
+        return false;
+    };
+    Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok() && hash.is_some()
+}
+
+/// A new API key with its display prefix and the hash to store. The key itself is not stored.
+pub fn generate_api_key() -> (String, String, String) {
+    let key = format!("{}{}", API_KEY_PREFIX, random_string(API_KEY_LENGTH));
+    let prefix = key[..API_KEY_DISPLAY_LENGTH].to_string();
+    let hash = hash_api_key(&key);
+    (key, prefix, hash)
+}
+
+/// API keys are long random strings, so a plain SHA-256 is enough to store them.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+pub fn random_string(length: usize) -> String {
+    rand::thread_rng().sample_iter(&Alphanumeric).take(length).map(char::from).collect()
+}
+
+/// Creates the first local user, a global admin, from `AUTH_ADMIN_USERNAME` and
+/// `AUTH_ADMIN_PASSWORD` while no user exists yet. Existing users are never changed.
+pub async fn bootstrap_admin(auth_service: &AuthService, settings: &AuthSettings) {
+    let users = match auth_service.count_users().await {
+        Ok(users) => users,
+        Err(e) => {
+            warn!("Failed to count users: {}", e);
+            return;
+        }
+    };
+    if users > 0 {
+        return;
+    }
+
+    let (Some(username), Some(password)) = (settings.admin_username.clone(), settings.admin_password.clone()) else {
+        warn!("No users exist; set AUTH_ADMIN_USERNAME and AUTH_ADMIN_PASSWORD to create the first one");
+        return;
+    };
+    if password.len() < MIN_PASSWORD_LENGTH {
+        warn!("AUTH_ADMIN_PASSWORD must have at least {} characters; no user created", MIN_PASSWORD_LENGTH);
+        return;
+    }
+
+    let password_hash = match hash_password(&password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    let user = NewUser {
+        username,
+        display_name: None,
+        email: None,
+        password_hash: Some(password_hash),
+        auth_provider: "LOCAL".to_string(),
+        external_id: None,
+        global_role: Some(permissions::Role::Admin.as_str().to_string()),
+    };
+    match auth_service.create_user(user).await {
+        Ok(user) => info!("Created initial user {}", user.username),
+        Err(e) => warn!("Failed to create initial user: {}", e),
+    }
+}
@@ -0,0 +1,381 @@
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use crate::auth::{AuthConfig, AuthUser};
+use crate::database::service::{ProjectResponse, ProjectService};
+use crate::database::team_service::TeamService;
+use sea_orm::DbErr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+/// What a user may do, on every project through their global role or on a team's projects
+/// through their membership. Each role includes the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Role {
+    Viewer,
+    Developer,
+    Maintainer,
+    Admin,
+}
+
+impl Role {
+    pub const NAMES: &'static [&'static str] = &["VIEWER", "DEVELOPER", "MAINTAINER", "ADMIN"];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "VIEWER" => Some(Role::Viewer),
+            "DEVELOPER" => Some(Role::Developer),
+            "MAINTAINER" => Some(Role::Maintainer),
+            "ADMIN" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "VIEWER",
+            Role::Developer => "DEVELOPER",
+            Role::Maintainer => "MAINTAINER",
+            Role::Admin => "ADMIN",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Projects, results, snapshots, reports, exports and scan logs
+    ViewResults,
+    /// Confirm, resolve or reopen issues
+    TriageIssues,
+    /// Start and cancel scans, capture snapshots and read the project's analysis token
+    RunAnalysis,
+    /// Quality gates and their assignment to projects
+    EditQualityGates,
+    /// Create projects, move them between teams, and manage notification channels
+    ManageProjects,
+    /// Team membership
+    ManageTeams,
+    /// SonarQube admin tokens
+    RotateTokens,
+    DeleteProjects,
+    /// Users, teams and background jobs; only through the global role
+    Administer,
+}
+
+impl Permission {
+    pub fn required_role(&self) -> Role {
+        match self {
+            Permission::ViewResults => Role::Viewer,
+            Permission::TriageIssues | Permission::RunAnalysis => Role::Developer,
+            Permission::EditQualityGates | Permission::ManageProjects => Role::Maintainer,
+            Permission::ManageTeams | Permission::RotateTokens | Permission::DeleteProjects | Permission::Administer => Role::Admin,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Permission::ViewResults => "view results",
+            Permission::TriageIssues => "triage issues",
+            Permission::RunAnalysis => "run analyses",
+            Permission::EditQualityGates => "edit quality gates",
+            Permission::ManageProjects => "manage projects",
+            Permission::ManageTeams => "manage teams",
+            Permission::RotateTokens => "rotate tokens",
+            Permission::DeleteProjects => "delete projects",
+            Permission::Administer => "administer SonarCute",
+        }
+    }
+}
+
+/// The roles of the caller, for permission checks in handlers. With authentication disabled
+/// every check passes.
+#[derive(Debug, Clone)]
+pub struct Access {
+    unrestricted: bool,
+    global_role: Option<Role>,
+    team_roles: HashMap<i32, Role>,
+}
+
+impl Access {
+    /// The caller's role on a project: the higher of their global role and their role in the owning team.
+    pub fn project_role(&self, project: &ProjectResponse) -> Option<Role> {
+        if self.unrestricted {
+            return Some(Role::Admin);
+        }
+        let team_role = project.team_id.and_then(|team_id| self.team_roles.get(&team_id).copied());
+        self.global_role.max(team_role)
+    }
+
+    pub fn team_role(&self, team_id: i32) -> Option<Role> {
+        if self.unrestricted {
+            return Some(Role::Admin);
+        }
+        self.global_role.max(self.team_roles.get(&team_id).copied())
+    }
+
+    pub fn can(&self, project: &ProjectResponse, permission: Permission) -> bool {
+        self.project_role(project).is_some_and(|role| role >= permission.required_role())
+    }
+
+    /// Blanks the project's analysis token for callers who cannot run analyses.
+    pub fn redact(&self, mut project: ProjectResponse) -> ProjectResponse {
+        if !self.can(&project, Permission::RunAnalysis) {
+            project.sonar_token = String::new();
+        }
+        project
+    }
+
+    /// Whether the caller sees every project rather than only their teams' projects.
+    pub fn sees_all_projects(&self) -> bool {
+        self.unrestricted || self.global_role.is_some()
+    }
+
+    /// Teams whose projects the caller can see.
+    pub fn team_ids(&self) -> Vec<i32> {
+        self.team_roles.keys().copied().collect()
+    }
+
+    /// Checks `permission` on a project. Callers who cannot see the project get the same 404 as
+    /// for a project that does not exist.
+    pub fn require(&self, project: &ProjectResponse, permission: Permission) -> Result<(), HttpResponse> {
+        match self.project_role(project) {
+            Some(role) if role >= permission.required_role() => Ok(()),
+            Some(role) => Err(forbidden(permission, role)),
+            None => Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }))),
+        }
+    }
+
+    /// Checks `permission` in a team.
+    pub fn require_team(&self, team_id: i32, permission: Permission) -> Result<(), HttpResponse> {
+        match self.team_role(team_id) {
+            Some(role) if role >= permission.required_role() => Ok(()),
+            Some(role) => Err(forbidden(permission, role)),
+            None => Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Team not found"
+            }))),
+        }
+    }
+
+    /// Checks `permission` through the global role, for actions not tied to a project.
+    pub fn require_global(&self, permission: Permission) -> Result<(), HttpResponse> {
+        if self.unrestricted || self.global_role.is_some_and(|role| role >= permission.required_role()) {
+            return Ok(());
+        }
+        Err(HttpResponse::Forbidden().json(serde_json::json!({
+            "error": format!("You need the global {} role to {}", permission.required_role().as_str(), permission.describe()),
+            "suggestion": "Ask an administrator to change your role with PUT /api/users/{id}/role"
+        })))
+    }
+
+    /// Checks `permission` through the global role or a role in any team.
+    pub fn require_any(&self, permission: Permission) -> Result<(), HttpResponse> {
+        let required = permission.required_role();
+        if self.team_roles.values().any(|role| *role >= required) {
+            return Ok(());
+        }
+        self.require_global(permission)
+    }
+
+    /// Loads a project and checks `permission` on it, for handlers that only have its id.
+    pub async fn require_project(&self, project_service: &ProjectService, project_id: i32, permission: Permission) -> Result<ProjectResponse, HttpResponse> {
+        self.require_found(project_service.get_project_by_id(project_id).await, permission)
+    }
+
+    fn require_found(&self, project: Result<Option<ProjectResponse>, DbErr>, permission: Permission) -> Result<ProjectResponse, HttpResponse> {
+        match project {
+            Ok(Some(project)) => self.require(&project, permission).map(|_| project),
+            Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            }))),
+            Err(e) => Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            }))),
+        }
+    }
+}
+
+fn forbidden(permission: Permission, role: Role) -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "error": format!("Your role {} cannot {}; {} is required", role.as_str(), permission.describe(), permission.required_role().as_str()),
+        "suggestion": "Ask a team admin for a higher role"
+    }))
+}
+
+impl FromRequest for Access {
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let user = req.extensions().get::<AuthUser>().cloned();
+        let enabled = req.app_data::<web::Data<AuthConfig>>().is_none_or(|config| config.enabled);
+        let team_service = req.app_data::<web::Data<TeamService>>().cloned();
+
+        Box::pin(async move {
+            let Some(user) = user else {
+                if !enabled {
+                    return Ok(Access { unrestricted: true, global_role: None, team_roles: HashMap::new() });
+                }
+                let response = HttpResponse::Unauthorized().json(serde_json::json!({
+                    "error": "Authentication required"
+                }));
+                return Err(InternalError::from_response("Unauthenticated", response).into());
+            };
+
+            let team_roles = match team_service {
+                Some(team_service) => team_service.get_user_roles(user.id).await.map_err(|e| {
+                    let response = HttpResponse::InternalServerError().json(serde_json::json!({
+                        "error": format!("Database error: {}", e)
+                    }));
+                    actix_web::Error::from(InternalError::from_response("Database error", response))
+                })?,
+                None => HashMap::new(),
+            };
+
+            Ok(Access {
+                unrestricted: false,
+                global_role: user.global_role,
+                team_roles,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::entities::Model as ProjectModel;
+
+    const ROLES: [Role; 4] = [Role::Viewer, Role::Developer, Role::Maintainer, Role::Admin];
+
+    fn project_model(team_id: Option<i32>) -> ProjectModel {
+        let now = chrono::Utc::now().naive_utc();
+        ProjectModel {
+            id: 1,
+            project_key: "demo".to_string(),
+            project_name: "Demo".to_string(),
+            project_path: "/src/demo".to_string(),
+            sonar_token: "sqp_demo".to_string(),
+            sonar_host_url: "http://localhost:9000".to_string(),
+            language: "java".to_string(),
+            sources_path: "src/main".to_string(),
+            tests_path: "src/test".to_string(),
+            coverage_report_path: None,
+            team_id,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn project(team_id: Option<i32>) -> ProjectResponse {
+        project_model(team_id).into()
+    }
+
+    fn access(global_role: Option<Role>, team_roles: &[(i32, Role)]) -> Access {
+        Access {
+            unrestricted: false,
+            global_role,
+            team_roles: team_roles.iter().copied().collect(),
+        }
+    }
+
+    fn status(result: Result<(), HttpResponse>) -> u16 {
+        result.err().map_or(200, |response| response.status().as_u16())
+    }
+
+    #[test]
+    fn each_permission_needs_its_role_or_a_higher_one() {
+        let lowest_roles = [
+            (Permission::ViewResults, Role::Viewer),
+            (Permission::TriageIssues, Role::Developer),
+            (Permission::RunAnalysis, Role::Developer),
+            (Permission::EditQualityGates, Role::Maintainer),
+            (Permission::ManageProjects, Role::Maintainer),
+            (Permission::ManageTeams, Role::Admin),
+            (Permission::RotateTokens, Role::Admin),
+            (Permission::DeleteProjects, Role::Admin),
+            (Permission::Administer, Role::Admin),
+        ];
+
+        for (permission, lowest) in lowest_roles {
+            for role in ROLES {
+                let allowed = role >= lowest;
+                let global = access(Some(role), &[]);
+                assert_eq!(global.can(&project(None), permission), allowed, "global {:?} for {:?}", role, permission);
+                assert_eq!(global.require_global(permission).is_ok(), allowed, "global {:?} for {:?}", role, permission);
+
+                let member = access(None, &[(7, role)]);
+                assert_eq!(member.can(&project(Some(7)), permission), allowed, "team {:?} for {:?}", role, permission);
+                assert_eq!(member.require_team(7, permission).is_ok(), allowed, "team {:?} for {:?}", role, permission);
+            }
+        }
+    }
+
+    #[test]
+    fn team_role_raises_a_lower_global_role() {
+        let access = access(Some(Role::Viewer), &[(7, Role::Maintainer)]);
+
+        assert_eq!(status(access.require(&project(Some(7)), Permission::EditQualityGates)), 200);
+        assert_eq!(status(access.require(&project(Some(8)), Permission::EditQualityGates)), 403);
+        assert_eq!(status(access.require(&project(Some(8)), Permission::ViewResults)), 200);
+        assert_eq!(status(access.require(&project(None), Permission::RunAnalysis)), 403);
+        assert_eq!(access.redact(project(Some(7))).sonar_token, "sqp_demo");
+        assert_eq!(access.redact(project(Some(8))).sonar_token, "");
+        assert!(access.sees_all_projects());
+    }
+
+    #[test]
+    fn global_role_applies_above_a_lower_team_role() {
+        let access = access(Some(Role::Maintainer), &[(7, Role::Viewer)]);
+
+        assert_eq!(status(access.require(&project(Some(7)), Permission::ManageProjects)), 200);
+        assert_eq!(status(access.require(&project(Some(7)), Permission::DeleteProjects)), 403);
+        assert_eq!(access.team_role(7), Some(Role::Maintainer));
+    }
+
+    #[test]
+    fn team_members_only_see_their_teams_projects() {
+        let access = access(None, &[(7, Role::Developer)]);
+
+        assert!(!access.sees_all_projects());
+        assert_eq!(access.team_ids(), vec![7]);
+        assert_eq!(status(access.require(&project(Some(7)), Permission::RunAnalysis)), 200);
+        assert_eq!(status(access.require(&project(Some(7)), Permission::ManageProjects)), 403);
+        assert_eq!(status(access.require(&project(Some(8)), Permission::ViewResults)), 404);
+        assert_eq!(status(access.require(&project(None), Permission::ViewResults)), 404);
+        assert_eq!(status(access.require_global(Permission::ViewResults)), 403);
+        assert_eq!(status(access.require_any(Permission::RunAnalysis)), 200);
+        assert_eq!(status(access.require_any(Permission::ManageProjects)), 403);
+    }
+
+    #[test]
+    fn unrestricted_access_passes_every_check() {
+        let access = Access { unrestricted: true, global_role: None, team_roles: HashMap::new() };
+
+        assert!(access.sees_all_projects());
+        assert_eq!(status(access.require(&project(None), Permission::DeleteProjects)), 200);
+        assert_eq!(status(access.require_global(Permission::Administer)), 200);
+        assert_eq!(status(access.require_team(7, Permission::ManageTeams)), 200);
+    }
+
+    #[test]
+    fn require_project_checks_the_loaded_projects_team() {
+        let access = access(Some(Role::Viewer), &[(7, Role::Developer)]);
+
+        let found = access.require_found(Ok(Some(project(Some(7)))), Permission::RunAnalysis).unwrap();
+        assert_eq!(found.team_id, Some(7));
+
+        let denied = access.require_found(Ok(Some(project(Some(8)))), Permission::RunAnalysis).unwrap_err();
+        assert_eq!(denied.status().as_u16(), 403);
+
+        let missing = access.require_found(Ok(None), Permission::ViewResults).unwrap_err();
+        assert_eq!(missing.status().as_u16(), 404);
+
+        let failed = access.require_found(Err(DbErr::Custom("connection lost".to_string())), Permission::ViewResults).unwrap_err();
+        assert_eq!(failed.status().as_u16(), 500);
+    }
+}
@@ -0,0 +1,243 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::audit::Audit;
+use crate::auth::handlers::parse_role;
+use crate::auth::permissions::{Access, Permission};
+use crate::database::auth_service::AuthService;
+use crate::database::team_service::{CreateTeamRequest, SetTeamMemberRequest, TeamResponse, TeamService, UpdateTeamRequest};
+
+/// Loads a team and checks `permission` in it.
+async fn require_team(access: &Access, team_service: &TeamService, team_id: i32, permission: Permission) -> std::result::Result<TeamResponse, HttpResponse> {
+    access.require_team(team_id, permission)?;
+    match team_service.get_team_by_id(team_id).await {
+        Ok(Some(team)) => Ok(team),
+        Ok(None) => Err(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found"
+        }))),
+        Err(e) => Err(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+/// Records the user's membership before it changes, as the audit event's `before`.
+async fn record_membership(audit: &Audit, team_service: &TeamService, team_id: i32, user_id: i32) {
+    if let Ok(members) = team_service.get_members(team_id).await
+        && let Some(member) = members.iter().find(|member| member.user_id == user_id)
+    {
+        audit.before(member);
+    }
+}
+
+/// Every team for users with a global role, otherwise the caller's teams.
+pub async fn get_teams(access: Access, team_service: web::Data<TeamService>) -> Result<HttpResponse> {
+    let teams = if access.sees_all_projects() {
+        team_service.get_teams().await
+    } else {
+        team_service.get_teams_by_ids(access.team_ids()).await
+    };
+
+    match teams {
+        Ok(teams) => Ok(HttpResponse::Ok().json(teams)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_team(path: web::Path<i32>, access: Access, team_service: web::Data<TeamService>) -> Result<HttpResponse> {
+    match require_team(&access, &team_service, path.into_inner(), Permission::ViewResults).await {
+        Ok(team) => Ok(HttpResponse::Ok().json(team)),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn create_team(
+    request: web::Json<CreateTeamRequest>,
+    access: Access,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let mut request = request.into_inner();
+    request.name = request.name.trim().to_string();
+    if request.name.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Team name must not be empty"
+        })));
+    }
+
+    match team_service.get_team_by_name(&request.name).await {
+        Ok(Some(_)) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("Team {} already exists", request.name)
+            })));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    }
+
+    match team_service.create_team(request).await {
+        Ok(team) => Ok(HttpResponse::Created().json(team)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn update_team(
+    path: web::Path<i32>,
+    request: web::Json<UpdateTeamRequest>,
+    access: Access,
+    audit: Audit,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let team_id = path.into_inner();
+    match require_team(&access, &team_service, team_id, Permission::ManageTeams).await {
+        Ok(team) => audit.before(&team),
+        Err(response) => return Ok(response),
+    }
+
+    let mut request = request.into_inner();
+    if let Some(name) = request.name.as_mut() {
+        *name = name.trim().to_string();
+        if name.is_empty() {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "Team name must not be empty"
+            })));
+        }
+        match team_service.get_team_by_name(name).await {
+            Ok(Some(other)) if other.id != team_id => {
+                return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                    "error": format!("Team {} already exists", name)
+                })));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                })));
+            }
+        }
+    }
+
+    match team_service.update_team(team_id, request).await {
+        Ok(Some(team)) => Ok(HttpResponse::Ok().json(team)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn delete_team(path: web::Path<i32>, access: Access, audit: Audit, team_service: web::Data<TeamService>) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let team_id = path.into_inner();
+    if let Ok(Some(team)) = team_service.get_team_by_id(team_id).await {
+        audit.before(&team);
+    }
+
+    match team_service.delete_team(team_id).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Team not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_team_members(path: web::Path<i32>, access: Access, team_service: web::Data<TeamService>) -> Result<HttpResponse> {
+    let team_id = path.into_inner();
+    if let Err(response) = require_team(&access, &team_service, team_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    match team_service.get_members(team_id).await {
+        Ok(members) => Ok(HttpResponse::Ok().json(members)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+/// Adds a user to a team or changes their role in it.
+pub async fn set_team_member(
+    path: web::Path<(i32, i32)>,
+    request: web::Json<SetTeamMemberRequest>,
+    access: Access,
+    audit: Audit,
+    team_service: web::Data<TeamService>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let (team_id, user_id) = path.into_inner();
+    if let Err(response) = require_team(&access, &team_service, team_id, Permission::ManageTeams).await {
+        return Ok(response);
+    }
+    let role = match parse_role(&request.role) {
+        Ok(role) => role,
+        Err(response) => return Ok(response),
+    };
+
+    match auth_service.get_user_by_id(user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "User not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    }
+
+    record_membership(&audit, &team_service, team_id, user_id).await;
+    if let Err(e) = team_service.set_member(team_id, user_id, role).await {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        })));
+    }
+
+    match team_service.get_members(team_id).await {
+        Ok(members) => Ok(HttpResponse::Ok().json(members)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn remove_team_member(
+    path: web::Path<(i32, i32)>,
+    access: Access,
+    audit: Audit,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (team_id, user_id) = path.into_inner();
+    if let Err(response) = require_team(&access, &team_service, team_id, Permission::ManageTeams).await {
+        return Ok(response);
+    }
+
+    record_membership(&audit, &team_service, team_id, user_id).await;
+    match team_service.remove_member(team_id, user_id).await {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "User is not a member of this team"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
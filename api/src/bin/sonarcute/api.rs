@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+/// Consecutive failed connections after which following an event stream gives up.
+const MAX_RECONNECTS: u32 = 5;
+
+/// Blocking client for the SonarCute HTTP API. Responses are kept as JSON values.
+pub struct ApiClient {
+    client: Client,
+    /// Without a timeout, for event streams that stay open while a scan runs.
+    stream_client: Client,
+    base_url: String,
+}
+
+impl ApiClient {
+    /// `api_key` is sent as a bearer token with every request.
+    pub fn new(base_url: &str, api_key: Option<&str>) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = api_key {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", api_key)).map_err(|_| anyhow!("API key contains invalid characters"))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+
+        let client = Client::builder().timeout(Duration::from_secs(120)).default_headers(headers.clone()).build()?;
+        let stream_client = Client::builder().timeout(None).default_headers(headers).build()?;
+        Ok(Self {
+            client,
+            stream_client,
+            base_url: format!("{}/api", base_url.trim_end_matches('/')),
+        })
+    }
+
+    pub fn get(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        self.send(self.client.get(self.url(path)).query(query))
+    }
+
+    pub fn post(&self, path: &str, body: &Value) -> Result<Value> {
+        self.send(self.client.post(self.url(path)).json(body))
+    }
+
+    pub fn post_query(&self, path: &str, query: &[(&str, String)]) -> Result<Value> {
+        self.send(self.client.post(self.url(path)).query(query))
+    }
+
+    pub fn delete(&self, path: &str, body: &Value) -> Result<Value> {
+        self.send(self.client.delete(self.url(path)).json(body))
+    }
+
+    /// Follows a Server-Sent Events stream, passing each event name and JSON data to `on_event`
+    /// until it returns `false`. Dropped connections are resumed with `Last-Event-ID`.
+    pub fn follow_events(&self, path: &str, mut on_event: impl FnMut(&str, &Value) -> bool) -> Result<()> {
+        let mut last_id: Option<String> = None;
+        let mut failures = 0;
+
+        loop {
+            let mut request = self.stream_client.get(self.url(path)).header("Accept", "text/event-stream");
+            if let Some(id) = &last_id {
+                request = request.header("Last-Event-ID", id);
+            }
+
+            let response = match request.send() {
+                Ok(response) if response.status().is_success() => Some(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let body: Value = response.json().unwrap_or(Value::Null);
+                    return Err(anyhow!("{}", body["error"].as_str().map(str::to_string).unwrap_or_else(|| status.to_string())));
+                }
+                Err(_) => None,
+            };
+
+            if let Some(response) = response {
+                let (mut event, mut data) = (String::from("message"), String::new());
+                for line in BufReader::new(response).lines() {
+                    let Ok(line) = line else { break };
+                    if line.is_empty() {
+                        if !data.is_empty() {
+                            failures = 0;
+                            let value = serde_json::from_str(&data).unwrap_or(Value::String(data.clone()));
+                            if !on_event(&event, &value) {
+                                return Ok(());
+                            }
+                        }
+                        (event, data) = (String::from("message"), String::new());
+                    } else if let Some(value) = line.strip_prefix("id:") {
+                        last_id = Some(value.trim().to_string());
+                    } else if let Some(value) = line.strip_prefix("event:") {
+                        event = value.trim().to_string();
+                    } else if let Some(value) = line.strip_prefix("data:") {
+                        if !data.is_empty() {
+                            data.push('\n');
+                        }
+                        data.push_str(value.strip_prefix(' ').unwrap_or(value));
+                    }
+                }
+            }
+
+            failures += 1;
+            if failures > MAX_RECONNECTS {
+                return Err(anyhow!("Lost the event stream from SonarCute at {}", self.base_url));
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Sends a request and turns non-2xx responses into errors carrying the API's `error` and `suggestion`.
+    fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let response = request.send().map_err(|e| anyhow!("Cannot reach SonarCute at {}: {}", self.base_url, e))?;
+        let status = response.status();
+        let body: Value = response.json().unwrap_or(Value::Null);
+
+        if status.is_success() {
+            return Ok(body);
+        }
+
+        let message = body["error"].as_str().map(str::to_string).unwrap_or_else(|| status.to_string());
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(anyhow!("{} (pass an API key with --api-key or SONARCUTE_API_KEY)", message));
+        }
+        match body["suggestion"].as_str() {
+            Some(suggestion) => Err(anyhow!("{} ({})", message, suggestion)),
+            None => Err(anyhow!("{}", message)),
+        }
+    }
+
+    /// Finds a registered project by id, key or path.
+    pub fn find_project(&self, reference: &str) -> Result<Value> {
+        let projects = self.get("/projects", &[])?;
+        projects
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|p| {
+                p["project_key"].as_str() == Some(reference)
+                    || p["project_path"].as_str() == Some(reference)
+                    || p["id"].as_i64().map(|id| id.to_string()).as_deref() == Some(reference)
+            })
+            .cloned()
+            .ok_or_else(|| anyhow!("Project not found: {}", reference))
+    }
+}
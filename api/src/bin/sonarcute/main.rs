@@ -0,0 +1,505 @@
+//! `sonarcute`: command-line client for the SonarCute API, for CI pipelines and scripts.
+
+mod api;
+mod output;
+
+use anyhow::{anyhow, Result};
+use api::ApiClient;
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Exit code when the quality gate or the coverage report check failed.
+const EXIT_GATE_FAILED: u8 = 1;
+/// Exit code for API, network and timeout errors, and scans that did not succeed.
+const EXIT_ERROR: u8 = 2;
+
+#[derive(Parser)]
+#[command(name = "sonarcute", version, about = "Command-line client for the SonarCute API")]
+struct Cli {
+    /// SonarCute server URL
+    #[arg(long, global = true, env = "SONARCUTE_URL", default_value = "http://localhost:8888")]
+    url: String,
+
+    /// API key, created with POST /api/api-keys
+    #[arg(long, global = true, env = "SONARCUTE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Output format
+    #[arg(short, long, global = true, value_enum, env = "SONARCUTE_OUTPUT", default_value = "human")]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Register, list and delete projects
+    #[command(subcommand)]
+    Projects(ProjectsCommand),
+    /// Print the scanner command of a project
+    Command {
+        /// Project id, key or path
+        project: String,
+    },
+    /// Fetch issues, coverage and quality gate status of a project
+    Results {
+        /// Project id, key or path
+        project: String,
+    },
+    /// Check a project's coverage report before scanning
+    Coverage {
+        /// Project id, key or path
+        project: String,
+        /// Report to check instead of the configured one, relative to the project path
+        #[arg(long)]
+        report: Option<String>,
+    },
+    /// Quality gate commands
+    #[command(subcommand)]
+    Gate(GateCommand),
+    /// Run scans on the SonarCute server and follow their output
+    #[command(subcommand)]
+    Scans(ScansCommand),
+}
+
+#[derive(Subcommand)]
+enum ProjectsCommand {
+    /// List registered projects
+    List,
+    /// Register a project in SonarCute and SonarQube
+    Create(CreateProject),
+    /// Propose the settings of a project from its build files
+    Detect {
+        /// Project directory; relative paths are resolved here when they exist locally
+        path: String,
+    },
+    /// Delete a project from SonarCute and SonarQube
+    Delete {
+        /// Project id, key or path
+        project: String,
+    },
+}
+
+#[derive(Args)]
+struct CreateProject {
+    #[arg(long)]
+    key: String,
+    #[arg(long)]
+    name: String,
+    /// Project directory, used to identify the project
+    #[arg(long)]
+    path: String,
+    #[arg(long)]
+    language: String,
+    #[arg(long, default_value = "src/main")]
+    sources: String,
+    #[arg(long, default_value = "src/test")]
+    tests: String,
+    /// Coverage report path, e.g. build/reports/jacoco/test/jacocoTestReport.xml
+    #[arg(long)]
+    coverage: Option<String>,
+    /// Id of the owning team; without a team only users with a global role see the project
+    #[arg(long)]
+    team: Option<i32>,
+}
+
+#[derive(Subcommand)]
+enum ScansCommand {
+    /// Queue a scan and follow it until SonarQube processed the analysis
+    Start {
+        /// Project id, key or path
+        project: String,
+        /// Print the queued run and exit instead of following it
+        #[arg(long)]
+        detach: bool,
+    },
+    /// List recent scans of a project
+    List {
+        /// Project id, key or path
+        project: String,
+    },
+    /// Follow the output of a scan; a finished scan's output is printed
+    Logs {
+        /// Scan run id
+        run: i64,
+    },
+    /// Cancel a queued or running scan
+    Cancel {
+        /// Scan run id
+        run: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum GateCommand {
+    /// Wait for the analysis to be processed and exit non-zero if the quality gate failed
+    Check(GateCheck),
+}
+
+#[derive(Args)]
+struct GateCheck {
+    /// Project id, key or path
+    project: String,
+    /// Scanner's report-task.txt; waits for exactly that Compute Engine task
+    #[arg(long, conflicts_with = "since")]
+    report_task: Option<PathBuf>,
+    /// Only accept an analysis made at or after this time (RFC 3339), e.g. the time the scan started
+    #[arg(long)]
+    since: Option<DateTime<Utc>>,
+    /// Seconds to wait for the analysis
+    #[arg(long, default_value_t = 300)]
+    timeout: u64,
+    /// Seconds between polls with --since
+    #[arg(long, default_value_t = 5)]
+    interval: u64,
+    /// Pass when no quality gate is assigned to the project instead of failing with an error
+    #[arg(long)]
+    allow_no_gate: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = ApiClient::new(&cli.url, cli.api_key.as_deref()).and_then(|client| run(&client, cli.command, cli.output));
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            if cli.output == OutputFormat::Json {
+                output::print_json(&json!({ "error": e.to_string() }));
+            } else {
+                eprintln!("error: {}", e);
+            }
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run(client: &ApiClient, command: Commands, format: OutputFormat) -> Result<u8> {
+    match command {
+        Commands::Projects(ProjectsCommand::List) => {
+            let projects = client.get("/projects", &[])?;
+            match format {
+                OutputFormat::Json => output::print_json(&projects),
+                OutputFormat::Human => output::print_projects(&projects),
+            }
+        }
+        Commands::Projects(ProjectsCommand::Create(args)) => {
+            let project = client.post(
+                "/projects",
+                &json!({
+                    "project_key": args.key,
+                    "project_name": args.name,
+                    "project_path": args.path,
+                    "language": args.language,
+                    "sources_path": args.sources,
+                    "tests_path": args.tests,
+                    "coverage_report_path": args.coverage,
+                    "team_id": args.team,
+                }),
+            )?;
+            match format {
+                OutputFormat::Json => output::print_json(&project),
+                OutputFormat::Human => output::print_project(&project),
+            }
+        }
+        Commands::Projects(ProjectsCommand::Detect { path }) => {
+            let path = fs::canonicalize(&path).map(|p| p.to_string_lossy().into_owned()).unwrap_or(path);
+            let detection = client.post("/projects/detect", &json!({ "project_path": path }))?;
+            match format {
+                OutputFormat::Json => output::print_json(&detection),
+                OutputFormat::Human => output::print_project_detection(&detection),
+            }
+        }
+        Commands::Projects(ProjectsCommand::Delete { project }) => {
+            let project = client.find_project(&project)?;
+            let response = client.delete("/projects", &json!({ "project_path": project["project_path"] }))?;
+            match format {
+                OutputFormat::Json => output::print_json(&response),
+                OutputFormat::Human => {
+                    println!("{}", response["message"].as_str().unwrap_or("Project deleted"));
+                    if let Some(warning) = response["warning"].as_str() {
+                        eprintln!("warning: {}", warning);
+                    }
+                }
+            }
+        }
+        Commands::Command { project } => {
+            let project = client.find_project(&project)?;
+            let response = client.post("/generate-command", &json!({ "project_path": project["project_path"] }))?;
+            match format {
+                OutputFormat::Json => output::print_json(&response),
+                OutputFormat::Human => println!("{}", response["command"].as_str().unwrap_or_default()),
+            }
+        }
+        Commands::Results { project } => {
+            let project = client.find_project(&project)?;
+            let results = client.post("/results", &json!({ "project_path": project["project_path"] }))?;
+            match format {
+                OutputFormat::Json => output::print_json(&results),
+                OutputFormat::Human => output::print_results(&results),
+            }
+        }
+        Commands::Coverage { project, report } => {
+            let project = client.find_project(&project)?;
+            let id = project["id"].as_i64().ok_or_else(|| anyhow!("Project has no id"))?;
+            let query: Vec<(&str, String)> = report.into_iter().map(|r| ("report_path", r)).collect();
+            let validation = client.get(&format!("/projects/{}/coverage/validate", id), &query)?;
+            match format {
+                OutputFormat::Json => output::print_json(&validation),
+                OutputFormat::Human => output::print_coverage_validation(&validation),
+            }
+            if validation["valid"] != true {
+                return Ok(EXIT_GATE_FAILED);
+            }
+        }
+        Commands::Gate(GateCommand::Check(args)) => return gate_check(client, &args, format),
+        Commands::Scans(ScansCommand::Start { project, detach }) => {
+            let project = client.find_project(&project)?;
+            let id = project["id"].as_i64().ok_or_else(|| anyhow!("Project has no id"))?;
+            let run = client.post_query(&format!("/projects/{}/scans", id), &[])?;
+            if detach {
+                match format {
+                    OutputFormat::Json => output::print_json(&run),
+                    OutputFormat::Human => println!("Queued scan run {}", output::text(&run["id"])),
+                }
+                return Ok(0);
+            }
+            let run_id = run["id"].as_i64().ok_or_else(|| anyhow!("Scan run has no id"))?;
+            return follow_scan(client, run_id, format);
+        }
+        Commands::Scans(ScansCommand::List { project }) => {
+            let project = client.find_project(&project)?;
+            let id = project["id"].as_i64().ok_or_else(|| anyhow!("Project has no id"))?;
+            let runs = client.get(&format!("/projects/{}/scans", id), &[])?;
+            match format {
+                OutputFormat::Json => output::print_json(&runs),
+                OutputFormat::Human => output::print_scans(&runs),
+            }
+        }
+        Commands::Scans(ScansCommand::Logs { run }) => return follow_scan(client, run, format),
+        Commands::Scans(ScansCommand::Cancel { run }) => {
+            let run = client.post_query(&format!("/scans/{}/cancel", run), &[])?;
+            match format {
+                OutputFormat::Json => output::print_json(&run),
+                OutputFormat::Human if run["status"] == "CANCELED" => println!("Scan run {} canceled", output::text(&run["id"])),
+                OutputFormat::Human => println!("Scan run {} will stop shortly", output::text(&run["id"])),
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// Exit code for a quality gate status. A project without quality gate (`NONE`) only passes with
+/// `--allow-no-gate`, so a missing gate assignment does not go unnoticed in CI.
+fn exit_code(status: &str, allow_no_gate: bool) -> u8 {
+    match status {
+        "OK" | "WARN" => 0,
+        "NONE" if allow_no_gate => 0,
+        "ERROR" => EXIT_GATE_FAILED,
+        _ => EXIT_ERROR,
+    }
+}
+
+/// Streams a scan's output until it finishes. Scanner output goes to stdout and stderr as the
+/// scanner wrote it; progress goes to stderr. Exits like `gate check` once the scan succeeded.
+fn follow_scan(client: &ApiClient, run_id: i64, format: OutputFormat) -> Result<u8> {
+    let mut result = Value::Null;
+    client.follow_events(&format!("/scans/{}/events", run_id), |event, data| {
+        match event {
+            "log" if data["stream"] == "stderr" => eprintln!("{}", output::text(&data["line"])),
+            "log" => println!("{}", output::text(&data["line"])),
+            "status" => eprintln!("==> {}", output::scan_status(data)),
+            "done" => {
+                result = data.clone();
+                return false;
+            }
+            _ => {}
+        }
+        true
+    })?;
+
+    let code = match (result["status"].as_str(), result["quality_gate_status"].as_str()) {
+        (Some("SUCCEEDED"), Some("ERROR")) => EXIT_GATE_FAILED,
+        (Some("SUCCEEDED"), _) => 0,
+        _ => EXIT_ERROR,
+    };
+
+    match format {
+        OutputFormat::Json => output::print_json(&result),
+        OutputFormat::Human => {
+            eprintln!("==> {}", output::scan_status(&result));
+            if let Some(error) = result["error"].as_str() {
+                eprintln!("error: {}", error);
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Reads `ceTaskId` from the `report-task.txt` the scanner writes after uploading a report.
+fn read_task_id(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path).map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("ceTaskId="))
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("No ceTaskId in {}", path.display()))
+}
+
+/// Long-polls `POST /projects/{id}/analysis/wait` until the Compute Engine task is processed
+/// and returns the snapshot of the analysis.
+fn wait_for_analysis(client: &ApiClient, project: &Value, args: &GateCheck, deadline: Instant, format: OutputFormat) -> Result<Value> {
+    let id = project["id"].as_i64().ok_or_else(|| anyhow!("Project has no id"))?;
+    let task_id = args.report_task.as_deref().map(read_task_id).transpose()?;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now()).as_secs().clamp(1, 60);
+        let mut query = vec![("timeout", remaining.to_string())];
+        if let Some(task_id) = &task_id {
+            query.push(("task_id", task_id.clone()));
+        }
+
+        let response = client.post_query(&format!("/projects/{}/analysis/wait", id), &query)?;
+        match response["status"].as_str().unwrap_or_default() {
+            "SUCCESS" => return Ok(response["snapshot"].clone()),
+            "FAILED" | "CANCELED" => {
+                return Err(anyhow!("Analysis task {}: {}", response["task"]["id"].as_str().unwrap_or_default(), response["error"].as_str().unwrap_or("failed")));
+            }
+            status => {
+                if Instant::now() >= deadline {
+                    return Err(anyhow!("Analysis still {} after {}s", status, args.timeout));
+                }
+                if format == OutputFormat::Human {
+                    eprintln!("Analysis task is {}, waiting...", status);
+                }
+            }
+        }
+    }
+}
+
+/// Polls the project's analyses until one was made at or after `since`, then stores a snapshot of it.
+fn wait_for_analysis_since(client: &ApiClient, project: &Value, since: DateTime<Utc>, args: &GateCheck, deadline: Instant, format: OutputFormat) -> Result<Value> {
+    let id = project["id"].as_i64().ok_or_else(|| anyhow!("Project has no id"))?;
+    let query = vec![
+        ("metrics", "alert_status".to_string()),
+        ("from", since.format("%Y-%m-%dT%H:%M:%S%z").to_string()),
+    ];
+
+    loop {
+        let history = client.get(&format!("/projects/{}/history", id), &query)?;
+        if history["analyses"].as_array().is_some_and(|a| !a.is_empty()) {
+            break;
+        }
+        if Instant::now() >= deadline {
+            return Err(anyhow!("No analysis of {} found within {}s", project["project_key"].as_str().unwrap_or_default(), args.timeout));
+        }
+        if format == OutputFormat::Human {
+            eprintln!("Waiting for analysis of {}...", project["project_key"].as_str().unwrap_or_default());
+        }
+        thread::sleep(Duration::from_secs(args.interval.max(1)));
+    }
+
+    client.post_query(&format!("/projects/{}/snapshots", id), &[])
+}
+
+/// Waits for the analysis selected by `--report-task` or `--since` (by default the last one
+/// once the project's Compute Engine queue is empty) and reports its quality gate.
+/// `ERROR` fails the check; a failed analysis or no analysis within the timeout is an error.
+fn gate_check(client: &ApiClient, args: &GateCheck, format: OutputFormat) -> Result<u8> {
+    let project = client.find_project(&args.project)?;
+    let deadline = Instant::now() + Duration::from_secs(args.timeout);
+
+    let snapshot = match args.since {
+        Some(since) => wait_for_analysis_since(client, &project, since, args, deadline, format)?,
+        None => wait_for_analysis(client, &project, args, deadline, format)?,
+    };
+    let status = snapshot["quality_gate_status"].as_str().unwrap_or("UNKNOWN");
+    let code = exit_code(status, args.allow_no_gate);
+
+    match format {
+        OutputFormat::Json => output::print_json(&json!({
+            "project_key": project["project_key"],
+            "passed": code == 0,
+            "snapshot": snapshot,
+        })),
+        OutputFormat::Human => {
+            println!(
+                "Quality gate {}: {} (analysis {})",
+                project["project_key"].as_str().unwrap_or_default(),
+                status,
+                snapshot["analysis_date"].as_str().unwrap_or("-")
+            );
+            output::print_conditions(&snapshot["quality_gate_conditions"]);
+            if status == "NONE" && code != 0 {
+                eprintln!("error: no quality gate is assigned to this project; pass --allow-no-gate to accept that");
+            }
+        }
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_gate_status_to_exit_code() {
+        assert_eq!(exit_code("OK", false), 0);
+        assert_eq!(exit_code("WARN", false), 0);
+        assert_eq!(exit_code("ERROR", false), EXIT_GATE_FAILED);
+        assert_eq!(exit_code("ERROR", true), EXIT_GATE_FAILED);
+        assert_eq!(exit_code("UNKNOWN", true), EXIT_ERROR);
+    }
+
+    #[test]
+    fn no_gate_passes_only_when_allowed() {
+        assert_eq!(exit_code("NONE", false), EXIT_ERROR);
+        assert_eq!(exit_code("NONE", true), 0);
+    }
+
+    #[test]
+    fn reads_the_task_id_from_report_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report-task.txt");
+        fs::write(&path, "projectKey=demo\nserverUrl=http://localhost:9000\nceTaskId=AYx1-2_3\nceTaskUrl=http://localhost:9000/api/ce/task?id=AYx1-2_3\n").unwrap();
+        assert_eq!(read_task_id(&path).unwrap(), "AYx1-2_3");
+
+        fs::write(&path, "projectKey=demo\r\n  ceTaskId=AY9\r\n").unwrap();
+        assert_eq!(read_task_id(&path).unwrap(), "AY9");
+    }
+
+    #[test]
+    fn report_task_without_task_id_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report-task.txt");
+        fs::write(&path, "projectKey=demo\n").unwrap();
+        assert!(read_task_id(&path).unwrap_err().to_string().contains("No ceTaskId"));
+
+        let missing = dir.path().join("missing.txt");
+        assert!(read_task_id(&missing).unwrap_err().to_string().contains("Cannot read"));
+    }
+
+    #[test]
+    fn parses_gate_check_flags() {
+        let cli = Cli::try_parse_from(["sonarcute", "gate", "check", "demo", "--allow-no-gate", "--timeout", "60"]).unwrap();
+        let Commands::Gate(GateCommand::Check(args)) = cli.command else {
+            panic!("expected gate check");
+        };
+        assert_eq!(args.project, "demo");
+        assert!(args.allow_no_gate);
+        assert_eq!((args.timeout, args.interval), (60, 5));
+    }
+}
@@ -0,0 +1,205 @@
+use serde_json::Value;
+
+pub fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()));
+}
+
+pub fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Prints rows as a left-aligned table sized to its widest cells.
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| rows.iter().map(|r| r[i].chars().count()).chain([h.len()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: Vec<String>| {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(c, w)| format!("{:<w$}", c, w = *w)).collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+
+    line(headers.iter().map(|h| h.to_string()).collect());
+    line(widths.iter().map(|w| "-".repeat(*w)).collect());
+    for row in rows {
+        line(row.clone());
+    }
+}
+
+pub fn print_projects(projects: &Value) {
+    let rows: Vec<Vec<String>> = projects
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|p| ["id", "project_key", "project_name", "language", "project_path"].iter().map(|f| text(&p[*f])).collect())
+        .collect();
+
+    if rows.is_empty() {
+        println!("No projects registered.");
+    } else {
+        print_table(&["ID", "KEY", "NAME", "LANGUAGE", "PATH"], &rows);
+    }
+}
+
+pub fn print_project(project: &Value) {
+    println!("Project {} ({})", text(&project["project_key"]), text(&project["id"]));
+    println!("  Name:     {}", text(&project["project_name"]));
+    println!("  Path:     {}", text(&project["project_path"]));
+    println!("  Language: {}", text(&project["language"]));
+    println!("  Team:     {}", text(&project["team_id"]));
+    println!("  Token:    {}", text(&project["sonar_token"]));
+}
+
+/// Human summary of `POST /api/projects/detect`, ending with the matching `projects create` command.
+pub fn print_project_detection(detection: &Value) {
+    let rows: Vec<Vec<String>> = detection["detected"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|b| ["manifest", "build_tool", "language", "sources_path", "tests_path", "coverage_report_path"].iter().map(|f| text(&b[*f])).collect())
+        .collect();
+    if !rows.is_empty() {
+        print_table(&["MANIFEST", "TOOL", "LANGUAGE", "SOURCES", "TESTS", "COVERAGE"], &rows);
+    }
+
+    for warning in detection["warnings"].as_array().into_iter().flatten() {
+        eprintln!("warning: {}", text(warning));
+    }
+
+    if let Some(language) = detection["language"].as_str() {
+        println!();
+        println!(
+            "sonarcute projects create --key {} --name '{}' --path '{}' --language {} --sources {} --tests {} --coverage {}",
+            text(&detection["project_key"]),
+            text(&detection["project_name"]),
+            text(&detection["project_path"]),
+            language,
+            text(&detection["sources_path"]),
+            text(&detection["tests_path"]),
+            text(&detection["coverage_report_path"])
+        );
+    }
+}
+
+pub fn print_conditions(conditions: &Value) {
+    let rows: Vec<Vec<String>> = conditions
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|c| ["status", "metricKey", "actualValue", "comparator", "errorThreshold"].iter().map(|f| text(&c[*f])).collect())
+        .collect();
+
+    if !rows.is_empty() {
+        print_table(&["STATUS", "METRIC", "ACTUAL", "OP", "THRESHOLD"], &rows);
+    }
+}
+
+/// Human summary of `POST /api/results`: quality gate, coverage and issue counts.
+pub fn print_results(results: &Value) {
+    let project = &results["project"];
+    println!("Project:      {} ({})", text(&project["project_name"]), text(&project["project_key"]));
+
+    let status = &results["quality_gate"]["projectStatus"];
+    println!("Quality gate: {}", text(&status["status"]));
+
+    let measures = results["coverage"]["component"]["measures"].as_array().cloned().unwrap_or_default();
+    for measure in &measures {
+        println!("{:<13} {}", format!("{}:", text(&measure["metric"])), text(&measure["value"]));
+    }
+
+    let issues = results["issues"]["issues"].as_array().cloned().unwrap_or_default();
+    let total = &results["issues"]["paging"]["total"];
+    println!("Issues:       {}", text(total));
+    // The results endpoint returns the first page only; break down by severity when it is complete
+    let complete = total.as_u64() == Some(issues.len() as u64);
+    for severity in ["BLOCKER", "CRITICAL", "MAJOR", "MINOR", "INFO"].into_iter().filter(|_| complete) {
+        let count = issues.iter().filter(|i| i["severity"].as_str() == Some(severity)).count();
+        if count > 0 {
+            println!("  {:<10} {}", severity, count);
+        }
+    }
+
+    for key in ["issues_error", "coverage_error", "quality_gate_error"] {
+        if let Some(error) = results[key]["error"].as_str() {
+            eprintln!("warning: {}", error);
+        }
+    }
+
+    println!();
+    print_conditions(&status["conditions"]);
+}
+
+/// One-line status of a scan run or `status` event, e.g. `RUNNING (UPLOADING)`.
+pub fn scan_status(run: &Value) -> String {
+    let mut status = text(&run["status"]);
+    if let Some(phase) = run["phase"].as_str() {
+        status = format!("{} ({})", status, phase);
+    }
+    if let Some(gate) = run["quality_gate_status"].as_str() {
+        status = format!("{}, quality gate {}", status, gate);
+    }
+    if let Some(duration) = run["duration_ms"].as_i64() {
+        status = format!("{} in {:.1}s", status, duration as f64 / 1000.0);
+    }
+    status
+}
+
+/// Abbreviated commit of a scan run or snapshot, marked when the working tree had changes.
+fn commit(value: &Value) -> String {
+    let Some(commit) = value["git_commit"].as_str() else {
+        return "-".to_string();
+    };
+    let short = &commit[..commit.len().min(8)];
+    if value["git_dirty"] == true { format!("{} (dirty)", short) } else { short.to_string() }
+}
+
+pub fn print_scans(runs: &Value) {
+    let rows: Vec<Vec<String>> = runs
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|r| {
+            let mut row: Vec<String> = ["id", "status", "quality_gate_status", "git_branch"].iter().map(|f| text(&r[*f])).collect();
+            row.push(commit(r));
+            row.extend(["queued_at", "duration_ms"].iter().map(|f| text(&r[*f])));
+            row
+        })
+        .collect();
+
+    if rows.is_empty() {
+        println!("No scans run yet.");
+    } else {
+        print_table(&["ID", "STATUS", "GATE", "BRANCH", "COMMIT", "QUEUED", "DURATION MS"], &rows);
+    }
+}
+
+/// Human summary of a coverage report check: format, totals, problems and warnings.
+pub fn print_coverage_validation(validation: &Value) {
+    println!("Report:   {} ({})", text(&validation["report_path"]), text(&validation["format"]));
+    if let Some(property) = validation["sonar_property"].as_str() {
+        println!("Property: {}", property);
+    }
+
+    let summary = &validation["summary"];
+    if summary.is_object() {
+        println!("Files:    {}", text(&summary["files"]));
+        println!("Lines:    {}/{} ({}%)", text(&summary["lines_covered"]), text(&summary["lines_total"]), text(&summary["line_coverage"]));
+        println!("Branches: {}/{} ({}%)", text(&summary["branches_covered"]), text(&summary["branches_total"]), text(&summary["branch_coverage"]));
+    }
+
+    for (label, key) in [("problem", "problems"), ("warning", "warnings")] {
+        for message in validation[key].as_array().into_iter().flatten() {
+            eprintln!("{}: {}", label, text(message));
+        }
+    }
+    for path in validation["missing_files"].as_array().into_iter().flatten() {
+        eprintln!("  not found: {}", text(path));
+    }
+
+    println!("{}", if validation["valid"] == true { "Coverage report OK" } else { "Coverage report has problems" });
+}
@@ -0,0 +1,68 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::audit::Audit;
+use crate::auth::permissions::{Access, Permission};
+use crate::config::logger::LogFilter;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetLogLevelRequest {
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LogLevelResponse {
+    pub level: String,
+    pub configured: String,
+    pub format: String,
+}
+
+fn log_level_response(log_filter: &LogFilter) -> LogLevelResponse {
+    LogLevelResponse {
+        level: log_filter.current(),
+        configured: log_filter.configured().to_string(),
+        format: log_filter.format().to_string(),
+    }
+}
+
+pub async fn get_log_level(
+    access: Access,
+    log_filter: web::Data<LogFilter>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    Ok(HttpResponse::Ok().json(log_level_response(&log_filter)))
+}
+
+/// Replaces the log filter of this instance until it restarts; other replicas keep theirs.
+pub async fn set_log_level(
+    req: web::Json<SetLogLevelRequest>,
+    access: Access,
+    log_filter: web::Data<LogFilter>,
+    audit: Audit,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let level = req.level.trim();
+    if level.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "level must not be empty",
+            "suggestion": format!("Use the configured filter to go back: {}", log_filter.configured())
+        })));
+    }
+
+    audit.before(&log_level_response(&log_filter));
+    if let Err(e) = log_filter.set(level) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid log filter: {}", e),
+            "suggestion": "Use RUST_LOG syntax, e.g. info or info,sea_orm=warn,sonar_cute_api=debug"
+        })));
+    }
+
+    info!("Log filter changed to {}", level);
+    Ok(HttpResponse::Ok().json(log_level_response(&log_filter)))
+}
@@ -1,22 +1,110 @@
-use std::env;
-
-pub fn init() {
-    unsafe {
-        env::set_var(
-            "RUST_LOG",
-            "info,debug,actix_web=info,actix_web=debug,actix_server=info",
-        );
-        env::set_var(
-            "RUST_BACKTRACE",
-            "full",
-        );
-    }
-
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_target(false)
-        .with_level(true)
-        .with_ansi(true)
-        .compact()
-        .init();
+use crate::config::LogSettings;
+use std::io::IsTerminal;
+use std::sync::{Arc, RwLock};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+/// The active log filter, which can be replaced while the server runs. Changes apply to this
+/// process only and last until it restarts.
+#[derive(Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    current: Arc<RwLock<String>>,
+    configured: String,
+    format: String,
+}
+
+impl LogFilter {
+    /// A filter starting from `directives` and the layer it reloads, which must be installed for
+    /// [`LogFilter::set`] to take effect.
+    fn new(directives: String, format: String) -> (Self, reload::Layer<EnvFilter, Registry>) {
+        let filter = EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new("info"));
+        let (layer, handle) = reload::Layer::new(filter);
+        let log_filter = LogFilter {
+            handle,
+            current: Arc::new(RwLock::new(directives.clone())),
+            configured: directives,
+            format,
+        };
+        (log_filter, layer)
+    }
+
+    /// Filter directives in effect, e.g. `info,sea_orm=warn`.
+    pub fn current(&self) -> String {
+        self.current.read().map(|current| current.clone()).unwrap_or_default()
+    }
+
+    /// Filter directives from the configuration, applied at startup.
+    pub fn configured(&self) -> &str {
+        &self.configured
+    }
+
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())?;
+        if let Ok(mut current) = self.current.write() {
+            *current = directives.to_string();
+        }
+        Ok(())
+    }
+}
+
+/// Installs the global subscriber: compact text for terminals or one JSON object per line for
+/// log aggregation. The configuration check has already validated the filter.
+pub fn init(settings: &LogSettings) -> LogFilter {
+    let (log_filter, layer) = LogFilter::new(settings.directives(), settings.format.clone());
+    let registry = tracing_subscriber::registry().with(layer);
+
+    if settings.format == "json" {
+        registry
+            .with(fmt::layer().json().flatten_event(true).with_current_span(false).with_span_list(false))
+            .init();
+    } else {
+        // Color codes only help terminals; redirected output stays plain text
+        registry
+            .with(fmt::layer().compact().with_target(false).with_level(true).with_ansi(std::io::stdout().is_terminal()))
+            .init();
+    }
+
+    log_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_replaces_the_active_filter() {
+        let (log_filter, layer) = LogFilter::new("info".to_string(), "text".to_string());
+        let _subscriber = Registry::default().with(layer);
+
+        log_filter.set("debug,sea_orm=warn").unwrap();
+
+        assert_eq!(log_filter.current(), "debug,sea_orm=warn");
+        assert_eq!(log_filter.configured(), "info");
+    }
+
+    #[test]
+    fn set_keeps_the_filter_when_the_new_one_is_invalid() {
+        let (log_filter, layer) = LogFilter::new("info".to_string(), "json".to_string());
+        let _subscriber = Registry::default().with(layer);
+
+        assert!(log_filter.set("info,sea_orm=loud").is_err());
+        assert_eq!(log_filter.current(), "info");
+        assert_eq!(log_filter.format(), "json");
+    }
+
+    #[test]
+    fn set_fails_once_the_subscriber_is_gone() {
+        let (log_filter, layer) = LogFilter::new("info".to_string(), "text".to_string());
+        drop(layer);
+
+        assert!(log_filter.set("debug").is_err());
+        assert_eq!(log_filter.current(), "info");
+    }
 }
@@ -0,0 +1,71 @@
+use crate::coverage::{attr, attr_number, CoverageBuilder, ParsedReport};
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// Parses a Cobertura XML report. File names are relative to one of the `<source>` directories.
+pub fn parse<R: BufRead>(input: R) -> Result<ParsedReport> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut coverage = CoverageBuilder::default();
+    let mut source_roots = Vec::new();
+    let mut file: Option<String> = None;
+    let mut in_source = false;
+    // Methods repeat the lines of their class
+    let mut in_methods = false;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"source" => in_source = true,
+                b"class" => {
+                    let path = attr(&e, b"filename").unwrap_or_default();
+                    coverage.file(&path);
+                    file = Some(path);
+                }
+                b"methods" => in_methods = true,
+                b"line" if !in_methods => record_line(&mut coverage, file.as_deref(), &e),
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"class" => coverage.file(&attr(&e, b"filename").unwrap_or_default()),
+                b"line" if !in_methods => record_line(&mut coverage, file.as_deref(), &e),
+                _ => {}
+            },
+            Event::Text(text) if in_source => {
+                let root = text.unescape()?.trim().to_string();
+                if !root.is_empty() {
+                    source_roots.push(root);
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"source" => in_source = false,
+                b"class" => file = None,
+                b"methods" => in_methods = false,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(coverage.build(source_roots))
+}
+
+fn record_line(coverage: &mut CoverageBuilder, file: Option<&str>, line: &BytesStart) {
+    let Some(path) = file else {
+        return;
+    };
+
+    coverage.line(path, attr_number(line, b"number") as u32, attr_number(line, b"hits") > 0);
+
+    // condition-coverage="50% (1/2)"
+    if attr(line, b"branch").as_deref() == Some("true")
+        && let Some(conditions) = attr(line, b"condition-coverage")
+        && let Some((covered, total)) = conditions.split_once('(').and_then(|(_, rest)| rest.trim_end_matches(')').split_once('/'))
+    {
+        coverage.branches(path, total.trim().parse().unwrap_or(0), covered.trim().parse().unwrap_or(0));
+    }
+}
@@ -0,0 +1,51 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::coverage;
+use crate::database::service::ProjectService;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageValidationQuery {
+    /// Report to check instead of the project's `coverage_report_path`, relative to `project_path`.
+    pub report_path: Option<String>,
+}
+
+pub async fn validate_coverage_report(
+    path: web::Path<i32>,
+    query: web::Query<CoverageValidationQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+) -> Result<HttpResponse> {
+    let project = match project_service.get_project_by_id(path.into_inner()).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require(&project, Permission::ViewResults) {
+        return Ok(response);
+    }
+
+    let Some(report_path) = query.into_inner().report_path.or_else(|| project.coverage_report_path.clone()) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "No coverage report path configured for this project",
+            "suggestion": "Pass the report location as ?report_path=, relative to the project path"
+        })));
+    };
+
+    // Reports can be large; parse them off the async workers
+    match web::block(move || coverage::validate(&project, &report_path)).await {
+        Ok(validation) => Ok(HttpResponse::Ok().json(validation)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Coverage validation failed: {}", e)
+        }))),
+    }
+}
@@ -0,0 +1,57 @@
+use crate::coverage::{attr, attr_number, CoverageBuilder, ParsedReport};
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// Parses a JaCoCo XML report. Files are named `package/SourceFile.java`, relative to a source directory.
+pub fn parse<R: BufRead>(input: R) -> Result<ParsedReport> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut coverage = CoverageBuilder::default();
+    let mut package = String::new();
+    let mut file: Option<String> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"package" => package = attr(&e, b"name").unwrap_or_default(),
+                b"sourcefile" => {
+                    let path = source_path(&package, &attr(&e, b"name").unwrap_or_default());
+                    coverage.file(&path);
+                    file = Some(path);
+                }
+                _ => {}
+            },
+            Event::Empty(e) => match e.name().as_ref() {
+                b"line" => {
+                    if let Some(path) = &file {
+                        let (missed_branches, covered_branches) = (attr_number(&e, b"mb"), attr_number(&e, b"cb"));
+                        coverage.line(path, attr_number(&e, b"nr") as u32, attr_number(&e, b"ci") > 0);
+                        coverage.branches(path, missed_branches + covered_branches, covered_branches);
+                    }
+                }
+                b"sourcefile" => coverage.file(&source_path(&package, &attr(&e, b"name").unwrap_or_default())),
+                _ => {}
+            },
+            Event::End(e) => match e.name().as_ref() {
+                b"sourcefile" => file = None,
+                b"package" => package.clear(),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(coverage.build(Vec::new()))
+}
+
+fn source_path(package: &str, name: &str) -> String {
+    if package.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", package, name)
+    }
+}
@@ -0,0 +1,37 @@
+use crate::coverage::{CoverageBuilder, ParsedReport};
+use anyhow::Result;
+use std::io::BufRead;
+
+/// Parses an lcov tracefile. `SF:` paths are absolute or relative to the directory the tests ran in.
+pub fn parse<R: BufRead>(input: R) -> Result<ParsedReport> {
+    let mut coverage = CoverageBuilder::default();
+    let mut file: Option<String> = None;
+
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if let Some(path) = line.strip_prefix("SF:") {
+            coverage.file(path);
+            file = Some(path.to_string());
+        } else if line == "end_of_record" {
+            file = None;
+        } else if let Some(path) = &file {
+            if let Some(data) = line.strip_prefix("DA:") {
+                // line,hits[,checksum]
+                let mut fields = data.split(',');
+                if let (Some(number), Some(hits)) = (fields.next(), fields.next())
+                    && let Ok(number) = number.parse()
+                {
+                    coverage.line(path, number, hits.parse::<f64>().is_ok_and(|hits| hits > 0.0));
+                }
+            } else if let Some(data) = line.strip_prefix("BRDA:") {
+                // line,block,branch,taken; taken is "-" when the line never ran
+                let taken = data.rsplit(',').next().unwrap_or("-");
+                coverage.branches(path, 1, u64::from(taken != "-" && taken != "0"));
+            }
+        }
+    }
+
+    Ok(coverage.build(Vec::new()))
+}
@@ -0,0 +1,568 @@
+pub mod cobertura;
+pub mod handlers;
+pub mod jacoco;
+pub mod lcov;
+pub mod opencover;
+
+use crate::database::service::ProjectResponse;
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Files listed per kind of mismatch; the counts in the messages cover all of them.
+const MAX_LISTED: usize = 50;
+
+/// Where the usual build tools write their reports, suggested when the configured report is missing.
+const COMMON_REPORT_PATHS: &[&str] = &[
+    "build/reports/jacoco/test/jacocoTestReport.xml",
+    "target/site/jacoco/jacoco.xml",
+    "coverage/lcov.info",
+    "coverage/cobertura-coverage.xml",
+    "coverage.xml",
+    "coverage.opencover.xml",
+];
+
+/// Passed for reports SonarQube does not import for the project's language, which validation reports.
+const DEFAULT_PROPERTY: &str = "sonar.coverage.jacoco.xmlReportPaths";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverageFormat {
+    Jacoco,
+    Lcov,
+    Cobertura,
+    Opencover,
+}
+
+impl CoverageFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            CoverageFormat::Jacoco => "JaCoCo",
+            CoverageFormat::Lcov => "lcov",
+            CoverageFormat::Cobertura => "Cobertura",
+            CoverageFormat::Opencover => "OpenCover",
+        }
+    }
+
+    /// Guesses the format of a report that does not exist yet from its file name.
+    fn from_file_name(path: &str) -> Self {
+        let name = Path::new(path).file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.ends_with(".info") || name.contains("lcov") {
+            CoverageFormat::Lcov
+        } else if name.contains("opencover") {
+            CoverageFormat::Opencover
+        } else if name.contains("cobertura") || name == "coverage.xml" {
+            CoverageFormat::Cobertura
+        } else {
+            CoverageFormat::Jacoco
+        }
+    }
+
+    /// The scanner property SonarQube imports this kind of report from for `language`.
+    pub fn sonar_property(self, language: &str) -> Option<&'static str> {
+        match (self, language.to_lowercase().as_str()) {
+            (CoverageFormat::Jacoco, _) => Some("sonar.coverage.jacoco.xmlReportPaths"),
+            (CoverageFormat::Lcov, "javascript" | "typescript" | "js" | "ts") => Some("sonar.javascript.lcov.reportPaths"),
+            (CoverageFormat::Cobertura, "python" | "py") => Some("sonar.python.coverage.reportPaths"),
+            (CoverageFormat::Opencover, "csharp" | "cs" | "c#") => Some("sonar.cs.opencover.reportsPaths"),
+            (CoverageFormat::Opencover, "vbnet" | "vb") => Some("sonar.vbnet.opencover.reportsPaths"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileCoverage {
+    pub path: String,
+    pub lines_total: u64,
+    pub lines_covered: u64,
+    pub branches_total: u64,
+    pub branches_covered: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CoverageSummary {
+    pub files: usize,
+    pub lines_total: u64,
+    pub lines_covered: u64,
+    pub line_coverage: Option<f64>,
+    pub branches_total: u64,
+    pub branches_covered: u64,
+    pub branch_coverage: Option<f64>,
+}
+
+impl CoverageSummary {
+    fn from_files(files: &[FileCoverage]) -> Self {
+        let mut summary = CoverageSummary { files: files.len(), ..Default::default() };
+        for file in files {
+            summary.lines_total += file.lines_total;
+            summary.lines_covered += file.lines_covered;
+            summary.branches_total += file.branches_total;
+            summary.branches_covered += file.branches_covered;
+        }
+        summary.line_coverage = percent(summary.lines_covered, summary.lines_total);
+        summary.branch_coverage = percent(summary.branches_covered, summary.branches_total);
+        summary
+    }
+}
+
+fn percent(covered: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| (covered as f64 * 1000.0 / total as f64).round() / 10.0)
+}
+
+pub struct ParsedReport {
+    pub files: Vec<FileCoverage>,
+    /// Directories the file names are relative to, as listed by Cobertura's `<source>` elements.
+    pub source_roots: Vec<String>,
+}
+
+#[derive(Default)]
+struct FileLines {
+    lines: HashMap<u32, bool>,
+    branches_total: u64,
+    branches_covered: u64,
+}
+
+/// Collects line hits per file. Reports may list a file, or a line of it, more than once.
+#[derive(Default)]
+pub struct CoverageBuilder {
+    files: BTreeMap<String, FileLines>,
+}
+
+impl CoverageBuilder {
+    fn entry(&mut self, path: &str) -> &mut FileLines {
+        if !self.files.contains_key(path) {
+            self.files.insert(path.to_string(), FileLines::default());
+        }
+        self.files.get_mut(path).expect("inserted above")
+    }
+
+    /// Registers a file, even if it has no coverable lines.
+    pub fn file(&mut self, path: &str) {
+        self.entry(path);
+    }
+
+    pub fn line(&mut self, path: &str, line: u32, covered: bool) {
+        *self.entry(path).lines.entry(line).or_default() |= covered;
+    }
+
+    pub fn branches(&mut self, path: &str, total: u64, covered: u64) {
+        let file = self.entry(path);
+        file.branches_total += total;
+        file.branches_covered += covered;
+    }
+
+    pub fn build(self, source_roots: Vec<String>) -> ParsedReport {
+        let files = self
+            .files
+            .into_iter()
+            .map(|(path, file)| FileCoverage {
+                path,
+                lines_total: file.lines.len() as u64,
+                lines_covered: file.lines.values().filter(|covered| **covered).count() as u64,
+                branches_total: file.branches_total,
+                branches_covered: file.branches_covered,
+            })
+            .collect();
+        ParsedReport { files, source_roots }
+    }
+}
+
+/// Unescaped value of an XML attribute.
+pub fn attr(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Numeric XML attribute, 0 when missing or malformed.
+pub fn attr_number(element: &BytesStart, name: &[u8]) -> u64 {
+    attr(element, name).and_then(|v| v.trim().parse().ok()).unwrap_or(0)
+}
+
+/// Recognizes a report from its first bytes: lcov records, or the root element of the XML formats.
+pub fn detect_format(path: &Path) -> Result<Option<CoverageFormat>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(8192).read_to_end(&mut head)?;
+    let head = String::from_utf8_lossy(&head);
+    let text = head.trim_start_matches('\u{feff}').trim_start();
+
+    if !text.starts_with('<') {
+        let is_lcov = text.lines().any(|line| line.starts_with("SF:") || line.starts_with("TN:"));
+        return Ok(is_lcov.then_some(CoverageFormat::Lcov));
+    }
+
+    let mut reader = Reader::from_str(text);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                return Ok(match e.name().as_ref() {
+                    b"report" => Some(CoverageFormat::Jacoco),
+                    b"coverage" => Some(CoverageFormat::Cobertura),
+                    b"CoverageSession" => Some(CoverageFormat::Opencover),
+                    _ => None,
+                });
+            }
+            Ok(Event::Eof) | Err(_) => return Ok(None),
+            Ok(_) => {}
+        }
+    }
+}
+
+/// The scanner property the generated command passes a project's coverage report as. The format
+/// is read from the report, or guessed from its name while it does not exist yet, since the
+/// command runs the tests that write it.
+pub fn report_property(project: &ProjectResponse, report_path: &str) -> &'static str {
+    let format = detect_format(&Path::new(&project.project_path).join(report_path))
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| CoverageFormat::from_file_name(report_path));
+    format.sonar_property(&project.language).unwrap_or(DEFAULT_PROPERTY)
+}
+
+pub fn parse(path: &Path, format: CoverageFormat) -> Result<ParsedReport> {
+    let input = BufReader::new(File::open(path)?);
+    match format {
+        CoverageFormat::Jacoco => jacoco::parse(input),
+        CoverageFormat::Lcov => lcov::parse(input),
+        CoverageFormat::Cobertura => cobertura::parse(input),
+        CoverageFormat::Opencover => opencover::parse(input),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoveredFile {
+    #[serde(flatten)]
+    pub coverage: FileCoverage,
+    /// Location of the file relative to `project_path`, when it exists.
+    pub resolved_path: Option<String>,
+    /// Whether the file is under `sources_path`, the only files SonarQube imports coverage for.
+    pub in_sources: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoverageValidation {
+    pub valid: bool,
+    pub report_path: String,
+    pub format: Option<CoverageFormat>,
+    pub sonar_property: Option<&'static str>,
+    pub summary: Option<CoverageSummary>,
+    pub files: Vec<CoveredFile>,
+    pub missing_files: Vec<String>,
+    pub outside_sources: Vec<String>,
+    pub problems: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks a project's coverage report the way the scanner will read it: that it exists under
+/// `project_path`, is in a format SonarQube imports for the project's language, and that the
+/// files it covers exist under `sources_path`.
+pub fn validate(project: &ProjectResponse, report_path: &str) -> CoverageValidation {
+    let mut validation = CoverageValidation {
+        valid: false,
+        report_path: report_path.to_string(),
+        format: None,
+        sonar_property: None,
+        summary: None,
+        files: Vec::new(),
+        missing_files: Vec::new(),
+        outside_sources: Vec::new(),
+        problems: Vec::new(),
+        warnings: Vec::new(),
+    };
+    check(project, report_path, &mut validation);
+    validation.valid = validation.problems.is_empty();
+    validation
+}
+
+fn check(project: &ProjectResponse, report_path: &str, validation: &mut CoverageValidation) {
+    let Ok(root) = Path::new(&project.project_path).canonicalize() else {
+        validation.problems.push(format!("Project path {} does not exist on the SonarCute host", project.project_path));
+        return;
+    };
+
+    let report = match root.join(report_path).canonicalize() {
+        Ok(report) if report.starts_with(&root) && report.is_file() => report,
+        Ok(report) if !report.starts_with(&root) => {
+            validation.problems.push(format!("Coverage report {} is outside the project path {}", report_path, project.project_path));
+            return;
+        }
+        _ => {
+            let found: Vec<&str> = COMMON_REPORT_PATHS.iter().copied().filter(|p| root.join(p).is_file()).collect();
+            let mut problem = format!("Coverage report {} not found in {}; run the tests with coverage enabled first", report_path, project.project_path);
+            if !found.is_empty() {
+                problem = format!("{}. Reports found at: {}", problem, found.join(", "));
+            }
+            validation.problems.push(problem);
+            return;
+        }
+    };
+
+    let format = match detect_format(&report) {
+        Ok(Some(format)) => format,
+        Ok(None) => {
+            validation.problems.push("Unrecognized coverage report; expected JaCoCo XML, lcov, Cobertura XML or OpenCover XML".to_string());
+            return;
+        }
+        Err(e) => {
+            validation.problems.push(format!("Cannot read coverage report: {}", e));
+            return;
+        }
+    };
+    validation.format = Some(format);
+
+    // The generated command passes the report as this property too
+    validation.sonar_property = format.sonar_property(&project.language);
+    if validation.sonar_property.is_none() {
+        validation.problems.push(format!("SonarQube does not import {} reports for {} projects", format.name(), project.language));
+    }
+
+    let parsed = match parse(&report, format) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            validation.problems.push(format!("Failed to parse {} report: {}", format.name(), e));
+            return;
+        }
+    };
+
+    let summary = CoverageSummary::from_files(&parsed.files);
+    if parsed.files.is_empty() {
+        validation.problems.push("The coverage report lists no files".to_string());
+    } else if summary.lines_covered == 0 {
+        validation.warnings.push("The coverage report has no covered lines; check that the tests ran with coverage enabled".to_string());
+    }
+    validation.summary = Some(summary);
+
+    let sources: Vec<PathBuf> = project.sources_path.split(',').map(|s| relative_dir(s.trim())).collect();
+    let mut missing = 0;
+    let mut missing_absolute = 0;
+    let mut outside = 0;
+
+    for file in parsed.files {
+        let resolved = resolve_source(&root, &sources, &parsed.source_roots, format, &file.path);
+        let in_sources = resolved.as_ref().is_some_and(|path| sources.iter().any(|source| path.starts_with(source)));
+
+        match &resolved {
+            None => {
+                missing += 1;
+                if Path::new(&file.path).is_absolute() {
+                    missing_absolute += 1;
+                }
+                if validation.missing_files.len() < MAX_LISTED {
+                    validation.missing_files.push(file.path.clone());
+                }
+            }
+            Some(_) if !in_sources => {
+                outside += 1;
+                if validation.outside_sources.len() < MAX_LISTED {
+                    validation.outside_sources.push(file.path.clone());
+                }
+            }
+            Some(_) => {}
+        }
+
+        validation.files.push(CoveredFile {
+            coverage: file,
+            resolved_path: resolved.map(|path| path.to_string_lossy().into_owned()),
+            in_sources,
+        });
+    }
+
+    let total = validation.files.len();
+    if missing > 0 {
+        let mut message = format!("{} of {} files in the report were not found under {}", missing, total, project.project_path);
+        if missing_absolute == missing {
+            message.push_str("; the report uses absolute paths from another location, e.g. a CI workspace or container");
+        } else if format == CoverageFormat::Jacoco {
+            message = format!("{}; JaCoCo paths are relative to sources_path ({})", message, project.sources_path);
+        }
+
+        if missing == total {
+            validation.problems.push(format!("{}. SonarQube will show 0% coverage", message));
+        } else {
+            validation.warnings.push(format!("{}. Their coverage is ignored", message));
+        }
+    }
+    if outside > 0 {
+        validation.warnings.push(format!(
+            "{} covered file(s) are outside sources_path ({}); SonarQube ignores their coverage",
+            outside, project.sources_path
+        ));
+    }
+}
+
+/// A directory from `sources_path`, without `./` so that it compares with resolved paths.
+fn relative_dir(dir: &str) -> PathBuf {
+    Path::new(dir).components().filter(|c| !matches!(c, Component::CurDir)).collect()
+}
+
+/// Finds a file named in the report and returns its path relative to `root`. JaCoCo names files
+/// relative to a source directory; the other formats use paths relative to the directory the
+/// tests ran in, to a `<source>` root, or absolute paths.
+fn resolve_source(root: &Path, sources: &[PathBuf], report_roots: &[String], format: CoverageFormat, path: &str) -> Option<PathBuf> {
+    let file = Path::new(path);
+    let candidates: Vec<PathBuf> = if file.is_absolute() {
+        vec![file.to_path_buf()]
+    } else if format == CoverageFormat::Jacoco {
+        sources.iter().map(|source| root.join(source).join(file)).collect()
+    } else {
+        std::iter::once(root.join(file))
+            .chain(report_roots.iter().map(|dir| root.join(dir).join(file)))
+            .chain(sources.iter().map(|source| root.join(source).join(file)))
+            .collect()
+    };
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.is_file())
+        .filter_map(|candidate| candidate.canonicalize().ok())
+        .find_map(|candidate| candidate.strip_prefix(root).ok().map(Path::to_path_buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project(root: &Path, language: &str, sources_path: &str) -> ProjectResponse {
+        let now = chrono::Utc::now().naive_utc();
+        ProjectResponse {
+            id: 1,
+            project_key: "demo".to_string(),
+            project_name: "Demo".to_string(),
+            project_path: root.to_string_lossy().into_owned(),
+            sonar_token: String::new(),
+            sonar_host_url: "http://localhost:9000".to_string(),
+            language: language.to_string(),
+            sources_path: sources_path.to_string(),
+            tests_path: "src/test".to_string(),
+            coverage_report_path: None,
+            team_id: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn resolves_jacoco_files_against_the_source_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "src/main/java/com/acme/Order.java", "class Order {}");
+        write(
+            dir.path(),
+            "build/jacoco.xml",
+            r#"<?xml version="1.0"?><report name="demo"><package name="com/acme"><sourcefile name="Order.java"><line nr="3" mi="0" ci="2" mb="1" cb="1"/><line nr="4" mi="1" ci="0" mb="0" cb="0"/></sourcefile></package></report>"#,
+        );
+
+        let validation = validate(&project(dir.path(), "java", "src/main/java"), "build/jacoco.xml");
+
+        assert!(validation.valid, "{:?}", validation.problems);
+        assert_eq!(validation.format, Some(CoverageFormat::Jacoco));
+        let file = &validation.files[0];
+        assert_eq!(file.coverage.path, "com/acme/Order.java");
+        assert_eq!(file.resolved_path.as_deref(), Some("src/main/java/com/acme/Order.java"));
+        assert!(file.in_sources);
+        assert_eq!((file.coverage.lines_total, file.coverage.lines_covered), (2, 1));
+        assert_eq!((file.coverage.branches_total, file.coverage.branches_covered), (2, 1));
+    }
+
+    #[test]
+    fn jacoco_files_not_under_sources_path_are_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "src/main/java/com/acme/Order.java", "class Order {}");
+        write(
+            dir.path(),
+            "jacoco.xml",
+            r#"<report name="demo"><package name="com/acme"><sourcefile name="Order.java"><line nr="3" mi="0" ci="1" mb="0" cb="0"/></sourcefile></package></report>"#,
+        );
+
+        let validation = validate(&project(dir.path(), "java", "src/main"), "jacoco.xml");
+
+        assert!(!validation.valid);
+        assert_eq!(validation.missing_files, vec!["com/acme/Order.java"]);
+        assert!(validation.problems[0].contains("JaCoCo paths are relative to sources_path"));
+    }
+
+    #[test]
+    fn lcov_lines_and_branches_that_never_ran_count_as_uncovered() {
+        let report = "TN:\nSF:src/app.ts\nDA:1,4\nDA:2,0\nDA:3,-\nBRDA:1,0,0,2\nBRDA:2,0,0,-\nBRDA:2,0,1,0\nend_of_record\n";
+
+        let parsed = lcov::parse(report.as_bytes()).unwrap();
+
+        let file = &parsed.files[0];
+        assert_eq!(file.path, "src/app.ts");
+        assert_eq!((file.lines_total, file.lines_covered), (3, 1));
+        assert_eq!((file.branches_total, file.branches_covered), (3, 1));
+    }
+
+    #[test]
+    fn merges_lines_listed_more_than_once() {
+        let report = "SF:src/app.ts\nDA:1,0\nDA:2,0\nend_of_record\nSF:src/app.ts\nDA:1,3\nDA:2,0\nend_of_record\n";
+
+        let parsed = lcov::parse(report.as_bytes()).unwrap();
+
+        assert_eq!(parsed.files.len(), 1);
+        assert_eq!((parsed.files[0].lines_total, parsed.files[0].lines_covered), (2, 1));
+    }
+
+    #[test]
+    fn resolves_cobertura_files_against_source_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "lib/shop/order.py", "");
+        write(
+            dir.path(),
+            "coverage.xml",
+            r#"<?xml version="1.0"?>
+<coverage version="7.4"><sources><source>lib</source></sources><packages><package name="shop"><classes>
+<class name="order.py" filename="shop/order.py"><methods><method name="total"><lines><line number="1" hits="1"/></lines></method></methods>
+<lines><line number="1" hits="1"/><line number="2" hits="0" branch="true" condition-coverage="50% (1/2)"/></lines></class>
+</classes></package></packages></coverage>"#,
+        );
+
+        let validation = validate(&project(dir.path(), "python", "lib"), "coverage.xml");
+
+        assert!(validation.valid, "{:?}", validation.problems);
+        assert_eq!(validation.sonar_property, Some("sonar.python.coverage.reportPaths"));
+        let file = &validation.files[0];
+        assert_eq!(file.resolved_path.as_deref(), Some("lib/shop/order.py"));
+        assert_eq!((file.coverage.lines_total, file.coverage.lines_covered), (2, 1));
+        assert_eq!((file.coverage.branches_total, file.coverage.branches_covered), (2, 1));
+    }
+
+    #[test]
+    fn rejects_a_report_outside_the_project_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("project")).unwrap();
+        write(dir.path(), "coverage/lcov.info", "SF:src/app.ts\nDA:1,1\nend_of_record\n");
+
+        let validation = validate(&project(&dir.path().join("project"), "typescript", "src"), "../coverage/lcov.info");
+
+        assert!(!validation.valid);
+        assert_eq!(validation.format, None);
+        assert!(validation.problems[0].contains("outside the project path"), "{:?}", validation.problems);
+    }
+
+    #[test]
+    fn passes_the_report_as_the_property_for_its_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let typescript = project(dir.path(), "typescript", "src");
+        assert_eq!(report_property(&typescript, "coverage/lcov.info"), "sonar.javascript.lcov.reportPaths");
+
+        // An existing report is recognized by its contents rather than its name
+        write(dir.path(), "coverage.xml", r#"<report name="demo"></report>"#);
+        assert_eq!(report_property(&project(dir.path(), "java", "src"), "coverage.xml"), "sonar.coverage.jacoco.xmlReportPaths");
+        assert_eq!(report_property(&project(dir.path(), "python", "src"), "missing/coverage.xml"), "sonar.python.coverage.reportPaths");
+    }
+}
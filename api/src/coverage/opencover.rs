@@ -0,0 +1,55 @@
+use crate::coverage::{attr, attr_number, CoverageBuilder, ParsedReport};
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+/// Parses an OpenCover XML report. Each module declares its files with absolute paths and ids,
+/// which sequence and branch points refer to.
+pub fn parse<R: BufRead>(input: R) -> Result<ParsedReport> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut coverage = CoverageBuilder::default();
+    // File ids are only unique within a module
+    let mut module_files: HashMap<u64, String> = HashMap::new();
+    let mut method_file: Option<u64> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"Module" => module_files.clear(),
+                b"File" => {
+                    if let Some(path) = attr(&e, b"fullPath") {
+                        coverage.file(&path);
+                        module_files.insert(attr_number(&e, b"uid"), path);
+                    }
+                }
+                b"Method" => method_file = None,
+                b"FileRef" => method_file = Some(attr_number(&e, b"uid")),
+                b"SequencePoint" => {
+                    if let Some(path) = point_file(&e, &module_files, method_file) {
+                        coverage.line(path, attr_number(&e, b"sl") as u32, attr_number(&e, b"vc") > 0);
+                    }
+                }
+                b"BranchPoint" => {
+                    if let Some(path) = point_file(&e, &module_files, method_file) {
+                        coverage.branches(path, 1, u64::from(attr_number(&e, b"vc") > 0));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(coverage.build(Vec::new()))
+}
+
+/// The file of a sequence or branch point: its `fileid`, or the file of the enclosing method.
+fn point_file<'a>(point: &BytesStart, module_files: &'a HashMap<u64, String>, method_file: Option<u64>) -> Option<&'a str> {
+    let id = attr(point, b"fileid").and_then(|id| id.parse().ok()).or(method_file)?;
+    module_files.get(&id).map(String::as_str)
+}
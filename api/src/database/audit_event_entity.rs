@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub request_id: String,
+    pub actor_user_id: Option<i32>,
+    pub actor_username: Option<String>,
+    pub auth_method: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub outcome: String,
+    pub status_code: i32,
+    pub error: Option<String>,
+    pub before: Option<Json>,
+    pub after: Option<Json>,
+    pub client_ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
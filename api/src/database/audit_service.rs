@@ -0,0 +1,172 @@
+use crate::database::audit_event_entity::ActiveModel as AuditEventActiveModel;
+use crate::database::audit_event_entity::Column as AuditEventColumn;
+use crate::database::audit_event_entity::Entity as AuditEventEntity;
+use crate::database::audit_event_entity::Model as AuditEventModel;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct NewAuditEvent {
+    pub request_id: String,
+    pub actor_user_id: Option<i32>,
+    pub actor_username: Option<String>,
+    pub auth_method: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub outcome: String,
+    pub status_code: i32,
+    pub error: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub client_ip: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEventResponse {
+    pub id: i64,
+    pub request_id: String,
+    pub actor_user_id: Option<i32>,
+    pub actor_username: Option<String>,
+    pub auth_method: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub outcome: String,
+    pub status_code: i32,
+    pub error: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub client_ip: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditEventModel> for AuditEventResponse {
+    fn from(model: AuditEventModel) -> Self {
+        Self {
+            id: model.id,
+            request_id: model.request_id,
+            actor_user_id: model.actor_user_id,
+            actor_username: model.actor_username,
+            auth_method: model.auth_method,
+            action: model.action,
+            target_type: model.target_type,
+            target_id: model.target_id,
+            method: model.method,
+            path: model.path,
+            outcome: model.outcome,
+            status_code: model.status_code,
+            error: model.error,
+            before: model.before,
+            after: model.after,
+            client_ip: model.client_ip,
+            created_at: model.created_at,
+        }
+    }
+}
+
+/// Filters of the audit log; unset fields match every event.
+#[derive(Debug)]
+pub struct AuditEventFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub outcome: Option<String>,
+    pub request_id: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+pub struct AuditService {
+    db: DatabaseConnection,
+}
+
+impl AuditService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(&self, event: NewAuditEvent) -> Result<(), DbErr> {
+        let model = AuditEventActiveModel {
+            request_id: Set(event.request_id),
+            actor_user_id: Set(event.actor_user_id),
+            actor_username: Set(event.actor_username),
+            auth_method: Set(event.auth_method),
+            action: Set(event.action),
+            target_type: Set(event.target_type),
+            target_id: Set(event.target_id),
+            method: Set(event.method),
+            path: Set(event.path),
+            outcome: Set(event.outcome),
+            status_code: Set(event.status_code),
+            error: Set(event.error),
+            before: Set(event.before),
+            after: Set(event.after),
+            client_ip: Set(event.client_ip),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        model.insert(&self.db).await?;
+        Ok(())
+    }
+
+    /// Matching events, newest first.
+    pub async fn get_events(&self, filter: &AuditEventFilter, limit: u64, offset: u64) -> Result<Vec<AuditEventResponse>, DbErr> {
+        let mut query = AuditEventEntity::find();
+        if let Some(actor) = &filter.actor {
+            query = query.filter(AuditEventColumn::ActorUsername.eq(actor.as_str()));
+        }
+        if let Some(action) = &filter.action {
+            // "project" matches every project action, "project.delete" only that one
+            query = if action.contains('.') {
+                query.filter(AuditEventColumn::Action.eq(action.as_str()))
+            } else {
+                query.filter(AuditEventColumn::Action.starts_with(format!("{}.", action)))
+            };
+        }
+        if let Some(target_type) = &filter.target_type {
+            query = query.filter(AuditEventColumn::TargetType.eq(target_type.as_str()));
+        }
+        if let Some(target_id) = &filter.target_id {
+            query = query.filter(AuditEventColumn::TargetId.eq(target_id.as_str()));
+        }
+        if let Some(outcome) = &filter.outcome {
+            query = query.filter(AuditEventColumn::Outcome.eq(outcome.to_uppercase()));
+        }
+        if let Some(request_id) = &filter.request_id {
+            query = query.filter(AuditEventColumn::RequestId.eq(request_id.as_str()));
+        }
+        if let Some(since) = filter.since {
+            query = query.filter(AuditEventColumn::CreatedAt.gte(since));
+        }
+        if let Some(until) = filter.until {
+            query = query.filter(AuditEventColumn::CreatedAt.lt(until));
+        }
+
+        let events = query
+            .order_by_desc(AuditEventColumn::CreatedAt)
+            .order_by_desc(AuditEventColumn::Id)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db)
+            .await?;
+        Ok(events.into_iter().map(AuditEventResponse::from).collect())
+    }
+
+    /// Deletes events recorded before `cutoff`, returning how many were removed.
+    pub async fn delete_before(&self, cutoff: NaiveDateTime) -> Result<u64, DbErr> {
+        let result = AuditEventEntity::delete_many()
+            .filter(AuditEventColumn::CreatedAt.lt(cutoff))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
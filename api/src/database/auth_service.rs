@@ -0,0 +1,385 @@
+use crate::database::api_key_entity::ActiveModel as ApiKeyActiveModel;
+use crate::database::api_key_entity::Column as ApiKeyColumn;
+use crate::database::api_key_entity::Entity as ApiKeyEntity;
+use crate::database::api_key_entity::Model as ApiKeyModel;
+use crate::database::user_entity::ActiveModel as UserActiveModel;
+use crate::database::user_entity::Column as UserColumn;
+use crate::database::user_entity::Entity as UserEntity;
+use crate::database::user_entity::Model as UserModel;
+use crate::database::user_session_entity::ActiveModel as SessionActiveModel;
+use crate::database::user_session_entity::Column as SessionColumn;
+use crate::database::user_session_entity::Entity as SessionEntity;
+use crate::database::user_session_entity::Model as SessionModel;
+use chrono::{Duration, NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, Value};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+/// How often `last_used_at` of an API key is written; keys used by CI call the API in bursts.
+const API_KEY_TOUCH_INTERVAL_SECS: i64 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    /// Role on every project; without one the user only sees their teams' projects
+    pub global_role: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateUserRequest {
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub password: Option<String>,
+    /// Required to change your own password
+    pub current_password: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetUserRoleRequest {
+    /// `null` removes the global role, leaving only team access
+    pub global_role: Option<String>,
+}
+
+/// A user to store; `password_hash` is `None` for users signing in through OIDC.
+#[derive(Debug)]
+pub struct NewUser {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub password_hash: Option<String>,
+    pub auth_provider: String, // "LOCAL" or "OIDC"
+    pub external_id: Option<String>,
+    pub global_role: Option<String>,
+}
+
+/// Changes to a user; `password_hash` is already hashed.
+#[derive(Debug, Default)]
+pub struct UserChanges {
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub password_hash: Option<String>,
+    pub active: Option<bool>,
+    /// Session and API key left valid when the password changes, those of the user changing it
+    pub keep_session_id: Option<i32>,
+    pub keep_api_key_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserResponse {
+    pub id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    pub auth_provider: String,
+    pub active: bool,
+    pub global_role: Option<String>,
+    pub last_login_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<UserModel> for UserResponse {
+    fn from(model: UserModel) -> Self {
+        Self {
+            id: model.id,
+            username: model.username,
+            display_name: model.display_name,
+            email: model.email,
+            auth_provider: model.auth_provider,
+            active: model.active,
+            global_role: model.global_role,
+            last_login_at: model.last_login_at,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Days until the key expires; keys without expiry stay valid until revoked
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    /// First characters of the key, to tell keys apart; the key itself is only shown once
+    pub key_prefix: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<ApiKeyModel> for ApiKeyResponse {
+    fn from(model: ApiKeyModel) -> Self {
+        Self {
+            id: model.id,
+            user_id: model.user_id,
+            name: model.name,
+            key_prefix: model.key_prefix,
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at,
+            revoked_at: model.revoked_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService {
+    db: DatabaseConnection,
+}
+
+impl AuthService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn count_users(&self) -> Result<u64, DbErr> {
+        UserEntity::find().count(&self.db).await
+    }
+
+    pub async fn get_users(&self) -> Result<Vec<UserResponse>, DbErr> {
+        let users = UserEntity::find().order_by_asc(UserColumn::Username).all(&self.db).await?;
+        Ok(users.into_iter().map(UserResponse::from).collect())
+    }
+
+    pub async fn get_user_by_id(&self, user_id: i32) -> Result<Option<UserModel>, DbErr> {
+        UserEntity::find_by_id(user_id).one(&self.db).await
+    }
+
+    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<UserModel>, DbErr> {
+        UserEntity::find().filter(UserColumn::Username.eq(username)).one(&self.db).await
+    }
+
+    pub async fn get_user_by_external_id(&self, auth_provider: &str, external_id: &str) -> Result<Option<UserModel>, DbErr> {
+        UserEntity::find()
+            .filter(UserColumn::AuthProvider.eq(auth_provider))
+            .filter(UserColumn::ExternalId.eq(external_id))
+            .one(&self.db)
+            .await
+    }
+
+    pub async fn create_user(&self, user: NewUser) -> Result<UserModel, DbErr> {
+        let now = Utc::now().naive_utc();
+        let model = UserActiveModel {
+            username: Set(user.username),
+            display_name: Set(user.display_name),
+            email: Set(user.email),
+            password_hash: Set(user.password_hash),
+            auth_provider: Set(user.auth_provider),
+            external_id: Set(user.external_id),
+            active: Set(true),
+            global_role: Set(user.global_role),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+
+        model.insert(&self.db).await
+    }
+
+    /// Applies `changes`. Deactivating a user ends their sessions; changing their password revokes
+    /// their sessions and API keys, except the ones in `changes`.
+    pub async fn update_user(&self, user_id: i32, changes: UserChanges) -> Result<Option<UserModel>, DbErr> {
+        let Some(user) = UserEntity::find_by_id(user_id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let password_changed = changes.password_hash.is_some();
+        let deactivated = changes.active == Some(false);
+
+        let mut user: UserActiveModel = user.into();
+        if let Some(display_name) = changes.display_name {
+            user.display_name = Set(Some(display_name));
+        }
+        if let Some(email) = changes.email {
+            user.email = Set(Some(email));
+        }
+        if let Some(password_hash) = changes.password_hash {
+            user.password_hash = Set(Some(password_hash));
+        }
+        if let Some(active) = changes.active {
+            user.active = Set(active);
+        }
+        user.updated_at = Set(Utc::now().naive_utc());
+        let result = user.update(&self.db).await?;
+
+        if deactivated {
+            self.revoke_user_sessions(user_id, None).await?;
+        } else if password_changed {
+            self.revoke_user_sessions(user_id, changes.keep_session_id).await?;
+            self.revoke_user_api_keys(user_id, changes.keep_api_key_id).await?;
+        }
+
+        Ok(Some(result))
+    }
+
+    pub async fn set_global_role(&self, user_id: i32, global_role: Option<String>) -> Result<Option<UserModel>, DbErr> {
+        let Some(user) = UserEntity::find_by_id(user_id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let mut user: UserActiveModel = user.into();
+        user.global_role = Set(global_role);
+        user.updated_at = Set(Utc::now().naive_utc());
+        Ok(Some(user.update(&self.db).await?))
+    }
+
+    pub async fn delete_user(&self, user_id: i32) -> Result<bool, DbErr> {
+        let result = UserEntity::delete_by_id(user_id).exec(&self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn record_login(&self, user_id: i32) -> Result<(), DbErr> {
+        UserEntity::update_many()
+            .col_expr(UserColumn::LastLoginAt, Expr::value(Value::from(Utc::now().naive_utc())))
+            .filter(UserColumn::Id.eq(user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Starts a session, dropping the user's sessions that already expired.
+    pub async fn create_session(&self, user_id: i32, auth_provider: &str, expires_at: NaiveDateTime) -> Result<SessionModel, DbErr> {
+        let now = Utc::now().naive_utc();
+        SessionEntity::delete_many()
+            .filter(SessionColumn::UserId.eq(user_id))
+            .filter(SessionColumn::ExpiresAt.lt(now))
+            .exec(&self.db)
+            .await?;
+
+        let session = SessionActiveModel {
+            user_id: Set(user_id),
+            auth_provider: Set(auth_provider.to_string()),
+            created_at: Set(now),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        };
+
+        session.insert(&self.db).await
+    }
+
+    pub async fn get_session(&self, session_id: i32) -> Result<Option<SessionModel>, DbErr> {
+        SessionEntity::find_by_id(session_id).one(&self.db).await
+    }
+
+    pub async fn revoke_session(&self, session_id: i32) -> Result<(), DbErr> {
+        SessionEntity::update_many()
+            .col_expr(SessionColumn::RevokedAt, Expr::value(Value::from(Utc::now().naive_utc())))
+            .filter(SessionColumn::Id.eq(session_id))
+            .filter(SessionColumn::RevokedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Revokes the user's sessions, except `keep`.
+    pub async fn revoke_user_sessions(&self, user_id: i32, keep: Option<i32>) -> Result<(), DbErr> {
+        let mut update = SessionEntity::update_many()
+            .col_expr(SessionColumn::RevokedAt, Expr::value(Value::from(Utc::now().naive_utc())))
+            .filter(SessionColumn::UserId.eq(user_id))
+            .filter(SessionColumn::RevokedAt.is_null());
+        if let Some(keep) = keep {
+            update = update.filter(SessionColumn::Id.ne(keep));
+        }
+        update.exec(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn create_api_key(
+        &self,
+        user_id: i32,
+        name: String,
+        key_prefix: String,
+        key_hash: String,
+        expires_at: Option<NaiveDateTime>,
+    ) -> Result<ApiKeyResponse, DbErr> {
+        let api_key = ApiKeyActiveModel {
+            user_id: Set(user_id),
+            name: Set(name),
+            key_prefix: Set(key_prefix),
+            key_hash: Set(key_hash),
+            expires_at: Set(expires_at),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        let result = api_key.insert(&self.db).await?;
+        Ok(ApiKeyResponse::from(result))
+    }
+
+    pub async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKeyModel>, DbErr> {
+        ApiKeyEntity::find().filter(ApiKeyColumn::KeyHash.eq(key_hash)).one(&self.db).await
+    }
+
+    pub async fn get_api_key(&self, key_id: i32) -> Result<Option<ApiKeyModel>, DbErr> {
+        ApiKeyEntity::find_by_id(key_id).one(&self.db).await
+    }
+
+    pub async fn get_api_keys(&self, user_id: i32) -> Result<Vec<ApiKeyResponse>, DbErr> {
+        let keys = ApiKeyEntity::find()
+            .filter(ApiKeyColumn::UserId.eq(user_id))
+            .order_by_desc(ApiKeyColumn::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(keys.into_iter().map(ApiKeyResponse::from).collect())
+    }
+
+    /// Revokes one of the user's keys. Returns `None` when the user has no such key.
+    pub async fn revoke_api_key(&self, user_id: i32, key_id: i32) -> Result<Option<ApiKeyResponse>, DbErr> {
+        let key = ApiKeyEntity::find_by_id(key_id)
+            .filter(ApiKeyColumn::UserId.eq(user_id))
+            .one(&self.db)
+            .await?;
+
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        if key.revoked_at.is_some() {
+            return Ok(Some(ApiKeyResponse::from(key)));
+        }
+
+        let mut key: ApiKeyActiveModel = key.into();
+        key.revoked_at = Set(Some(Utc::now().naive_utc()));
+        let result = key.update(&self.db).await?;
+        Ok(Some(ApiKeyResponse::from(result)))
+    }
+
+    /// Revokes the user's API keys, except `keep`.
+    pub async fn revoke_user_api_keys(&self, user_id: i32, keep: Option<i32>) -> Result<(), DbErr> {
+        let mut update = ApiKeyEntity::update_many()
+            .col_expr(ApiKeyColumn::RevokedAt, Expr::value(Value::from(Utc::now().naive_utc())))
+            .filter(ApiKeyColumn::UserId.eq(user_id))
+            .filter(ApiKeyColumn::RevokedAt.is_null());
+        if let Some(keep) = keep {
+            update = update.filter(ApiKeyColumn::Id.ne(keep));
+        }
+        update.exec(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn touch_api_key(&self, key_id: i32) -> Result<(), DbErr> {
+        let now = Utc::now().naive_utc();
+        ApiKeyEntity::update_many()
+            .col_expr(ApiKeyColumn::LastUsedAt, Expr::value(Value::from(now)))
+            .filter(ApiKeyColumn::Id.eq(key_id))
+            .filter(
+                Condition::any()
+                    .add(ApiKeyColumn::LastUsedAt.is_null())
+                    .add(ApiKeyColumn::LastUsedAt.lt(now - Duration::seconds(API_KEY_TOUCH_INTERVAL_SECS))),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@ pub struct Model {
     pub sources_path: String,
     pub tests_path: String,
     pub coverage_report_path: Option<String>,
+    pub team_id: Option<i32>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -0,0 +1,263 @@
+use crate::database::scheduled_job_entity::ActiveModel as JobActiveModel;
+use crate::database::scheduled_job_entity::Column as JobColumn;
+use crate::database::scheduled_job_entity::Entity as JobEntity;
+use crate::database::scheduled_job_entity::Model as JobModel;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, Value};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateJobRequest {
+    pub cron_expression: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobResponse {
+    pub id: i32,
+    pub name: String,
+    pub job_type: String,
+    pub cron_expression: String,
+    pub enabled: bool,
+    pub next_run_at: Option<NaiveDateTime>,
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_status: Option<String>,
+    pub last_message: Option<String>,
+    pub last_result: Option<serde_json::Value>,
+    pub last_duration_ms: Option<i64>,
+    pub locked_by: Option<String>,
+    pub locked_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<JobModel> for JobResponse {
+    fn from(model: JobModel) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            job_type: model.job_type,
+            cron_expression: model.cron_expression,
+            enabled: model.enabled,
+            next_run_at: model.next_run_at,
+            last_run_at: model.last_run_at,
+            last_status: model.last_status,
+            last_message: model.last_message,
+            last_result: model.last_result,
+            last_duration_ms: model.last_duration_ms,
+            locked_by: model.locked_by,
+            locked_until: model.locked_until,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JobRunOutcome {
+    pub status: String, // "SUCCESS" or "FAILED"
+    pub message: String,
+    pub result: Option<serde_json::Value>,
+    pub started_at: NaiveDateTime,
+    pub duration_ms: i64,
+    pub next_run_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone)]
+pub struct JobService {
+    db: DatabaseConnection,
+}
+
+impl JobService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_all_jobs(&self) -> Result<Vec<JobResponse>, DbErr> {
+        let jobs = JobEntity::find()
+            .order_by_asc(JobColumn::Name)
+            .all(&self.db)
+            .await?;
+        Ok(jobs.into_iter().map(JobResponse::from).collect())
+    }
+
+    pub async fn get_job_by_name(&self, name: &str) -> Result<Option<JobResponse>, DbErr> {
+        let job = JobEntity::find()
+            .filter(JobColumn::Name.eq(name))
+            .one(&self.db)
+            .await?;
+        Ok(job.map(JobResponse::from))
+    }
+
+    pub async fn update_job(&self, name: &str, request: UpdateJobRequest) -> Result<Option<JobResponse>, DbErr> {
+        let job = JobEntity::find()
+            .filter(JobColumn::Name.eq(name))
+            .one(&self.db)
+            .await?;
+
+        let Some(job) = job else {
+            return Ok(None);
+        };
+
+        let mut job: JobActiveModel = job.into();
+        if let Some(cron_expression) = request.cron_expression {
+            job.cron_expression = Set(cron_expression);
+            // The scheduler recomputes the next run from the new expression
+            job.next_run_at = Set(None);
+        }
+        if let Some(enabled) = request.enabled {
+            job.enabled = Set(enabled);
+        }
+        job.updated_at = Set(Utc::now().naive_utc());
+
+        let result = job.update(&self.db).await?;
+        Ok(Some(JobResponse::from(result)))
+    }
+
+    pub async fn schedule_now(&self, name: &str) -> Result<bool, DbErr> {
+        let now = Utc::now().naive_utc();
+        let result = JobEntity::update_many()
+            .col_expr(JobColumn::NextRunAt, Expr::value(Value::from(now)))
+            .col_expr(JobColumn::UpdatedAt, Expr::value(Value::from(now)))
+            .filter(JobColumn::Name.eq(name))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn set_next_run(&self, job_id: i32, next_run_at: Option<NaiveDateTime>) -> Result<(), DbErr> {
+        JobEntity::update_many()
+            .col_expr(JobColumn::NextRunAt, Expr::value(Value::from(next_run_at)))
+            .filter(JobColumn::Id.eq(job_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Claims a due job for `instance` until `lease_until` and moves its next run to `next_run_at`,
+    /// so the run is not due again if the lease expires before it completes. The conditional update
+    /// is atomic, so when several API replicas tick at once exactly one of them gets `true`.
+    pub async fn try_acquire(&self, job_id: i32, instance: &str, lease_until: NaiveDateTime, next_run_at: Option<NaiveDateTime>) -> Result<bool, DbErr> {
+        let now = Utc::now().naive_utc();
+        let result = JobEntity::update_many()
+            .col_expr(JobColumn::LockedBy, Expr::value(Value::from(instance.to_string())))
+            .col_expr(JobColumn::LockedUntil, Expr::value(Value::from(lease_until)))
+            .col_expr(JobColumn::NextRunAt, Expr::value(Value::from(next_run_at)))
+            .col_expr(JobColumn::LastStatus, Expr::value(Value::from("RUNNING".to_string())))
+            .filter(JobColumn::Id.eq(job_id))
+            .filter(JobColumn::Enabled.eq(true))
+            .filter(JobColumn::NextRunAt.lte(now))
+            .filter(
+                Condition::any()
+                    .add(JobColumn::LockedUntil.is_null())
+                    .add(JobColumn::LockedUntil.lt(now)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Extends the lease of a running job. Returns `false` when `instance` no longer holds it.
+    pub async fn renew_lease(&self, job_id: i32, instance: &str, lease_until: NaiveDateTime) -> Result<bool, DbErr> {
+        let result = JobEntity::update_many()
+            .col_expr(JobColumn::LockedUntil, Expr::value(Value::from(lease_until)))
+            .filter(JobColumn::Id.eq(job_id))
+            .filter(JobColumn::LockedBy.eq(instance))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Records a finished run and releases the lease, unless `instance` lost it to another replica
+    /// in the meantime. `next_run_at` is only moved when it still holds `claimed_next_run_at`, the
+    /// value set by `try_acquire`, so a run requested or rescheduled meanwhile is kept.
+    /// Returns `false` when the lease was lost and nothing was recorded.
+    pub async fn complete_run(
+        &self,
+        job_id: i32,
+        instance: &str,
+        claimed_next_run_at: Option<NaiveDateTime>,
+        outcome: JobRunOutcome,
+    ) -> Result<bool, DbErr> {
+        let result = complete_run_update(job_id, instance, claimed_next_run_at, outcome, Utc::now().naive_utc())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
+
+fn complete_run_update(
+    job_id: i32,
+    instance: &str,
+    claimed_next_run_at: Option<NaiveDateTime>,
+    outcome: JobRunOutcome,
+    now: NaiveDateTime,
+) -> UpdateMany<JobEntity> {
+    let unchanged = match claimed_next_run_at {
+        Some(claimed) => Expr::col(JobColumn::NextRunAt).eq(claimed),
+        None => Expr::col(JobColumn::NextRunAt).is_null(),
+    };
+    let next_run_at = Expr::case(unchanged, Value::from(outcome.next_run_at)).finally(Expr::col(JobColumn::NextRunAt));
+
+    JobEntity::update_many()
+        .col_expr(JobColumn::LastRunAt, Expr::value(Value::from(outcome.started_at)))
+        .col_expr(JobColumn::LastStatus, Expr::value(Value::from(outcome.status)))
+        .col_expr(JobColumn::LastMessage, Expr::value(Value::from(outcome.message)))
+        .col_expr(JobColumn::LastResult, Expr::value(Value::from(outcome.result)))
+        .col_expr(JobColumn::LastDurationMs, Expr::value(Value::from(outcome.duration_ms)))
+        .col_expr(JobColumn::NextRunAt, next_run_at.into())
+        .col_expr(JobColumn::LockedBy, Expr::value(Value::String(None)))
+        .col_expr(JobColumn::LockedUntil, Expr::value(Value::ChronoDateTime(None)))
+        .col_expr(JobColumn::UpdatedAt, Expr::value(Value::from(now)))
+        .filter(JobColumn::Id.eq(job_id))
+        .filter(JobColumn::LockedBy.eq(instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::new(NaiveDate::from_ymd_opt(2024, 10, 1).unwrap(), time.parse().unwrap())
+    }
+
+    fn outcome() -> JobRunOutcome {
+        JobRunOutcome {
+            status: "SUCCESS".to_string(),
+            message: "Captured snapshots for 2 project(s)".to_string(),
+            result: None,
+            started_at: at("10:00:00"),
+            duration_ms: 1200,
+            next_run_at: Some(at("11:00:00")),
+        }
+    }
+
+    fn sql(claimed_next_run_at: Option<NaiveDateTime>) -> String {
+        complete_run_update(7, "sonarcute-a1", claimed_next_run_at, outcome(), at("10:00:01"))
+            .into_query()
+            .to_string(sea_orm::sea_query::PostgresQueryBuilder)
+    }
+
+    #[test]
+    fn completes_only_runs_still_leased_by_the_instance() {
+        let sql = sql(Some(at("11:00:00")));
+        assert!(sql.ends_with(r#"WHERE "scheduled_jobs"."id" = 7 AND "scheduled_jobs"."locked_by" = 'sonarcute-a1'"#), "{}", sql);
+        assert!(sql.contains(r#""locked_by" = NULL, "locked_until" = NULL"#), "{}", sql);
+    }
+
+    #[test]
+    fn keeps_a_next_run_changed_during_the_run() {
+        assert!(
+            sql(Some(at("11:00:00"))).contains(r#""next_run_at" = (CASE WHEN ("next_run_at" = '2024-10-01 11:00:00') THEN '2024-10-01 11:00:00' ELSE "next_run_at" END)"#),
+            "{}",
+            sql(Some(at("11:00:00")))
+        );
+        assert!(
+            sql(None).contains(r#""next_run_at" = (CASE WHEN ("next_run_at" IS NULL) THEN '2024-10-01 11:00:00' ELSE "next_run_at" END)"#),
+            "{}",
+            sql(None)
+        );
+    }
+}
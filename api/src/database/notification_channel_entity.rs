@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "notification_channels")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub name: String,
+    pub project_id: Option<i32>,
+    pub channel_type: String,
+    pub target: String,
+    pub template: Option<String>,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
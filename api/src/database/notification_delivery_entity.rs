@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "notification_deliveries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub channel_id: i32,
+    pub project_id: i32,
+    pub event_key: String,
+    pub status: String,
+    pub subject: String,
+    pub body: String,
+    pub payload: Json,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,305 @@
+use crate::database::notification_channel_entity::ActiveModel as ChannelActiveModel;
+use crate::database::notification_channel_entity::Column as ChannelColumn;
+use crate::database::notification_channel_entity::Entity as ChannelEntity;
+use crate::database::notification_channel_entity::Model as ChannelModel;
+use crate::database::notification_delivery_entity::ActiveModel as DeliveryActiveModel;
+use crate::database::notification_delivery_entity::Column as DeliveryColumn;
+use crate::database::notification_delivery_entity::Entity as DeliveryEntity;
+use crate::database::notification_delivery_entity::Model as DeliveryModel;
+use crate::config::SmtpSettings;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, OnConflict, Value};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateChannelRequest {
+    pub name: String,
+    pub project_id: Option<i32>, // None for a global channel
+    pub channel_type: String,    // "WEBHOOK", "SLACK", "TEAMS" or "EMAIL"
+    pub target: String,          // URL, or comma separated addresses for EMAIL
+    pub template: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateChannelRequest {
+    pub name: Option<String>,
+    pub target: Option<String>,
+    pub template: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelResponse {
+    pub id: i32,
+    pub name: String,
+    pub project_id: Option<i32>,
+    pub channel_type: String,
+    pub target: String,
+    pub template: Option<String>,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<ChannelModel> for ChannelResponse {
+    fn from(model: ChannelModel) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            project_id: model.project_id,
+            channel_type: model.channel_type,
+            target: model.target,
+            template: model.template,
+            enabled: model.enabled,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NewDelivery {
+    pub channel_id: i32,
+    pub project_id: i32,
+    pub event_key: String,
+    pub subject: String,
+    pub body: String,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryResponse {
+    pub id: i32,
+    pub channel_id: i32,
+    pub project_id: i32,
+    pub event_key: String,
+    pub status: String,
+    pub subject: String,
+    pub body: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: NaiveDateTime,
+    pub sent_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<DeliveryModel> for DeliveryResponse {
+    fn from(model: DeliveryModel) -> Self {
+        Self {
+            id: model.id,
+            channel_id: model.channel_id,
+            project_id: model.project_id,
+            event_key: model.event_key,
+            status: model.status,
+            subject: model.subject,
+            body: model.body,
+            payload: model.payload,
+            attempts: model.attempts,
+            last_error: model.last_error,
+            next_attempt_at: model.next_attempt_at,
+            sent_at: model.sent_at,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationService {
+    db: DatabaseConnection,
+    smtp: Arc<SmtpSettings>,
+}
+
+impl NotificationService {
+    pub fn new(db: DatabaseConnection, smtp: SmtpSettings) -> Self {
+        Self { db, smtp: Arc::new(smtp) }
+    }
+
+    /// Mail server of EMAIL channels.
+    pub fn smtp(&self) -> &SmtpSettings {
+        &self.smtp
+    }
+
+    pub async fn get_channels(&self, project_id: Option<i32>) -> Result<Vec<ChannelResponse>, DbErr> {
+        let mut query = ChannelEntity::find();
+        if let Some(project_id) = project_id {
+            query = query.filter(ChannelColumn::ProjectId.eq(project_id));
+        }
+
+        let channels = query.order_by_asc(ChannelColumn::Id).all(&self.db).await?;
+        Ok(channels.into_iter().map(ChannelResponse::from).collect())
+    }
+
+    /// Enabled channels that apply to a project: its own channels plus the global ones.
+    pub async fn get_channels_for_project(&self, project_id: i32) -> Result<Vec<ChannelResponse>, DbErr> {
+        let channels = ChannelEntity::find()
+            .filter(ChannelColumn::Enabled.eq(true))
+            .filter(
+                Condition::any()
+                    .add(ChannelColumn::ProjectId.eq(project_id))
+                    .add(ChannelColumn::ProjectId.is_null()),
+            )
+            .all(&self.db)
+            .await?;
+        Ok(channels.into_iter().map(ChannelResponse::from).collect())
+    }
+
+    pub async fn get_channel_by_id(&self, channel_id: i32) -> Result<Option<ChannelResponse>, DbErr> {
+        let channel = ChannelEntity::find_by_id(channel_id).one(&self.db).await?;
+        Ok(channel.map(ChannelResponse::from))
+    }
+
+    pub async fn create_channel(&self, request: CreateChannelRequest) -> Result<ChannelResponse, DbErr> {
+        let now = Utc::now().naive_utc();
+
+        let channel = ChannelActiveModel {
+            name: Set(request.name),
+            project_id: Set(request.project_id),
+            channel_type: Set(request.channel_type),
+            target: Set(request.target),
+            template: Set(request.template),
+            enabled: Set(request.enabled.unwrap_or(true)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+
+        let result = channel.insert(&self.db).await?;
+        Ok(ChannelResponse::from(result))
+    }
+
+    pub async fn update_channel(&self, channel_id: i32, request: UpdateChannelRequest) -> Result<Option<ChannelResponse>, DbErr> {
+        let Some(channel) = ChannelEntity::find_by_id(channel_id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let mut channel: ChannelActiveModel = channel.into();
+        if let Some(name) = request.name {
+            channel.name = Set(name);
+        }
+        if let Some(target) = request.target {
+            channel.target = Set(target);
+        }
+        if let Some(template) = request.template {
+            // An empty template restores the default message
+            channel.template = Set(Some(template).filter(|t| !t.is_empty()));
+        }
+        if let Some(enabled) = request.enabled {
+            channel.enabled = Set(enabled);
+        }
+        channel.updated_at = Set(Utc::now().naive_utc());
+
+        let result = channel.update(&self.db).await?;
+        Ok(Some(ChannelResponse::from(result)))
+    }
+
+    pub async fn delete_channel(&self, channel_id: i32) -> Result<bool, DbErr> {
+        let result = ChannelEntity::delete_by_id(channel_id).exec(&self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Queues a delivery. Returns `None` when the channel already has a delivery for this event.
+    pub async fn enqueue_delivery(&self, delivery: NewDelivery) -> Result<Option<i32>, DbErr> {
+        let now = Utc::now().naive_utc();
+
+        let model = DeliveryActiveModel {
+            channel_id: Set(delivery.channel_id),
+            project_id: Set(delivery.project_id),
+            event_key: Set(delivery.event_key),
+            status: Set("PENDING".to_string()),
+            subject: Set(delivery.subject),
+            body: Set(delivery.body),
+            payload: Set(delivery.payload),
+            attempts: Set(0),
+            next_attempt_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        };
+
+        let result = DeliveryEntity::insert(model)
+            .on_conflict(
+                OnConflict::columns([DeliveryColumn::ChannelId, DeliveryColumn::EventKey])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db)
+            .await?;
+
+        match result {
+            TryInsertResult::Inserted(inserted) => Ok(Some(inserted.last_insert_id)),
+            TryInsertResult::Empty | TryInsertResult::Conflicted => Ok(None),
+        }
+    }
+
+    pub async fn get_due_deliveries(&self, limit: u64) -> Result<Vec<DeliveryResponse>, DbErr> {
+        let deliveries = DeliveryEntity::find()
+            .filter(DeliveryColumn::Status.eq("PENDING"))
+            .filter(DeliveryColumn::NextAttemptAt.lte(Utc::now().naive_utc()))
+            .order_by_asc(DeliveryColumn::NextAttemptAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(deliveries.into_iter().map(DeliveryResponse::from).collect())
+    }
+
+    /// Claims a due delivery by pushing its next attempt to `lease_until`, so concurrent
+    /// senders skip it and a crashed sender's delivery is retried once the lease expires.
+    pub async fn claim_delivery(&self, delivery_id: i32, lease_until: NaiveDateTime) -> Result<bool, DbErr> {
+        let result = DeliveryEntity::update_many()
+            .col_expr(DeliveryColumn::NextAttemptAt, Expr::value(Value::from(lease_until)))
+            .filter(DeliveryColumn::Id.eq(delivery_id))
+            .filter(DeliveryColumn::Status.eq("PENDING"))
+            .filter(DeliveryColumn::NextAttemptAt.lte(Utc::now().naive_utc()))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    pub async fn mark_delivery_sent(&self, delivery_id: i32) -> Result<(), DbErr> {
+        let now = Utc::now().naive_utc();
+        DeliveryEntity::update_many()
+            .col_expr(DeliveryColumn::Status, Expr::value(Value::from("SENT".to_string())))
+            .col_expr(DeliveryColumn::Attempts, Expr::col(DeliveryColumn::Attempts).add(1))
+            .col_expr(DeliveryColumn::LastError, Expr::value(Value::String(None)))
+            .col_expr(DeliveryColumn::SentAt, Expr::value(Value::from(now)))
+            .filter(DeliveryColumn::Id.eq(delivery_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Records a failed attempt. With no `retry_at` the delivery is given up as FAILED.
+    pub async fn mark_delivery_failed(&self, delivery_id: i32, error: &str, retry_at: Option<NaiveDateTime>) -> Result<(), DbErr> {
+        let status = if retry_at.is_some() { "PENDING" } else { "FAILED" };
+        let mut update = DeliveryEntity::update_many()
+            .col_expr(DeliveryColumn::Status, Expr::value(Value::from(status.to_string())))
+            .col_expr(DeliveryColumn::Attempts, Expr::col(DeliveryColumn::Attempts).add(1))
+            .col_expr(DeliveryColumn::LastError, Expr::value(Value::from(error.to_string())));
+        if let Some(retry_at) = retry_at {
+            update = update.col_expr(DeliveryColumn::NextAttemptAt, Expr::value(Value::from(retry_at)));
+        }
+        update.filter(DeliveryColumn::Id.eq(delivery_id)).exec(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn get_deliveries(&self, channel_id: Option<i32>, status: Option<&str>, limit: u64) -> Result<Vec<DeliveryResponse>, DbErr> {
+        let mut query = DeliveryEntity::find();
+        if let Some(channel_id) = channel_id {
+            query = query.filter(DeliveryColumn::ChannelId.eq(channel_id));
+        }
+        if let Some(status) = status {
+            query = query.filter(DeliveryColumn::Status.eq(status));
+        }
+
+        let deliveries = query
+            .order_by_desc(DeliveryColumn::CreatedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(deliveries.into_iter().map(DeliveryResponse::from).collect())
+    }
+}
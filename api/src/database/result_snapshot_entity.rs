@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "result_snapshots")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub project_id: i32,
+    pub analysis_key: Option<String>,
+    pub analysis_date: Option<NaiveDateTime>,
+    pub quality_gate_status: String,
+    pub quality_gate_conditions: Json,
+    pub issues_total: i32,
+    pub issues_by_severity: Json,
+    pub issues_by_type: Json,
+    pub measures: Json,
+    pub source: String,
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,343 @@
+use crate::database::scan_run_entity::ActiveModel as ScanRunActiveModel;
+use crate::database::scan_run_entity::Column as ScanRunColumn;
+use crate::database::scan_run_entity::Entity as ScanRunEntity;
+use crate::database::scan_run_entity::Model as ScanRunModel;
+use crate::git::GitMetadata;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::sea_query::{Expr, SimpleExpr, Value};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+pub const ACTIVE_STATUSES: [&str; 2] = ["QUEUED", "RUNNING"];
+
+/// Output of one stream to store with a heartbeat.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputUpdate {
+    /// Lines written since the last heartbeat, added after the stored output.
+    Append(String),
+    /// The whole output, after older lines were dropped to stay within the output limit.
+    Replace(String),
+}
+
+impl OutputUpdate {
+    fn expr(&self, column: ScanRunColumn) -> Option<SimpleExpr> {
+        match self {
+            OutputUpdate::Append(output) if output.is_empty() => None,
+            OutputUpdate::Append(output) => Some(Expr::cust_with_exprs("$1 || $2", [Expr::col(column).into(), Expr::value(output.clone())])),
+            OutputUpdate::Replace(output) => Some(Expr::value(output.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRunResponse {
+    pub id: i32,
+    pub project_id: i32,
+    pub status: String, // "QUEUED", "RUNNING", "SUCCEEDED", "FAILED", "CANCELED" or "TIMED_OUT"
+    pub phase: Option<String>, // While running: "SCANNING", "UPLOADING" or "PROCESSING"
+    pub command: String,
+    pub ce_task_id: Option<String>,
+    pub quality_gate_status: Option<String>,
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub cancel_requested: bool,
+    pub runner_id: Option<String>,
+    pub queued_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub duration_ms: Option<i64>,
+}
+
+impl From<ScanRunModel> for ScanRunResponse {
+    fn from(model: ScanRunModel) -> Self {
+        Self {
+            id: model.id,
+            project_id: model.project_id,
+            status: model.status,
+            phase: model.phase,
+            command: model.command,
+            ce_task_id: model.ce_task_id,
+            quality_gate_status: model.quality_gate_status,
+            git_branch: model.git_branch,
+            git_commit: model.git_commit,
+            git_dirty: model.git_dirty,
+            exit_code: model.exit_code,
+            error: model.error,
+            stdout: model.stdout,
+            stderr: model.stderr,
+            cancel_requested: model.cancel_requested,
+            runner_id: model.runner_id,
+            queued_at: model.queued_at,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+            duration_ms: model.duration_ms,
+        }
+    }
+}
+
+/// A run without its captured output, for listings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanRunSummary {
+    pub id: i32,
+    pub project_id: i32,
+    pub status: String,
+    pub phase: Option<String>,
+    pub ce_task_id: Option<String>,
+    pub quality_gate_status: Option<String>,
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub queued_at: NaiveDateTime,
+    pub started_at: Option<NaiveDateTime>,
+    pub finished_at: Option<NaiveDateTime>,
+    pub duration_ms: Option<i64>,
+}
+
+impl From<ScanRunModel> for ScanRunSummary {
+    fn from(model: ScanRunModel) -> Self {
+        Self {
+            id: model.id,
+            project_id: model.project_id,
+            status: model.status,
+            phase: model.phase,
+            ce_task_id: model.ce_task_id,
+            quality_gate_status: model.quality_gate_status,
+            git_branch: model.git_branch,
+            git_commit: model.git_commit,
+            git_dirty: model.git_dirty,
+            exit_code: model.exit_code,
+            error: model.error,
+            queued_at: model.queued_at,
+            started_at: model.started_at,
+            finished_at: model.finished_at,
+            duration_ms: model.duration_ms,
+        }
+    }
+}
+
+impl From<ScanRunResponse> for ScanRunSummary {
+    fn from(run: ScanRunResponse) -> Self {
+        Self {
+            id: run.id,
+            project_id: run.project_id,
+            status: run.status,
+            phase: run.phase,
+            ce_task_id: run.ce_task_id,
+            quality_gate_status: run.quality_gate_status,
+            git_branch: run.git_branch,
+            git_commit: run.git_commit,
+            git_dirty: run.git_dirty,
+            exit_code: run.exit_code,
+            error: run.error,
+            queued_at: run.queued_at,
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+            duration_ms: run.duration_ms,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanRunOutcome {
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub ce_task_id: Option<String>,
+    pub quality_gate_status: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: i64,
+}
+
+#[derive(Clone)]
+pub struct ScanService {
+    db: DatabaseConnection,
+}
+
+impl ScanService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_run(&self, project_id: i32, command: String) -> Result<ScanRunResponse, DbErr> {
+        let run = ScanRunActiveModel {
+            project_id: Set(project_id),
+            status: Set("QUEUED".to_string()),
+            command: Set(command),
+            stdout: Set(String::new()),
+            stderr: Set(String::new()),
+            cancel_requested: Set(false),
+            queued_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        let result = run.insert(&self.db).await?;
+        Ok(ScanRunResponse::from(result))
+    }
+
+    pub async fn get_run(&self, run_id: i32) -> Result<Option<ScanRunResponse>, DbErr> {
+        let run = ScanRunEntity::find_by_id(run_id).one(&self.db).await?;
+        Ok(run.map(ScanRunResponse::from))
+    }
+
+    pub async fn get_runs(&self, project_id: i32, limit: u64) -> Result<Vec<ScanRunSummary>, DbErr> {
+        let runs = ScanRunEntity::find()
+            .filter(ScanRunColumn::ProjectId.eq(project_id))
+            .order_by_desc(ScanRunColumn::QueuedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+        Ok(runs.into_iter().map(ScanRunSummary::from).collect())
+    }
+
+    /// The queued or running scan of a project, if any.
+    pub async fn get_active_run(&self, project_id: i32) -> Result<Option<ScanRunResponse>, DbErr> {
+        let run = ScanRunEntity::find()
+            .filter(ScanRunColumn::ProjectId.eq(project_id))
+            .filter(ScanRunColumn::Status.is_in(ACTIVE_STATUSES))
+            .one(&self.db)
+            .await?;
+        Ok(run.map(ScanRunResponse::from))
+    }
+
+    /// Claims the oldest queued run for `runner_id`. The conditional update is atomic, so a run
+    /// is started by exactly one replica.
+    pub async fn claim_next(&self, runner_id: &str) -> Result<Option<ScanRunResponse>, DbErr> {
+        let candidates = ScanRunEntity::find()
+            .filter(ScanRunColumn::Status.eq("QUEUED"))
+            .order_by_asc(ScanRunColumn::QueuedAt)
+            .limit(5)
+            .all(&self.db)
+            .await?;
+
+        for candidate in candidates {
+            let now = Utc::now().naive_utc();
+            let result = ScanRunEntity::update_many()
+                .col_expr(ScanRunColumn::Status, Expr::value(Value::from("RUNNING".to_string())))
+                .col_expr(ScanRunColumn::Phase, Expr::value(Value::from("SCANNING".to_string())))
+                .col_expr(ScanRunColumn::RunnerId, Expr::value(Value::from(runner_id.to_string())))
+                .col_expr(ScanRunColumn::StartedAt, Expr::value(Value::from(now)))
+                .col_expr(ScanRunColumn::HeartbeatAt, Expr::value(Value::from(now)))
+                .filter(ScanRunColumn::Id.eq(candidate.id))
+                .filter(ScanRunColumn::Status.eq("QUEUED"))
+                .exec(&self.db)
+                .await?;
+
+            if result.rows_affected == 1 {
+                return self.get_run(candidate.id).await;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Stores the output written since the last heartbeat and returns whether cancellation was requested.
+    pub async fn heartbeat(&self, run_id: i32, stdout: &OutputUpdate, stderr: &OutputUpdate) -> Result<bool, DbErr> {
+        let mut update = ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::HeartbeatAt, Expr::value(Value::from(Utc::now().naive_utc())))
+            .filter(ScanRunColumn::Id.eq(run_id));
+        for (column, output) in [(ScanRunColumn::Stdout, stdout), (ScanRunColumn::Stderr, stderr)] {
+            if let Some(expr) = output.expr(column) {
+                update = update.col_expr(column, expr);
+            }
+        }
+        update.exec(&self.db).await?;
+
+        let run = ScanRunEntity::find_by_id(run_id).one(&self.db).await?;
+        Ok(run.is_some_and(|r| r.cancel_requested))
+    }
+
+    /// Records the phase a running scan entered, and the Compute Engine task once it is known.
+    pub async fn set_phase(&self, run_id: i32, phase: &str, ce_task_id: Option<&str>) -> Result<(), DbErr> {
+        let mut update = ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::Phase, Expr::value(Value::from(phase.to_string())))
+            .filter(ScanRunColumn::Id.eq(run_id))
+            .filter(ScanRunColumn::Status.eq("RUNNING"));
+        if let Some(ce_task_id) = ce_task_id {
+            update = update.col_expr(ScanRunColumn::CeTaskId, Expr::value(Value::from(ce_task_id.to_string())));
+        }
+
+        update.exec(&self.db).await?;
+        Ok(())
+    }
+
+    /// Records the commit a running scan checked out and the command it runs with it.
+    pub async fn set_git_metadata(&self, run_id: i32, git: Option<&GitMetadata>, command: String) -> Result<(), DbErr> {
+        ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::Command, Expr::value(Value::from(command)))
+            .col_expr(ScanRunColumn::GitBranch, Expr::value(Value::from(git.and_then(|g| g.branch.clone()))))
+            .col_expr(ScanRunColumn::GitCommit, Expr::value(Value::from(git.and_then(|g| g.commit.clone()))))
+            .col_expr(ScanRunColumn::GitDirty, Expr::value(Value::from(git.map(|g| g.dirty))))
+            .filter(ScanRunColumn::Id.eq(run_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn finish_run(&self, run_id: i32, outcome: ScanRunOutcome) -> Result<Option<ScanRunSummary>, DbErr> {
+        let run = ScanRunEntity::find_by_id(run_id).one(&self.db).await?;
+
+        let Some(run) = run else {
+            return Ok(None);
+        };
+
+        let mut run: ScanRunActiveModel = run.into();
+        run.status = Set(outcome.status);
+        run.phase = Set(None);
+        run.exit_code = Set(outcome.exit_code);
+        run.error = Set(outcome.error);
+        run.ce_task_id = Set(outcome.ce_task_id);
+        run.quality_gate_status = Set(outcome.quality_gate_status);
+        run.stdout = Set(outcome.stdout);
+        run.stderr = Set(outcome.stderr);
+        run.finished_at = Set(Some(Utc::now().naive_utc()));
+        run.duration_ms = Set(Some(outcome.duration_ms));
+        let result = run.update(&self.db).await?;
+
+        Ok(Some(ScanRunSummary::from(result)))
+    }
+
+    /// Cancels a queued run immediately, or flags a running one for its runner to stop.
+    /// Returns `None` when the run does not exist.
+    pub async fn request_cancel(&self, run_id: i32) -> Result<Option<ScanRunResponse>, DbErr> {
+        let now = Utc::now().naive_utc();
+        ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::Status, Expr::value(Value::from("CANCELED".to_string())))
+            .col_expr(ScanRunColumn::FinishedAt, Expr::value(Value::from(now)))
+            .filter(ScanRunColumn::Id.eq(run_id))
+            .filter(ScanRunColumn::Status.eq("QUEUED"))
+            .exec(&self.db)
+            .await?;
+
+        ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::CancelRequested, Expr::value(Value::from(true)))
+            .filter(ScanRunColumn::Id.eq(run_id))
+            .filter(ScanRunColumn::Status.eq("RUNNING"))
+            .exec(&self.db)
+            .await?;
+
+        self.get_run(run_id).await
+    }
+
+    /// Fails running scans whose runner stopped sending heartbeats, e.g. after a restart.
+    pub async fn fail_stale_runs(&self, stale_before: NaiveDateTime) -> Result<u64, DbErr> {
+        let now = Utc::now().naive_utc();
+        let result = ScanRunEntity::update_many()
+            .col_expr(ScanRunColumn::Status, Expr::value(Value::from("FAILED".to_string())))
+            .col_expr(ScanRunColumn::Phase, Expr::value(Value::String(None)))
+            .col_expr(ScanRunColumn::Error, Expr::value(Value::from("Scan runner stopped before the scan finished".to_string())))
+            .col_expr(ScanRunColumn::FinishedAt, Expr::value(Value::from(now)))
+            .filter(ScanRunColumn::Status.eq("RUNNING"))
+            .filter(ScanRunColumn::HeartbeatAt.lt(stale_before))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
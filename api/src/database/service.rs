@@ -16,6 +16,13 @@ pub struct CreateProjectRequest {
     pub sources_path: String,
     pub tests_path: String,
     pub coverage_report_path: Option<String>,
+    /// Owning team; projects without a team are only visible to users with a global role
+    pub team_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetProjectTeamRequest {
+    pub team_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,6 +63,7 @@ pub struct ProjectResponse {
     pub sources_path: String,
     pub tests_path: String,
     pub coverage_report_path: Option<String>,
+    pub team_id: Option<i32>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
@@ -73,6 +81,7 @@ impl From<ProjectModel> for ProjectResponse {
             sources_path: model.sources_path,
             tests_path: model.tests_path,
             coverage_report_path: model.coverage_report_path,
+            team_id: model.team_id,
             created_at: model.created_at,
             updated_at: model.updated_at,
         }
@@ -103,6 +112,7 @@ impl ProjectService {
             sources_path: Set(request.sources_path),
             tests_path: Set(request.tests_path),
             coverage_report_path: Set(request.coverage_report_path),
+            team_id: Set(request.team_id),
             created_at: Set(now),
             updated_at: Set(now),
             ..Default::default()
@@ -121,6 +131,20 @@ impl ProjectService {
         Ok(project.map(ProjectResponse::from))
     }
 
+    pub async fn get_project_by_key(&self, project_key: &str) -> Result<Option<ProjectResponse>, DbErr> {
+        let project = ProjectEntity::find()
+            .filter(crate::database::entities::Column::ProjectKey.eq(project_key))
+            .one(&self.db)
+            .await?;
+
+        Ok(project.map(ProjectResponse::from))
+    }
+
+    pub async fn get_project_by_id(&self, project_id: i32) -> Result<Option<ProjectResponse>, DbErr> {
+        let project = ProjectEntity::find_by_id(project_id).one(&self.db).await?;
+        Ok(project.map(ProjectResponse::from))
+    }
+
     pub async fn update_sonar_token(&self, project_id: i32, token: String) -> Result<(), DbErr> {
         let project = ProjectEntity::find_by_id(project_id).one(&self.db).await?;
         
@@ -139,6 +163,26 @@ impl ProjectService {
         Ok(projects.into_iter().map(ProjectResponse::from).collect())
     }
 
+    pub async fn get_projects_for_teams(&self, team_ids: Vec<i32>) -> Result<Vec<ProjectResponse>, DbErr> {
+        let projects = ProjectEntity::find()
+            .filter(crate::database::entities::Column::TeamId.is_in(team_ids))
+            .all(&self.db)
+            .await?;
+        Ok(projects.into_iter().map(ProjectResponse::from).collect())
+    }
+
+    pub async fn update_project_team(&self, project_id: i32, team_id: Option<i32>) -> Result<Option<ProjectResponse>, DbErr> {
+        let Some(project) = ProjectEntity::find_by_id(project_id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let mut project: ProjectActiveModel = project.into();
+        project.team_id = Set(team_id);
+        project.updated_at = Set(Utc::now().naive_utc());
+        let result = project.update(&self.db).await?;
+        Ok(Some(ProjectResponse::from(result)))
+    }
+
     pub async fn create_admin_token(&self, request: CreateAdminTokenRequest) -> Result<AdminTokenResponse, DbErr> {
         let now = Utc::now().naive_utc();
         
@@ -183,6 +227,20 @@ impl ProjectService {
         Ok(admin_token.map(|token| token.token_value))
     }
 
+    pub async fn get_all_admin_tokens(&self) -> Result<Vec<AdminTokenResponse>, DbErr> {
+        let admin_tokens = AdminTokenEntity::find().all(&self.db).await?;
+        Ok(admin_tokens.into_iter().map(|token| AdminTokenResponse {
+            id: token.id,
+            username: token.username,
+            token_name: token.token_name,
+            token_value: token.token_value,
+            token_type: token.token_type,
+            sonar_host_url: token.sonar_host_url,
+            created_at: token.created_at,
+            updated_at: token.updated_at,
+        }).collect())
+    }
+
     pub async fn update_admin_token_value(&self, token_id: i32, token_value: String) -> Result<(), DbErr> {
         let admin_token = AdminTokenEntity::find_by_id(token_id).one(&self.db).await?;
         
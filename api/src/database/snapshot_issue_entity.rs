@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "snapshot_issues")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub snapshot_id: i32,
+    pub issue_key: String,
+    pub hash: Option<String>,
+    pub rule: String,
+    pub severity: String,
+    pub issue_type: String,
+    pub component: String,
+    pub line: Option<i32>,
+    pub message: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,203 @@
+use crate::database::result_snapshot_entity::ActiveModel as SnapshotActiveModel;
+use crate::database::result_snapshot_entity::Column as SnapshotColumn;
+use crate::database::result_snapshot_entity::Entity as SnapshotEntity;
+use crate::database::result_snapshot_entity::Model as SnapshotModel;
+use crate::database::snapshot_issue_entity::ActiveModel as SnapshotIssueActiveModel;
+use crate::database::snapshot_issue_entity::Column as SnapshotIssueColumn;
+use crate::database::snapshot_issue_entity::Entity as SnapshotIssueEntity;
+use crate::database::snapshot_issue_entity::Model as SnapshotIssueModel;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct NewSnapshot {
+    pub project_id: i32,
+    pub analysis_key: Option<String>,
+    pub analysis_date: Option<NaiveDateTime>,
+    pub quality_gate_status: String,
+    pub quality_gate_conditions: serde_json::Value,
+    pub issues_total: i32,
+    pub issues_by_severity: serde_json::Value,
+    pub issues_by_type: serde_json::Value,
+    pub measures: serde_json::Value,
+    pub source: String, // "MANUAL", "SCHEDULED", "WEBHOOK", "CE_TASK" or "SCAN"
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotResponse {
+    pub id: i32,
+    pub project_id: i32,
+    pub analysis_key: Option<String>,
+    pub analysis_date: Option<NaiveDateTime>,
+    pub quality_gate_status: String,
+    pub quality_gate_conditions: serde_json::Value,
+    pub issues_total: i32,
+    pub issues_by_severity: serde_json::Value,
+    pub issues_by_type: serde_json::Value,
+    pub measures: serde_json::Value,
+    pub source: String,
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<SnapshotModel> for SnapshotResponse {
+    fn from(model: SnapshotModel) -> Self {
+        Self {
+            id: model.id,
+            project_id: model.project_id,
+            analysis_key: model.analysis_key,
+            analysis_date: model.analysis_date,
+            quality_gate_status: model.quality_gate_status,
+            quality_gate_conditions: model.quality_gate_conditions,
+            issues_total: model.issues_total,
+            issues_by_severity: model.issues_by_severity,
+            issues_by_type: model.issues_by_type,
+            measures: model.measures,
+            source: model.source,
+            git_branch: model.git_branch,
+            git_commit: model.git_commit,
+            git_dirty: model.git_dirty,
+            created_at: model.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotIssue {
+    pub key: String,
+    pub hash: Option<String>,
+    pub rule: String,
+    pub severity: String,
+    #[serde(rename = "type")]
+    pub issue_type: String,
+    pub component: String,
+    pub line: Option<i32>,
+    pub message: String,
+}
+
+impl From<SnapshotIssueModel> for SnapshotIssue {
+    fn from(model: SnapshotIssueModel) -> Self {
+        Self {
+            key: model.issue_key,
+            hash: model.hash,
+            rule: model.rule,
+            severity: model.severity,
+            issue_type: model.issue_type,
+            component: model.component,
+            line: model.line,
+            message: model.message,
+        }
+    }
+}
+
+// Keeps each INSERT well below the Postgres bind parameter limit
+const ISSUE_INSERT_BATCH: usize = 1000;
+
+#[derive(Clone)]
+pub struct SnapshotService {
+    db: DatabaseConnection,
+}
+
+impl SnapshotService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_snapshot(&self, snapshot: NewSnapshot, issues: Vec<SnapshotIssue>) -> Result<SnapshotResponse, DbErr> {
+        let txn = self.db.begin().await?;
+
+        let model = SnapshotActiveModel {
+            project_id: Set(snapshot.project_id),
+            analysis_key: Set(snapshot.analysis_key),
+            analysis_date: Set(snapshot.analysis_date),
+            quality_gate_status: Set(snapshot.quality_gate_status),
+            quality_gate_conditions: Set(snapshot.quality_gate_conditions),
+            issues_total: Set(snapshot.issues_total),
+            issues_by_severity: Set(snapshot.issues_by_severity),
+            issues_by_type: Set(snapshot.issues_by_type),
+            measures: Set(snapshot.measures),
+            source: Set(snapshot.source),
+            git_branch: Set(snapshot.git_branch),
+            git_commit: Set(snapshot.git_commit),
+            git_dirty: Set(snapshot.git_dirty),
+            created_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        let result = model.insert(&txn).await?;
+
+        for batch in issues.chunks(ISSUE_INSERT_BATCH) {
+            let models = batch.iter().map(|issue| SnapshotIssueActiveModel {
+                snapshot_id: Set(result.id),
+                issue_key: Set(issue.key.clone()),
+                hash: Set(issue.hash.clone()),
+                rule: Set(issue.rule.clone()),
+                severity: Set(issue.severity.clone()),
+                issue_type: Set(issue.issue_type.clone()),
+                component: Set(issue.component.clone()),
+                line: Set(issue.line),
+                message: Set(issue.message.clone()),
+                ..Default::default()
+            });
+            SnapshotIssueEntity::insert_many(models).exec(&txn).await?;
+        }
+
+        txn.commit().await?;
+        Ok(SnapshotResponse::from(result))
+    }
+
+    pub async fn get_snapshot_issues(&self, snapshot_id: i32) -> Result<Vec<SnapshotIssue>, DbErr> {
+        let issues = SnapshotIssueEntity::find()
+            .filter(SnapshotIssueColumn::SnapshotId.eq(snapshot_id))
+            .all(&self.db)
+            .await?;
+
+        Ok(issues.into_iter().map(SnapshotIssue::from).collect())
+    }
+
+    pub async fn get_snapshot_by_analysis(&self, project_id: i32, analysis_key: &str) -> Result<Option<SnapshotResponse>, DbErr> {
+        let snapshot = SnapshotEntity::find()
+            .filter(SnapshotColumn::ProjectId.eq(project_id))
+            .filter(SnapshotColumn::AnalysisKey.eq(analysis_key))
+            .one(&self.db)
+            .await?;
+
+        Ok(snapshot.map(SnapshotResponse::from))
+    }
+
+    pub async fn get_snapshot_by_id(&self, project_id: i32, snapshot_id: i32) -> Result<Option<SnapshotResponse>, DbErr> {
+        let snapshot = SnapshotEntity::find_by_id(snapshot_id)
+            .filter(SnapshotColumn::ProjectId.eq(project_id))
+            .one(&self.db)
+            .await?;
+
+        Ok(snapshot.map(SnapshotResponse::from))
+    }
+
+    pub async fn get_latest_snapshot(&self, project_id: i32) -> Result<Option<SnapshotResponse>, DbErr> {
+        let snapshot = SnapshotEntity::find()
+            .filter(SnapshotColumn::ProjectId.eq(project_id))
+            .order_by_desc(SnapshotColumn::CreatedAt)
+            .one(&self.db)
+            .await?;
+
+        Ok(snapshot.map(SnapshotResponse::from))
+    }
+
+    pub async fn get_snapshots(&self, project_id: i32, limit: u64) -> Result<Vec<SnapshotResponse>, DbErr> {
+        let snapshots = SnapshotEntity::find()
+            .filter(SnapshotColumn::ProjectId.eq(project_id))
+            .order_by_desc(SnapshotColumn::CreatedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        Ok(snapshots.into_iter().map(SnapshotResponse::from).collect())
+    }
+}
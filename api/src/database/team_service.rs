@@ -0,0 +1,222 @@
+use crate::auth::permissions::Role;
+use crate::database::team_entity::ActiveModel as TeamActiveModel;
+use crate::database::team_entity::Column as TeamColumn;
+use crate::database::team_entity::Entity as TeamEntity;
+use crate::database::team_entity::Model as TeamModel;
+use crate::database::team_member_entity::ActiveModel as TeamMemberActiveModel;
+use crate::database::team_member_entity::Column as TeamMemberColumn;
+use crate::database::team_member_entity::Entity as TeamMemberEntity;
+use crate::database::user_entity::Column as UserColumn;
+use crate::database::user_entity::Entity as UserEntity;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTeamRequest {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTeamRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetTeamMemberRequest {
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamResponse {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<TeamModel> for TeamResponse {
+    fn from(model: TeamModel) -> Self {
+        Self {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamMemberResponse {
+    pub team_id: i32,
+    pub user_id: i32,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub role: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+pub struct TeamService {
+    db: DatabaseConnection,
+}
+
+impl TeamService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_teams(&self) -> Result<Vec<TeamResponse>, DbErr> {
+        let teams = TeamEntity::find().order_by_asc(TeamColumn::Name).all(&self.db).await?;
+        Ok(teams.into_iter().map(TeamResponse::from).collect())
+    }
+
+    pub async fn get_teams_by_ids(&self, team_ids: Vec<i32>) -> Result<Vec<TeamResponse>, DbErr> {
+        let teams = TeamEntity::find()
+            .filter(TeamColumn::Id.is_in(team_ids))
+            .order_by_asc(TeamColumn::Name)
+            .all(&self.db)
+            .await?;
+        Ok(teams.into_iter().map(TeamResponse::from).collect())
+    }
+
+    pub async fn get_team_by_id(&self, team_id: i32) -> Result<Option<TeamResponse>, DbErr> {
+        let team = TeamEntity::find_by_id(team_id).one(&self.db).await?;
+        Ok(team.map(TeamResponse::from))
+    }
+
+    pub async fn get_team_by_name(&self, name: &str) -> Result<Option<TeamResponse>, DbErr> {
+        let team = TeamEntity::find().filter(TeamColumn::Name.eq(name)).one(&self.db).await?;
+        Ok(team.map(TeamResponse::from))
+    }
+
+    pub async fn create_team(&self, request: CreateTeamRequest) -> Result<TeamResponse, DbErr> {
+        let now = Utc::now().naive_utc();
+        let team = TeamActiveModel {
+            name: Set(request.name),
+            description: Set(request.description),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+
+        let result = team.insert(&self.db).await?;
+        Ok(TeamResponse::from(result))
+    }
+
+    pub async fn update_team(&self, team_id: i32, request: UpdateTeamRequest) -> Result<Option<TeamResponse>, DbErr> {
+        let Some(team) = TeamEntity::find_by_id(team_id).one(&self.db).await? else {
+            return Ok(None);
+        };
+
+        let mut team: TeamActiveModel = team.into();
+        if let Some(name) = request.name {
+            team.name = Set(name);
+        }
+        if let Some(description) = request.description {
+            team.description = Set(Some(description));
+        }
+        team.updated_at = Set(Utc::now().naive_utc());
+
+        let result = team.update(&self.db).await?;
+        Ok(Some(TeamResponse::from(result)))
+    }
+
+    /// Deletes a team with its memberships. Its projects stay, without a team.
+    pub async fn delete_team(&self, team_id: i32) -> Result<bool, DbErr> {
+        let result = TeamEntity::delete_by_id(team_id).exec(&self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    pub async fn get_members(&self, team_id: i32) -> Result<Vec<TeamMemberResponse>, DbErr> {
+        let members = TeamMemberEntity::find()
+            .filter(TeamMemberColumn::TeamId.eq(team_id))
+            .all(&self.db)
+            .await?;
+
+        let user_ids: Vec<i32> = members.iter().map(|m| m.user_id).collect();
+        let users: HashMap<i32, _> = UserEntity::find()
+            .filter(UserColumn::Id.is_in(user_ids))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|user| (user.id, user))
+            .collect();
+
+        let mut members: Vec<TeamMemberResponse> = members
+            .into_iter()
+            .filter_map(|member| {
+                let user = users.get(&member.user_id)?;
+                Some(TeamMemberResponse {
+                    team_id: member.team_id,
+                    user_id: member.user_id,
+                    username: user.username.clone(),
+                    display_name: user.display_name.clone(),
+                    role: member.role,
+                    created_at: member.created_at,
+                    updated_at: member.updated_at,
+                })
+            })
+            .collect();
+        members.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(members)
+    }
+
+    /// Adds a user to a team, or changes their role if they are already a member.
+    pub async fn set_member(&self, team_id: i32, user_id: i32, role: Role) -> Result<(), DbErr> {
+        let now = Utc::now().naive_utc();
+        let existing = TeamMemberEntity::find()
+            .filter(TeamMemberColumn::TeamId.eq(team_id))
+            .filter(TeamMemberColumn::UserId.eq(user_id))
+            .one(&self.db)
+            .await?;
+
+        match existing {
+            Some(member) => {
+                let mut member: TeamMemberActiveModel = member.into();
+                member.role = Set(role.as_str().to_string());
+                member.updated_at = Set(now);
+                member.update(&self.db).await?;
+            }
+            None => {
+                let member = TeamMemberActiveModel {
+                    team_id: Set(team_id),
+                    user_id: Set(user_id),
+                    role: Set(role.as_str().to_string()),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    ..Default::default()
+                };
+                member.insert(&self.db).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn remove_member(&self, team_id: i32, user_id: i32) -> Result<bool, DbErr> {
+        let result = TeamMemberEntity::delete_many()
+            .filter(TeamMemberColumn::TeamId.eq(team_id))
+            .filter(TeamMemberColumn::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// The user's role in each of their teams, by team id.
+    pub async fn get_user_roles(&self, user_id: i32) -> Result<HashMap<i32, Role>, DbErr> {
+        let members = TeamMemberEntity::find()
+            .filter(TeamMemberColumn::UserId.eq(user_id))
+            .all(&self.db)
+            .await?;
+        Ok(members
+            .into_iter()
+            .filter_map(|member| Some((member.team_id, Role::parse(&member.role)?)))
+            .collect())
+    }
+}
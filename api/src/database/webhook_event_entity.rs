@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use chrono::NaiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub project_id: Option<i32>,
+    pub project_key: String,
+    pub task_id: Option<String>,
+    pub task_status: String,
+    pub branch: Option<String>,
+    pub is_main_branch: bool,
+    pub quality_gate_status: Option<String>,
+    pub analysed_at: Option<NaiveDateTime>,
+    pub payload: Json,
+    pub received_at: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
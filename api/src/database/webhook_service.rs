@@ -0,0 +1,98 @@
+use crate::database::webhook_event_entity::ActiveModel as WebhookEventActiveModel;
+use crate::database::webhook_event_entity::Column as WebhookEventColumn;
+use crate::database::webhook_event_entity::Entity as WebhookEventEntity;
+use crate::database::webhook_event_entity::Model as WebhookEventModel;
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug)]
+pub struct NewWebhookEvent {
+    pub project_id: Option<i32>,
+    pub project_key: String,
+    pub task_id: Option<String>,
+    pub task_status: String,
+    pub branch: Option<String>,
+    pub is_main_branch: bool,
+    pub quality_gate_status: Option<String>,
+    pub analysed_at: Option<NaiveDateTime>,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEventResponse {
+    pub id: i32,
+    pub project_id: Option<i32>,
+    pub project_key: String,
+    pub task_id: Option<String>,
+    pub task_status: String,
+    pub branch: Option<String>,
+    pub is_main_branch: bool,
+    pub quality_gate_status: Option<String>,
+    pub analysed_at: Option<NaiveDateTime>,
+    pub payload: serde_json::Value,
+    pub received_at: NaiveDateTime,
+}
+
+impl From<WebhookEventModel> for WebhookEventResponse {
+    fn from(model: WebhookEventModel) -> Self {
+        Self {
+            id: model.id,
+            project_id: model.project_id,
+            project_key: model.project_key,
+            task_id: model.task_id,
+            task_status: model.task_status,
+            branch: model.branch,
+            is_main_branch: model.is_main_branch,
+            quality_gate_status: model.quality_gate_status,
+            analysed_at: model.analysed_at,
+            payload: model.payload,
+            received_at: model.received_at,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookEventService {
+    db: DatabaseConnection,
+}
+
+impl WebhookEventService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn create_event(&self, event: NewWebhookEvent) -> Result<WebhookEventResponse, DbErr> {
+        let model = WebhookEventActiveModel {
+            project_id: Set(event.project_id),
+            project_key: Set(event.project_key),
+            task_id: Set(event.task_id),
+            task_status: Set(event.task_status),
+            branch: Set(event.branch),
+            is_main_branch: Set(event.is_main_branch),
+            quality_gate_status: Set(event.quality_gate_status),
+            analysed_at: Set(event.analysed_at),
+            payload: Set(event.payload),
+            received_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        };
+
+        let result = model.insert(&self.db).await?;
+        Ok(WebhookEventResponse::from(result))
+    }
+
+    pub async fn get_events(&self, project_key: Option<&str>, limit: u64) -> Result<Vec<WebhookEventResponse>, DbErr> {
+        let mut query = WebhookEventEntity::find();
+        if let Some(project_key) = project_key {
+            query = query.filter(WebhookEventColumn::ProjectKey.eq(project_key));
+        }
+
+        let events = query
+            .order_by_desc(WebhookEventColumn::ReceivedAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        Ok(events.into_iter().map(WebhookEventResponse::from).collect())
+    }
+}
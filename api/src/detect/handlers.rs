@@ -0,0 +1,58 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::database::service::ProjectService;
+use crate::detect;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DetectProjectRequest {
+    pub project_path: String,
+}
+
+pub async fn detect_project_settings(
+    req: web::Json<DetectProjectRequest>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+) -> Result<HttpResponse> {
+    // Detection reads the server's filesystem, so it is limited to users who may create projects
+    if let Err(response) = access.require_any(Permission::ManageProjects) {
+        return Ok(response);
+    }
+
+    let project_path = req.into_inner().project_path;
+
+    if !Path::new(&project_path).is_dir() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Project path {} is not a directory", project_path),
+            "suggestion": "Pass the absolute path of the project as seen by the SonarCute server"
+        })));
+    }
+
+    // Walking the project tree touches the filesystem; keep it off the async workers
+    let detect_path = project_path.clone();
+    let mut detection = match web::block(move || detect::detect(&detect_path)).await {
+        Ok(detection) => detection,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Project detection failed: {}", e)
+            })));
+        }
+    };
+
+    match project_service.get_project_by_path(&project_path).await {
+        Ok(Some(project)) => detection.warnings.push(format!("This path is already registered as project {}", project.project_key)),
+        Ok(None) => {
+            if let Ok(Some(project)) = project_service.get_project_by_key(&detection.project_key).await {
+                detection.warnings.push(format!("Project key {} is already used by {}", project.project_key, project.project_path));
+            }
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(detection))
+}
@@ -0,0 +1,533 @@
+pub mod handlers;
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How deep below the project root .NET project files are looked for.
+const DOTNET_SEARCH_DEPTH: usize = 2;
+
+/// Directories that hold dependencies or build output rather than sources.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "bin", "obj", "target", "build", "dist", "vendor", "venv"];
+
+/// A build manifest found in the project and the settings it suggests.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedBuild {
+    pub build_tool: &'static str,
+    /// Manifest the settings were derived from, relative to the project path
+    pub manifest: String,
+    pub language: &'static str,
+    /// Name declared in the manifest, if any
+    pub project_name: Option<String>,
+    pub sources_path: String,
+    pub tests_path: String,
+    pub coverage_report_path: String,
+    /// Whether a report already exists at `coverage_report_path`
+    pub coverage_report_exists: bool,
+}
+
+/// Settings proposed for registering the project at `project_path`. The top-level fields come from
+/// the first entry in `detected`; the others are listed so a different build can be picked.
+#[derive(Debug, Serialize)]
+pub struct ProjectDetection {
+    pub project_path: String,
+    pub project_key: String,
+    pub project_name: String,
+    pub build_tool: Option<&'static str>,
+    pub language: Option<&'static str>,
+    pub languages: Vec<&'static str>,
+    pub sources_path: Option<String>,
+    pub tests_path: Option<String>,
+    pub coverage_report_path: Option<String>,
+    pub detected: Vec<DetectedBuild>,
+    pub warnings: Vec<String>,
+}
+
+/// Inspects the build manifests in `project_path`. JVM and .NET builds come first since a
+/// `package.json` next to them usually only drives frontend or tooling scripts.
+pub fn detect(project_path: &str) -> ProjectDetection {
+    let root = Path::new(project_path);
+    let mut warnings = Vec::new();
+
+    let detected: Vec<DetectedBuild> = [gradle(root, &mut warnings), maven(root, &mut warnings), dotnet(root), go(root), cargo(root, &mut warnings), python(root), node(root)]
+        .into_iter()
+        .flatten()
+        .map(|mut build| {
+            build.coverage_report_exists = root.join(&build.coverage_report_path).is_file();
+            build
+        })
+        .collect();
+
+    let mut languages = Vec::new();
+    for build in &detected {
+        if !languages.contains(&build.language) {
+            languages.push(build.language);
+        }
+    }
+
+    let primary = detected.first();
+    let project_name = primary
+        .and_then(|build| build.project_name.clone())
+        .or_else(|| root.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| project_path.to_string());
+
+    match primary {
+        None => warnings.push(
+            "No build manifest found (build.gradle, pom.xml, package.json, *.csproj, pyproject.toml, go.mod, Cargo.toml); enter the language and paths manually".to_string(),
+        ),
+        Some(build) => {
+            if build.build_tool == "gradle" && !root.join("gradlew").is_file() {
+                warnings.push("No Gradle wrapper (gradlew) in the project; the generated scanner command and server-side scans run ./gradlew".to_string());
+            }
+            if !build.coverage_report_exists {
+                warnings.push(format!("No coverage report at {} yet; run the tests with coverage enabled before scanning", build.coverage_report_path));
+            }
+        }
+    }
+
+    ProjectDetection {
+        project_path: project_path.to_string(),
+        project_key: project_key(&project_name),
+        project_name,
+        build_tool: primary.map(|build| build.build_tool),
+        language: primary.map(|build| build.language),
+        languages,
+        sources_path: primary.map(|build| build.sources_path.clone()),
+        tests_path: primary.map(|build| build.tests_path.clone()),
+        coverage_report_path: primary.map(|build| build.coverage_report_path.clone()),
+        detected,
+        warnings,
+    }
+}
+
+/// The key the web UI derives from a project name: lowercase, anything but letters and digits replaced by `-`.
+pub fn project_key(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+fn gradle(root: &Path, warnings: &mut Vec<String>) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["build.gradle.kts", "build.gradle", "settings.gradle.kts", "settings.gradle"])?;
+    let settings = first_file(root, &["settings.gradle.kts", "settings.gradle"]).and_then(|file| fs::read_to_string(root.join(file)).ok()).unwrap_or_default();
+
+    // rootProject.name = 'app' and include 'app', ':lib'
+    let project_name = settings.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("rootProject.name")?.trim_start().strip_prefix('=')?;
+        quoted_strings(value).into_iter().next()
+    });
+    let modules: Vec<String> = settings
+        .lines()
+        .filter(|line| line.trim_start().starts_with("include"))
+        .flat_map(quoted_strings)
+        .map(|module| module.trim_start_matches(':').replace(':', "/"))
+        .collect();
+
+    let module = jvm_module(root, &modules, "Gradle", warnings);
+    let (language, sources_path, tests_path) = jvm_layout(root, &module);
+    let coverage_report_path = format!("{}build/reports/jacoco/test/jacocoTestReport.xml", module);
+    Some(build("gradle", manifest, language, project_name, sources_path, tests_path, coverage_report_path))
+}
+
+fn maven(root: &Path, warnings: &mut Vec<String>) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["pom.xml"])?;
+    let (artifact_id, modules) = read_pom(&root.join(manifest));
+
+    let module = jvm_module(root, &modules, "Maven", warnings);
+    let (language, sources_path, tests_path) = jvm_layout(root, &module);
+    let coverage_report_path = format!("{}target/site/jacoco/jacoco.xml", module);
+    Some(build("maven", manifest, language, artifact_id, sources_path, tests_path, coverage_report_path))
+}
+
+/// The module prefix (`app/` or empty) of a multi-module build whose root has no sources itself.
+fn jvm_module(root: &Path, modules: &[String], tool: &str, warnings: &mut Vec<String>) -> String {
+    if modules.is_empty() || root.join("src/main").is_dir() {
+        return String::new();
+    }
+
+    let with_sources: Vec<&String> = modules.iter().filter(|module| root.join(module).join("src/main").is_dir()).collect();
+    let Some(module) = with_sources.first() else {
+        return String::new();
+    };
+    if with_sources.len() > 1 {
+        warnings.push(format!(
+            "{} build has several modules with sources ({}); proposing {}, change the paths to analyse another module",
+            tool,
+            with_sources.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(", "),
+            module
+        ));
+    }
+    format!("{}/", module)
+}
+
+fn jvm_layout(root: &Path, module: &str) -> (&'static str, String, String) {
+    let kotlin = !root.join(module).join("src/main/java").is_dir() && root.join(module).join("src/main/kotlin").is_dir();
+    let (language, dir) = if kotlin { ("kotlin", "kotlin") } else { ("java", "java") };
+    (language, format!("{}src/main/{}", module, dir), format!("{}src/test/{}", module, dir))
+}
+
+/// The `artifactId` and `modules` declared directly under `<project>`.
+fn read_pom(path: &Path) -> (Option<String>, Vec<String>) {
+    let (mut artifact_id, mut modules) = (None, Vec::new());
+    let Ok(content) = fs::read_to_string(path) else {
+        return (artifact_id, modules);
+    };
+
+    let mut reader = Reader::from_str(&content);
+    let mut elements: Vec<Vec<u8>> = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => elements.push(e.name().as_ref().to_vec()),
+            Ok(Event::End(_)) => {
+                elements.pop();
+            }
+            Ok(Event::Text(text)) => {
+                let Ok(text) = text.unescape() else { continue };
+                let text = text.trim().to_string();
+                match elements.iter().map(Vec::as_slice).collect::<Vec<_>>().as_slice() {
+                    [b"project", b"artifactId"] => artifact_id = Some(text),
+                    [b"project", b"modules", b"module"] => modules.push(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    (artifact_id, modules)
+}
+
+fn dotnet(root: &Path) -> Option<DetectedBuild> {
+    let mut solutions = Vec::new();
+    let mut projects = Vec::new();
+    find_files(root, DOTNET_SEARCH_DEPTH, &mut |path| match path.extension().and_then(|ext| ext.to_str()) {
+        Some("sln") => solutions.push(path.to_path_buf()),
+        Some("csproj") => projects.push(path.to_path_buf()),
+        _ => {}
+    });
+
+    let manifest = solutions.first().or(projects.first())?;
+    let project_name = manifest.file_stem().map(|stem| stem.to_string_lossy().into_owned());
+
+    // Test projects are conventionally named Foo.Tests or Foo.UnitTests
+    let tests_path = projects
+        .iter()
+        .find(|project| project.file_stem().is_some_and(|stem| stem.to_string_lossy().ends_with("Tests")))
+        .and_then(|project| project.parent())
+        .map(|dir| relative(root, dir))
+        .unwrap_or_else(|| ".".to_string());
+
+    Some(build("dotnet", &relative(root, manifest), "csharp", project_name, ".".to_string(), tests_path, "coverage.opencover.xml".to_string()))
+}
+
+fn go(root: &Path) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["go.mod"])?;
+    let content = fs::read_to_string(root.join(manifest)).unwrap_or_default();
+
+    // module github.com/acme/service
+    let project_name = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .and_then(|module| module.trim().trim_matches('"').rsplit('/').next().map(str::to_string));
+
+    // Go tests live next to the code they test
+    Some(build("go", manifest, "go", project_name, ".".to_string(), ".".to_string(), "coverage.out".to_string()))
+}
+
+fn cargo(root: &Path, warnings: &mut Vec<String>) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["Cargo.toml"])?;
+    let content = fs::read_to_string(root.join(manifest)).unwrap_or_default();
+
+    let project_name = toml_name(&content, &["package"]);
+    if project_name.is_none() && content.lines().any(|line| line.trim() == "[workspace]") {
+        warnings.push("Cargo.toml is a workspace without a root package; set the sources path to the crate to analyse".to_string());
+    }
+
+    let tests_path = first_dir(root, &["tests"]).unwrap_or("src");
+    // cargo tarpaulin --out Xml writes a Cobertura report to the project root
+    Some(build("cargo", manifest, "rust", project_name, "src".to_string(), tests_path.to_string(), "cobertura.xml".to_string()))
+}
+
+fn python(root: &Path) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["pyproject.toml", "setup.py"])?;
+    let content = fs::read_to_string(root.join(manifest)).unwrap_or_default();
+
+    let build_tool = if content.lines().any(|line| line.trim() == "[tool.poetry]") { "poetry" } else { "pip" };
+    let project_name = toml_name(&content, &["project", "tool.poetry"]);
+
+    // src layout, else a package named after the project, else the root
+    let package_dir = project_name.as_deref().map(|name| name.replace('-', "_"));
+    let sources_path = first_dir(root, &["src"])
+        .map(str::to_string)
+        .or_else(|| package_dir.filter(|dir| root.join(dir).is_dir()))
+        .unwrap_or_else(|| ".".to_string());
+    let tests_path = first_dir(root, &["tests", "test"]).map(str::to_string).unwrap_or_else(|| sources_path.clone());
+
+    Some(build(build_tool, manifest, "python", project_name, sources_path, tests_path, "coverage.xml".to_string()))
+}
+
+fn node(root: &Path) -> Option<DetectedBuild> {
+    let manifest = first_file(root, &["package.json"])?;
+    let package: serde_json::Value = fs::read_to_string(root.join(manifest))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let build_tool = if root.join("pnpm-lock.yaml").is_file() {
+        "pnpm"
+    } else if root.join("yarn.lock").is_file() {
+        "yarn"
+    } else {
+        "npm"
+    };
+    let typescript = root.join("tsconfig.json").is_file()
+        || ["dependencies", "devDependencies"].iter().any(|deps| package[deps].get("typescript").is_some());
+    let language = if typescript { "typescript" } else { "javascript" };
+
+    // @acme/web -> web
+    let project_name = package["name"].as_str().map(|name| name.rsplit('/').next().unwrap_or(name).to_string());
+
+    let sources_path = first_dir(root, &["src", "lib"]).unwrap_or(".");
+    let tests_path = first_dir(root, &["test", "tests", "__tests__"]).unwrap_or(sources_path);
+    Some(build(build_tool, manifest, language, project_name, sources_path.to_string(), tests_path.to_string(), "coverage/lcov.info".to_string()))
+}
+
+fn build(
+    build_tool: &'static str,
+    manifest: &str,
+    language: &'static str,
+    project_name: Option<String>,
+    sources_path: String,
+    tests_path: String,
+    coverage_report_path: String,
+) -> DetectedBuild {
+    DetectedBuild {
+        build_tool,
+        manifest: manifest.to_string(),
+        language,
+        project_name: project_name.filter(|name| !name.is_empty()),
+        sources_path,
+        tests_path,
+        coverage_report_path,
+        // Checked once all builds are detected
+        coverage_report_exists: false,
+    }
+}
+
+fn first_file<'a>(root: &Path, names: &[&'a str]) -> Option<&'a str> {
+    names.iter().copied().find(|name| root.join(name).is_file())
+}
+
+fn first_dir<'a>(root: &Path, names: &[&'a str]) -> Option<&'a str> {
+    names.iter().copied().find(|name| root.join(name).is_dir())
+}
+
+/// Visits the files up to `depth` directories below `dir`, in name order, skipping hidden and build output directories.
+fn find_files(dir: &Path, depth: usize, visit: &mut dyn FnMut(&Path)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        if path.is_dir() {
+            if depth > 0 && !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                find_files(&path, depth - 1, visit);
+            }
+        } else {
+            visit(&path);
+        }
+    }
+}
+
+/// `path` relative to `root` with `/` separators, `.` for the root itself.
+fn relative(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let parts: Vec<String> = relative.components().map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+    if parts.is_empty() { ".".to_string() } else { parts.join("/") }
+}
+
+/// The `name = "..."` of the first of `sections` that declares one. Enough TOML for a manifest's
+/// project name, without pulling in a parser.
+fn toml_name(content: &str, sections: &[&str]) -> Option<String> {
+    let mut section = String::new();
+    let mut names: Vec<(String, String)> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(header) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            section = header.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("name").map(str::trim_start).and_then(|rest| rest.strip_prefix('='))
+            && let Some(name) = quoted_strings(value).into_iter().next()
+        {
+            names.push((section.clone(), name));
+        }
+    }
+    sections.iter().find_map(|wanted| names.iter().find(|(section, _)| section == wanted).map(|(_, name)| name.clone()))
+}
+
+/// The single- or double-quoted strings in `text`.
+fn quoted_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(['"', '\'']) {
+        let quote = rest[start..].chars().next().unwrap_or('"');
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(quote) else { break };
+        strings.push(after[..end].to_string());
+        rest = &after[end + 1..];
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn detect_in(root: &Path) -> ProjectDetection {
+        detect(&root.to_string_lossy())
+    }
+
+    #[test]
+    fn detects_a_multi_module_gradle_build() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "settings.gradle.kts", "rootProject.name = \"shop\"\ninclude(\":app\", \":docs\")\n");
+        write(dir.path(), "build.gradle.kts", "");
+        write(dir.path(), "app/src/main/kotlin/Main.kt", "");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("gradle"));
+        assert_eq!(detection.project_name, "shop");
+        assert_eq!(detection.language, Some("kotlin"));
+        assert_eq!(detection.sources_path.as_deref(), Some("app/src/main/kotlin"));
+        assert_eq!(detection.coverage_report_path.as_deref(), Some("app/build/reports/jacoco/test/jacocoTestReport.xml"));
+        assert!(detection.warnings.iter().any(|w| w.contains("gradlew")));
+    }
+
+    #[test]
+    fn detects_a_maven_build_and_an_existing_report() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "pom.xml", "<project><parent><artifactId>parent</artifactId></parent><artifactId>billing</artifactId></project>");
+        write(dir.path(), "src/main/java/App.java", "");
+        write(dir.path(), "target/site/jacoco/jacoco.xml", "<report/>");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("maven"));
+        assert_eq!(detection.project_name, "billing");
+        assert_eq!(detection.sources_path.as_deref(), Some("src/main/java"));
+        assert!(detection.detected[0].coverage_report_exists);
+        // Only Gradle builds run the wrapper
+        assert!(detection.warnings.is_empty(), "{:?}", detection.warnings);
+    }
+
+    #[test]
+    fn detects_a_dotnet_solution_and_its_test_project() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "Shop.sln", "");
+        write(dir.path(), "src/Shop/Shop.csproj", "");
+        write(dir.path(), "tests/Shop.Tests/Shop.Tests.csproj", "");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("dotnet"));
+        assert_eq!(detection.project_name, "Shop");
+        assert_eq!(detection.language, Some("csharp"));
+        assert_eq!(detection.tests_path.as_deref(), Some("tests/Shop.Tests"));
+    }
+
+    #[test]
+    fn detects_a_go_module() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "go.mod", "module github.com/acme/ledger\n\ngo 1.22\n");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("go"));
+        assert_eq!(detection.project_name, "ledger");
+        assert_eq!(detection.project_key, "ledger");
+        assert_eq!(detection.sources_path.as_deref(), Some("."));
+    }
+
+    #[test]
+    fn detects_a_cargo_package_and_warns_about_workspaces() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "Cargo.toml", "[package]\nname = \"parser\"\n\n[dependencies]\nname-gen = \"1\"\n");
+        write(dir.path(), "tests/parse.rs", "");
+
+        let detection = detect_in(dir.path());
+        assert_eq!(detection.project_name, "parser");
+        assert_eq!(detection.language, Some("rust"));
+        assert_eq!(detection.tests_path.as_deref(), Some("tests"));
+
+        write(dir.path(), "Cargo.toml", "[workspace]\nmembers = [\"parser\"]\n");
+        let detection = detect_in(dir.path());
+        assert!(detection.warnings.iter().any(|w| w.contains("workspace")));
+    }
+
+    #[test]
+    fn detects_a_poetry_project_with_a_package_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "pyproject.toml", "[tool.poetry]\nname = \"price-engine\"\n");
+        write(dir.path(), "price_engine/__init__.py", "");
+        write(dir.path(), "tests/test_price.py", "");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("poetry"));
+        assert_eq!(detection.project_name, "price-engine");
+        assert_eq!(detection.sources_path.as_deref(), Some("price_engine"));
+        assert_eq!(detection.tests_path.as_deref(), Some("tests"));
+    }
+
+    #[test]
+    fn detects_a_typescript_package() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "package.json", r#"{"name": "@acme/web", "devDependencies": {"typescript": "5"}}"#);
+        write(dir.path(), "yarn.lock", "");
+        write(dir.path(), "src/index.ts", "");
+
+        let detection = detect_in(dir.path());
+
+        assert_eq!(detection.build_tool, Some("yarn"));
+        assert_eq!(detection.project_name, "web");
+        assert_eq!(detection.language, Some("typescript"));
+        assert_eq!(detection.coverage_report_path.as_deref(), Some("coverage/lcov.info"));
+    }
+
+    #[test]
+    fn prefers_jvm_and_dotnet_builds_over_package_json() {
+        let dir = tempfile::tempdir().unwrap();
+        write(dir.path(), "package.json", r#"{"name": "tooling"}"#);
+        write(dir.path(), "pyproject.toml", "[project]\nname = \"scripts\"\n");
+        write(dir.path(), "pom.xml", "<project><artifactId>core</artifactId></project>");
+        write(dir.path(), "build.gradle", "");
+
+        let detection = detect_in(dir.path());
+
+        let tools: Vec<&str> = detection.detected.iter().map(|build| build.build_tool).collect();
+        assert_eq!(tools, ["gradle", "maven", "pip", "npm"]);
+        assert_eq!(detection.build_tool, Some("gradle"));
+        assert_eq!(detection.languages, ["java", "python", "javascript"]);
+    }
+
+    #[test]
+    fn warns_when_no_manifest_is_found() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let detection = detect_in(dir.path());
+
+        assert!(detection.detected.is_empty());
+        assert_eq!(detection.language, None);
+        assert!(detection.warnings[0].starts_with("No build manifest found"));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::report;
+use crate::sonarqube::client::Issue;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// One entry of a Code Climate report, the format of GitLab's Code Quality widget.
+#[derive(Debug, Serialize)]
+pub struct CodeClimateIssue {
+    #[serde(rename = "type")]
+    pub issue_type: &'static str,
+    pub check_name: String,
+    pub description: String,
+    pub categories: Vec<&'static str>,
+    pub severity: &'static str,
+    pub fingerprint: String,
+    pub location: CodeClimateLocation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CodeClimateLocation {
+    pub path: String,
+    pub lines: CodeClimateLines,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CodeClimateLines {
+    pub begin: i32,
+    pub end: i32,
+}
+
+pub fn severity(severity: &str) -> &'static str {
+    match severity {
+        "BLOCKER" => "blocker",
+        "CRITICAL" => "critical",
+        "MAJOR" => "major",
+        "MINOR" => "minor",
+        _ => "info",
+    }
+}
+
+pub fn category(issue_type: &str) -> &'static str {
+    match issue_type {
+        "BUG" => "Bug Risk",
+        "VULNERABILITY" | "SECURITY_HOTSPOT" => "Security",
+        _ => "Clarity",
+    }
+}
+
+/// Stable across analyses as long as the flagged line is unchanged: SonarQube's line `hash`
+/// combined with rule and file, so different rules on the same line stay distinct.
+/// Issues without a hash fall back to their key.
+fn fingerprint(issue: &Issue, path: &str) -> String {
+    let identity = issue.hash.as_deref().unwrap_or(&issue.key);
+    hex::encode(Sha256::digest(format!("{}:{}:{}", issue.rule, path, identity)))
+}
+
+fn convert(project_key: &str, issue: &Issue) -> CodeClimateIssue {
+    // Project-level issues have no file; GitLab still needs a path
+    let path = match report::issue_location(project_key, &issue.component) {
+        path if issue.component == project_key || path.is_empty() => ".".to_string(),
+        path => path,
+    };
+    let (begin, end) = match (&issue.text_range, issue.line) {
+        (Some(range), _) => (range.start_line.max(1), range.end_line.max(range.start_line).max(1)),
+        (None, Some(line)) => (line.max(1), line.max(1)),
+        (None, None) => (1, 1),
+    };
+
+    CodeClimateIssue {
+        issue_type: "issue",
+        check_name: issue.rule.clone(),
+        description: issue.message.clone(),
+        categories: vec![category(&issue.issue_type)],
+        severity: severity(&issue.severity),
+        fingerprint: fingerprint(issue, &path),
+        location: CodeClimateLocation {
+            path,
+            lines: CodeClimateLines { begin, end },
+        },
+    }
+}
+
+pub fn report(project_key: &str, issues: &[Issue]) -> Vec<CodeClimateIssue> {
+    issues.iter().map(|issue| convert(project_key, issue)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sonarqube::client::TextRange;
+
+    fn issue(rule: &str, component: &str, line: Option<i32>, hash: Option<&str>) -> Issue {
+        Issue {
+            key: "AY1".to_string(),
+            rule: rule.to_string(),
+            severity: "CRITICAL".to_string(),
+            component: component.to_string(),
+            project: "demo".to_string(),
+            line,
+            message: "Remove this unused variable".to_string(),
+            effort: None,
+            debt: None,
+            status: "OPEN".to_string(),
+            resolution: None,
+            hash: hash.map(str::to_string),
+            author: None,
+            creation_date: "2024-12-01T10:00:00+0000".to_string(),
+            update_date: "2024-12-01T10:00:00+0000".to_string(),
+            tags: Vec::new(),
+            issue_type: "BUG".to_string(),
+            text_range: None,
+        }
+    }
+
+    #[test]
+    fn maps_severities_and_categories() {
+        let severities: Vec<&str> = ["BLOCKER", "CRITICAL", "MAJOR", "MINOR", "INFO", "UNKNOWN"].iter().map(|s| severity(s)).collect();
+        assert_eq!(severities, vec!["blocker", "critical", "major", "minor", "info", "info"]);
+
+        assert_eq!(category("BUG"), "Bug Risk");
+        assert_eq!(category("VULNERABILITY"), "Security");
+        assert_eq!(category("SECURITY_HOTSPOT"), "Security");
+        assert_eq!(category("CODE_SMELL"), "Clarity");
+    }
+
+    #[test]
+    fn converts_issues_to_code_climate_entries() {
+        let report = report("demo", &[issue("rust:S1481", "demo:src/main.rs", Some(12), Some("abc"))]);
+
+        assert_eq!(serde_json::to_value(&report).unwrap(), serde_json::json!([{
+            "type": "issue",
+            "check_name": "rust:S1481",
+            "description": "Remove this unused variable",
+            "categories": ["Bug Risk"],
+            "severity": "critical",
+            "fingerprint": hex::encode(Sha256::digest("rust:S1481:src/main.rs:abc")),
+            "location": { "path": "src/main.rs", "lines": { "begin": 12, "end": 12 } }
+        }]));
+    }
+
+    #[test]
+    fn fingerprints_follow_the_line_hash_not_the_line() {
+        let moved = convert("demo", &issue("rust:S1481", "demo:src/main.rs", Some(40), Some("abc")));
+        let original = convert("demo", &issue("rust:S1481", "demo:src/main.rs", Some(12), Some("abc")));
+        let other_rule = convert("demo", &issue("rust:S1172", "demo:src/main.rs", Some(12), Some("abc")));
+        let other_file = convert("demo", &issue("rust:S1481", "demo:src/lib.rs", Some(12), Some("abc")));
+
+        assert_eq!(moved.fingerprint, original.fingerprint);
+        assert_ne!(other_rule.fingerprint, original.fingerprint);
+        assert_ne!(other_file.fingerprint, original.fingerprint);
+
+        // Without a hash the issue key identifies the issue
+        let unhashed = convert("demo", &issue("rust:S1481", "demo:src/main.rs", Some(12), None));
+        assert_eq!(unhashed.fingerprint, hex::encode(Sha256::digest("rust:S1481:src/main.rs:AY1")));
+    }
+
+    #[test]
+    fn project_level_issues_point_at_the_project_root() {
+        let entry = convert("demo", &issue("common-rust:DuplicatedBlocks", "demo", None, None));
+        assert_eq!(entry.location.path, ".");
+        assert_eq!((entry.location.lines.begin, entry.location.lines.end), (1, 1));
+    }
+
+    #[test]
+    fn text_range_takes_precedence_over_line() {
+        let mut ranged = issue("rust:S1481", "demo:src/main.rs", Some(12), None);
+        ranged.text_range = Some(TextRange { start_line: 12, end_line: 15, start_offset: Some(4), end_offset: Some(1) });
+        let lines = convert("demo", &ranged).location.lines;
+        assert_eq!((lines.begin, lines.end), (12, 15));
+
+        // Line 0 becomes line 1, and a range never ends before it begins
+        ranged.text_range = Some(TextRange { start_line: 0, end_line: 0, start_offset: None, end_offset: None });
+        let lines = convert("demo", &ranged).location.lines;
+        assert_eq!((lines.begin, lines.end), (1, 1));
+        ranged.text_range = Some(TextRange { start_line: 9, end_line: 7, start_offset: None, end_offset: None });
+        let lines = convert("demo", &ranged).location.lines;
+        assert_eq!((lines.begin, lines.end), (9, 9));
+
+        let lines = convert("demo", &issue("rust:S1481", "demo:src/main.rs", Some(7), None)).location.lines;
+        assert_eq!((lines.begin, lines.end), (7, 7));
+    }
+}
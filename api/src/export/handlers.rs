@@ -0,0 +1,242 @@
+use actix_web::{web, HttpResponse, HttpResponseBuilder, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::config::AppConfig;
+use crate::database::service::ProjectService;
+use crate::export::{codeclimate, junit, sarif, spreadsheet};
+use crate::sonarqube::admin_client::project_client;
+use crate::sonarqube::client::{IssueFilter, IssueSearch, SonarQubeClient, MAX_SEARCH_RESULTS};
+use chrono::Utc;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueExportQuery {
+    pub format: Option<String>, // "csv" (default), "xlsx", "sarif" or "codeclimate"
+    pub branch: Option<String>,
+    pub severities: Option<String>,
+    pub types: Option<String>,
+    pub rules: Option<String>,
+    pub tags: Option<String>,
+}
+
+impl IssueExportQuery {
+    fn filter(&self) -> IssueFilter {
+        IssueFilter {
+            branch: self.branch.clone(),
+            severities: self.severities.clone(),
+            types: self.types.clone(),
+            rules: self.rules.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+pub const FORMATS: [&str; 4] = ["csv", "xlsx", "sarif", "codeclimate"];
+
+fn attachment(project_key: &str, kind: &str, extension: &str) -> (&'static str, String) {
+    (
+        "Content-Disposition",
+        format!("attachment; filename=\"{}_{}_{}.{}\"", project_key, kind, Utc::now().format("%Y-%m-%d"), extension),
+    )
+}
+
+/// Flags an export the SonarQube search limit cut short: `X-Truncated: true` and the full count in
+/// `X-Total-Count`, so clients can tell a partial file from a complete one.
+fn flag_truncated<'a>(response: &'a mut HttpResponseBuilder, project_key: &str, total: i32, exported: usize) -> &'a mut HttpResponseBuilder {
+    if total.max(0) as usize > exported {
+        warn!("Issue export for {} truncated to {} of {} issues", project_key, exported, total);
+        response
+            .insert_header(("X-Truncated", "true"))
+            .insert_header(("X-Total-Count", total.to_string()));
+    }
+    response
+}
+
+async fn search_issues(sonar_client: &SonarQubeClient, project_key: &str, filter: &IssueFilter) -> std::result::Result<IssueSearch, HttpResponse> {
+    sonar_client.search_all_issues(project_key, filter).await.map_err(|e| {
+        HttpResponse::BadGateway().json(serde_json::json!({
+            "error": format!("Failed to fetch issues: {}", e)
+        }))
+    })
+}
+
+pub async fn export_project_issues(
+    path: web::Path<i32>,
+    query: web::Query<IssueExportQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let format = query.format.as_deref().unwrap_or("csv").to_lowercase();
+    if !FORMATS.contains(&format.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unsupported export format: {}", format),
+            "suggestion": format!("Supported formats: {}", FORMATS.join(", "))
+        })));
+    }
+
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::ViewResults, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+    let filter = query.filter();
+
+    if format == "csv" {
+        return export_csv(&project.project_key, sonar_client, filter).await;
+    }
+
+    let search = match search_issues(&sonar_client, &project.project_key, &filter).await {
+        Ok(search) => search,
+        Err(response) => return Ok(response),
+    };
+    let mut response = HttpResponse::Ok();
+    flag_truncated(&mut response, &project.project_key, search.total, search.issues.len());
+
+    match format.as_str() {
+        "sarif" => {
+            let log = sarif::log(sonar_client.base_url(), &project.project_key, &search.issues, &search.rules);
+            Ok(response
+                .content_type("application/sarif+json")
+                .insert_header(attachment(&project.project_key, "issues", "sarif"))
+                .json(log))
+        }
+        "codeclimate" => Ok(response
+            .insert_header(attachment(&project.project_key, "codequality", "json"))
+            .json(codeclimate::report(&project.project_key, &search.issues))),
+        _ => match spreadsheet::xlsx(&project.project_key, &search.issues) {
+            Ok(bytes) => Ok(response
+                .content_type("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+                .insert_header(attachment(&project.project_key, "issues", "xlsx"))
+                .body(bytes)),
+            Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to write XLSX: {}", e)
+            }))),
+        },
+    }
+}
+
+async fn export_csv(project_key: &str, sonar_client: SonarQubeClient, filter: IssueFilter) -> Result<HttpResponse> {
+    // CSV is streamed page by page. The first page is fetched up front so SonarQube
+    // errors still produce a proper error response.
+    let first_page = match sonar_client.search_issues_page(project_key, &filter, 1).await {
+        Ok(page) => page,
+        Err(e) => {
+            return Ok(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to fetch issues: {}", e)
+            })));
+        }
+    };
+    let first_chunk = match spreadsheet::csv_chunk(project_key, &first_page.issues, true) {
+        Ok(chunk) => chunk,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": e.to_string()
+            })));
+        }
+    };
+
+    let total = first_page.paging.total;
+    let next_page = first_page.paging.has_more().then_some(2);
+    let remaining = stream::unfold((sonar_client, project_key.to_string(), filter, next_page), |(client, key, filter, page)| async move {
+        let page = page?;
+        let error = match client.search_issues_page(&key, &filter, page).await {
+            Ok(response) => {
+                let next = response.paging.has_more().then_some(page + 1);
+                let chunk = spreadsheet::csv_chunk(&key, &response.issues, false);
+                return Some((chunk.map(web::Bytes::from).map_err(std::io::Error::other), (client, key, filter, next)));
+            }
+            Err(e) => e,
+        };
+        // Headers are already sent, so a failure can only abort the download
+        warn!("Issue export for {} failed on page {}: {}", key, page, error);
+        Some((Err(std::io::Error::other(error.to_string())), (client, key, filter, None)))
+    });
+
+    let body = stream::once(async move { Ok::<_, std::io::Error>(web::Bytes::from(first_chunk)) }).chain(remaining);
+
+    let mut response = HttpResponse::Ok();
+    flag_truncated(&mut response, project_key, total, total.clamp(0, MAX_SEARCH_RESULTS) as usize);
+    Ok(response
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(attachment(project_key, "issues", "csv"))
+        .streaming(body))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JUnitQuery {
+    pub branch: Option<String>,
+    pub issues: Option<bool>,
+    pub severities: Option<String>, // Issue severities reported as failures (default BLOCKER,CRITICAL)
+}
+
+pub async fn export_quality_gate_junit(
+    path: web::Path<i32>,
+    query: web::Query<JUnitQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::ViewResults, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let quality_gate = match &query.branch {
+        Some(branch) => sonar_client.get_branch_quality_gate(&project.project_key, branch).await,
+        None => sonar_client.get_project_quality_gate(&project.project_key).await,
+    };
+    let status = match quality_gate {
+        Ok(response) => response.project_status,
+        Err(e) => {
+            return Ok(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to fetch quality gate status: {}", e)
+            })));
+        }
+    };
+
+    let issues = if query.issues.unwrap_or(false) {
+        let filter = IssueFilter {
+            branch: query.branch.clone(),
+            severities: Some(query.severities.clone().unwrap_or_else(|| junit::DEFAULT_ISSUE_SEVERITIES.to_string())),
+            ..Default::default()
+        };
+        match search_issues(&sonar_client, &project.project_key, &filter).await {
+            Ok(search) => Some(search),
+            Err(response) => return Ok(response),
+        }
+    } else {
+        None
+    };
+
+    let mut response = HttpResponse::Ok();
+    if let Some(search) = &issues {
+        flag_truncated(&mut response, &project.project_key, search.total, search.issues.len());
+    }
+    let xml = junit::report(&project.project_key, &project.project_name, &status, issues.as_ref().map(|search| search.issues.as_slice()));
+    Ok(response
+        .content_type("application/xml; charset=utf-8")
+        .insert_header(attachment(&project.project_key, "junit", "xml"))
+        .body(xml))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_exports_cut_off_by_the_search_limit() {
+        let mut response = HttpResponse::Ok();
+        let response = flag_truncated(&mut response, "demo", 12_345, 10_000).finish();
+        assert_eq!(response.headers().get("X-Truncated").unwrap(), "true");
+        assert_eq!(response.headers().get("X-Total-Count").unwrap(), "12345");
+    }
+
+    #[test]
+    fn complete_exports_are_not_flagged() {
+        let mut response = HttpResponse::Ok();
+        let response = flag_truncated(&mut response, "demo", 42, 42).finish();
+        assert!(response.headers().get("X-Truncated").is_none());
+        assert!(response.headers().get("X-Total-Count").is_none());
+    }
+}
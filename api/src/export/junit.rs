@@ -0,0 +1,304 @@
+use crate::report;
+use crate::sonarqube::client::{Condition, Issue, ProjectStatus};
+use chrono::Utc;
+use std::fmt::Write;
+
+/// Severities reported as failures when issues are included and no other severities are requested.
+pub const DEFAULT_ISSUE_SEVERITIES: &str = "BLOCKER,CRITICAL";
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Parsers turn literal tabs and newlines in attributes into spaces
+            '\t' => escaped.push_str("&#9;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            // Other control characters are not allowed in XML 1.0
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn comparator_text(comparator: &str) -> &str {
+    match comparator {
+        "GT" => "greater than",
+        "LT" => "less than",
+        "EQ" => "equal to",
+        "NE" => "not equal to",
+        other => other,
+    }
+}
+
+/// Test case name of a condition, e.g. `new_coverage must not be less than 80`.
+fn condition_name(condition: &Condition) -> String {
+    match &condition.error_threshold {
+        Some(threshold) => format!("{} must not be {} {}", condition.metric_key, comparator_text(&condition.comparator), threshold),
+        None => condition.metric_key.clone(),
+    }
+}
+
+fn condition_case(xml: &mut String, project_key: &str, condition: &Condition) {
+    let actual = condition.actual_value.as_deref().unwrap_or("n/a");
+    let threshold = condition.error_threshold.as_deref().unwrap_or("n/a");
+    let detail = format!("actual: {}, threshold: {} ({})", actual, threshold, condition.comparator);
+
+    let _ = write!(
+        xml,
+        "    <testcase classname=\"quality_gate.{}\" name=\"{}\"",
+        escape(project_key),
+        escape(&condition_name(condition))
+    );
+    if condition.status == "ERROR" {
+        let _ = writeln!(
+            xml,
+            ">\n      <failure message=\"{} failed: {}\" type=\"{}\">{}</failure>\n    </testcase>",
+            escape(&condition.metric_key),
+            escape(&detail),
+            escape(&condition.status),
+            escape(&detail)
+        );
+    } else {
+        let _ = writeln!(xml, ">\n      <system-out>{}: {}</system-out>\n    </testcase>", escape(&condition.status), escape(&detail));
+    }
+}
+
+fn issue_case(xml: &mut String, project_key: &str, issue: &Issue) {
+    let path = report::issue_location(project_key, &issue.component);
+    let location = match issue.line {
+        Some(line) => format!("{}:{}", path, line),
+        None => path.clone(),
+    };
+
+    let _ = writeln!(
+        xml,
+        "    <testcase classname=\"{}\" name=\"{} at {}\" file=\"{}\"{}>\n      <failure message=\"{}\" type=\"{}\">{} {} at {}: {}</failure>\n    </testcase>",
+        escape(&path),
+        escape(&issue.rule),
+        escape(&location),
+        escape(&path),
+        issue.line.map(|line| format!(" line=\"{}\"", line)).unwrap_or_default(),
+        escape(&issue.message),
+        escape(&issue.severity),
+        escape(&issue.severity),
+        escape(&issue.issue_type),
+        escape(&location),
+        escape(&issue.message)
+    );
+}
+
+/// JUnit XML with one test suite for the quality gate conditions and, when `issues` is given,
+/// one for the issues, each of which is a failure.
+pub fn report(project_key: &str, project_name: &str, status: &ProjectStatus, issues: Option<&[Issue]>) -> String {
+    let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S");
+    let condition_failures = status.conditions.iter().filter(|c| c.status == "ERROR").count();
+    let issue_count = issues.map(|i| i.len()).unwrap_or(0);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"SonarQube {}\" tests=\"{}\" failures=\"{}\" errors=\"0\">",
+        escape(project_name),
+        status.conditions.len() + issue_count,
+        condition_failures + issue_count
+    );
+
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"Quality Gate: {}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" timestamp=\"{}\">",
+        escape(project_name),
+        status.conditions.len(),
+        condition_failures,
+        timestamp
+    );
+    let _ = writeln!(xml, "    <properties>\n      <property name=\"status\" value=\"{}\"/>\n    </properties>", escape(&status.status));
+    for condition in &status.conditions {
+        condition_case(&mut xml, project_key, condition);
+    }
+    xml.push_str("  </testsuite>\n");
+
+    if let Some(issues) = issues {
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"Issues: {}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" timestamp=\"{}\">",
+            escape(project_name),
+            issues.len(),
+            issues.len(),
+            timestamp
+        );
+        for issue in issues {
+            issue_case(&mut xml, project_key, issue);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::Reader;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Default)]
+    struct Element {
+        name: String,
+        attributes: HashMap<String, String>,
+        text: String,
+        children: Vec<Element>,
+    }
+
+    impl Element {
+        fn attr(&self, name: &str) -> &str {
+            self.attributes.get(name).map(String::as_str).unwrap_or_else(|| panic!("{} has no {} attribute", self.name, name))
+        }
+
+        fn children(&self, name: &str) -> Vec<&Element> {
+            self.children.iter().filter(|c| c.name == name).collect()
+        }
+    }
+
+    fn element(start: &BytesStart) -> Element {
+        Element {
+            name: String::from_utf8(start.name().as_ref().to_vec()).unwrap(),
+            attributes: start
+                .attributes()
+                .map(|attribute| {
+                    let attribute = attribute.unwrap();
+                    (String::from_utf8(attribute.key.as_ref().to_vec()).unwrap(), attribute.unescape_value().unwrap().into_owned())
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Parses the report into its root element, failing on anything that is not well-formed.
+    fn parse(xml: &str) -> Element {
+        let mut reader = Reader::from_str(xml);
+        let mut stack = vec![Element::default()];
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(start) => stack.push(element(&start)),
+                Event::Empty(start) => {
+                    let empty = element(&start);
+                    stack.last_mut().unwrap().children.push(empty);
+                }
+                Event::End(_) => {
+                    let closed = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(closed);
+                }
+                Event::Text(text) => stack.last_mut().unwrap().text.push_str(text.unescape().unwrap().trim()),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(stack.len(), 1, "unclosed elements in {}", xml);
+        let mut document = stack.pop().unwrap();
+        assert_eq!(document.children.len(), 1, "expected a single root in {}", xml);
+        document.children.remove(0)
+    }
+
+    fn status(status: &str, conditions: serde_json::Value) -> ProjectStatus {
+        serde_json::from_value(serde_json::json!({ "status": status, "conditions": conditions })).unwrap()
+    }
+
+    fn issue(key: &str, message: &str, line: Option<i32>) -> Issue {
+        serde_json::from_value(serde_json::json!({
+            "key": key, "rule": "rust:S1481", "severity": "CRITICAL", "component": "demo:src/main.rs",
+            "project": "demo", "line": line, "message": message, "status": "OPEN",
+            "creationDate": "2024-12-01T10:00:00+0000", "updateDate": "2024-12-01T10:00:00+0000",
+            "tags": [], "type": "BUG"
+        }))
+        .unwrap()
+    }
+
+    fn gate() -> ProjectStatus {
+        status("ERROR", serde_json::json!([
+            { "status": "ERROR", "metricKey": "new_coverage", "comparator": "LT", "errorThreshold": "80", "actualValue": "64.2" },
+            { "status": "OK", "metricKey": "new_bugs", "comparator": "GT", "errorThreshold": "0", "actualValue": "0" }
+        ]))
+    }
+
+    #[test]
+    fn counts_tests_and_failures_per_suite() {
+        let issues = [issue("AY1", "Remove this", Some(3)), issue("AY2", "Fix that", None)];
+        let root = parse(&report("demo", "Demo", &gate(), Some(&issues)));
+
+        assert_eq!(root.name, "testsuites");
+        assert_eq!((root.attr("tests"), root.attr("failures")), ("4", "3"));
+
+        let suites = root.children("testsuite");
+        assert_eq!(suites.len(), 2);
+        assert_eq!((suites[0].attr("tests"), suites[0].attr("failures")), ("2", "1"));
+        assert_eq!(suites[0].children("properties")[0].children("property")[0].attr("value"), "ERROR");
+        assert_eq!((suites[1].attr("tests"), suites[1].attr("failures")), ("2", "2"));
+        assert_eq!(suites[1].children("testcase").len(), 2);
+
+        let without_issues = parse(&report("demo", "Demo", &gate(), None));
+        assert_eq!((without_issues.attr("tests"), without_issues.attr("failures")), ("2", "1"));
+        assert_eq!(without_issues.children("testsuite").len(), 1);
+    }
+
+    #[test]
+    fn failing_conditions_are_failures() {
+        let root = parse(&report("demo", "Demo", &gate(), None));
+        let cases = root.children("testsuite")[0].children("testcase");
+
+        assert_eq!(cases[0].attr("name"), "new_coverage must not be less than 80");
+        assert_eq!(cases[0].attr("classname"), "quality_gate.demo");
+        let failure = cases[0].children("failure");
+        assert_eq!(failure.len(), 1);
+        assert_eq!(failure[0].attr("type"), "ERROR");
+        assert_eq!(failure[0].attr("message"), "new_coverage failed: actual: 64.2, threshold: 80 (LT)");
+        assert_eq!(failure[0].text, "actual: 64.2, threshold: 80 (LT)");
+
+        assert_eq!(cases[1].attr("name"), "new_bugs must not be greater than 0");
+        assert!(cases[1].children("failure").is_empty());
+        assert_eq!(cases[1].children("system-out")[0].text, "OK: actual: 0, threshold: 0 (GT)");
+    }
+
+    #[test]
+    fn issues_carry_file_and_line() {
+        let issues = [issue("AY1", "Remove this", Some(3)), issue("AY2", "Fix that", None)];
+        let root = parse(&report("demo", "Demo", &gate(), Some(&issues)));
+        let cases = root.children("testsuite")[1].children("testcase");
+
+        assert_eq!(cases[0].attr("name"), "rust:S1481 at src/main.rs:3");
+        assert_eq!((cases[0].attr("file"), cases[0].attr("line")), ("src/main.rs", "3"));
+        assert_eq!(cases[0].children("failure")[0].text, "CRITICAL BUG at src/main.rs:3: Remove this");
+        assert!(!cases[1].attributes.contains_key("line"));
+    }
+
+    #[test]
+    fn escapes_markup_and_drops_control_characters() {
+        let name = "A&B <\"quoted\"> 'single'\u{1}\u{1b}";
+        let message = "Use a && b < c > d \"here\" 'now'\u{7}\tend";
+        let gate = status("ERROR", serde_json::json!([
+            { "status": "ERROR", "metricKey": "custom<&>", "comparator": "GT", "errorThreshold": "\"1\"", "actualValue": "'2'\u{0}" }
+        ]));
+        let xml = report("demo&\"key\"", name, &gate, Some(&[issue("AY1", message, Some(1))]));
+
+        assert!(!xml.chars().any(|c| c.is_control() && c != '\n'), "{:?}", xml);
+
+        let root = parse(&xml);
+        assert_eq!(root.attr("name"), "SonarQube A&B <\"quoted\"> 'single'");
+
+        let condition = &root.children("testsuite")[0].children("testcase")[0];
+        assert_eq!(condition.attr("classname"), "quality_gate.demo&\"key\"");
+        assert_eq!(condition.attr("name"), "custom<&> must not be greater than \"1\"");
+        assert_eq!(condition.children("failure")[0].text, "actual: '2', threshold: \"1\" (GT)");
+
+        let issue = &root.children("testsuite")[1].children("testcase")[0];
+        assert_eq!(issue.children("failure")[0].attr("message"), "Use a && b < c > d \"here\" 'now'\tend");
+    }
+}
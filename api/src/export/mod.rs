@@ -0,0 +1,5 @@
+pub mod codeclimate;
+pub mod handlers;
+pub mod junit;
+pub mod sarif;
+pub mod spreadsheet;
@@ -0,0 +1,320 @@
+use crate::report;
+use crate::sonarqube::client::{Issue, IssueRule};
+use serde_json::{json, Value};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://docs.oasis-open.org/sarif/sarif/v2.1.0/errata01/os/schemas/sarif-schema-2.1.0.json";
+
+/// SARIF result level for a SonarQube severity.
+pub fn level(severity: &str) -> &'static str {
+    match severity {
+        "BLOCKER" | "CRITICAL" => "error",
+        "MAJOR" => "warning",
+        _ => "note",
+    }
+}
+
+/// `tool.driver.rules` entry. Rules missing from SonarQube's metadata are named after their key.
+fn rule_descriptor(sonar_url: &str, rule_key: &str, metadata: Option<&IssueRule>, issues: &[Issue]) -> Value {
+    let name = metadata.map(|r| r.name.as_str()).unwrap_or(rule_key);
+    let severity = issues.iter().find(|i| i.rule == rule_key).map(|i| i.severity.as_str()).unwrap_or("MAJOR");
+
+    let mut descriptor = json!({
+        "id": rule_key,
+        "name": name,
+        "shortDescription": { "text": name },
+        "helpUri": format!("{}/coding_rules?open={}&rule_key={}", sonar_url.trim_end_matches('/'), rule_key, rule_key),
+        "defaultConfiguration": { "level": level(severity) },
+    });
+    if let Some(language) = metadata.and_then(|r| r.lang_name.as_ref().or(r.lang.as_ref())) {
+        descriptor["properties"] = json!({ "language": language });
+    }
+    descriptor
+}
+
+/// Physical location of an issue. Project-level issues have no file and get no location.
+fn location(project_key: &str, issue: &Issue) -> Option<Value> {
+    let path = report::issue_location(project_key, &issue.component);
+    if issue.component == project_key || path.is_empty() {
+        return None;
+    }
+
+    let mut physical = json!({
+        "artifactLocation": { "uri": path, "uriBaseId": "%SRCROOT%" },
+    });
+
+    // SonarQube offsets are 0-based; SARIF columns are 1-based with an exclusive end
+    if let Some(range) = &issue.text_range {
+        let mut region = json!({ "startLine": range.start_line.max(1), "endLine": range.end_line.max(range.start_line).max(1) });
+        if let Some(start) = range.start_offset {
+            region["startColumn"] = json!(start + 1);
+        }
+        if let Some(end) = range.end_offset {
+            region["endColumn"] = json!(end + 1);
+        }
+        physical["region"] = region;
+    } else if let Some(line) = issue.line.filter(|l| *l > 0) {
+        physical["region"] = json!({ "startLine": line });
+    }
+
+    Some(json!({ "physicalLocation": physical }))
+}
+
+fn result(project_key: &str, rule_index: usize, issue: &Issue) -> Value {
+    let mut result = json!({
+        "ruleId": issue.rule,
+        "ruleIndex": rule_index,
+        "level": level(&issue.severity),
+        "message": { "text": issue.message },
+        "properties": {
+            "key": issue.key,
+            "severity": issue.severity,
+            "type": issue.issue_type,
+            "status": issue.status,
+            "tags": issue.tags,
+        },
+    });
+    if let Some(location) = location(project_key, issue) {
+        result["locations"] = json!([location]);
+    }
+    if let Some(hash) = &issue.hash {
+        result["partialFingerprints"] = json!({ "sonarqube/lineHash": hash });
+    }
+    if let Some(effort) = &issue.effort {
+        result["properties"]["effort"] = json!(effort);
+    }
+    result
+}
+
+/// Builds a SARIF 2.1.0 log with a single SonarQube run.
+pub fn log(sonar_url: &str, project_key: &str, issues: &[Issue], rules: &[IssueRule]) -> Value {
+    let mut rule_keys: Vec<&str> = Vec::new();
+    for issue in issues {
+        if !rule_keys.contains(&issue.rule.as_str()) {
+            rule_keys.push(&issue.rule);
+        }
+    }
+
+    let descriptors: Vec<Value> = rule_keys
+        .iter()
+        .map(|key| rule_descriptor(sonar_url, key, rules.iter().find(|r| r.key == *key), issues))
+        .collect();
+    let results: Vec<Value> = issues
+        .iter()
+        .map(|issue| {
+            let rule_index = rule_keys.iter().position(|key| *key == issue.rule).unwrap_or_default();
+            result(project_key, rule_index, issue)
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "SonarQube",
+                    "informationUri": sonar_url,
+                    "rules": descriptors,
+                }
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "description": { "text": "Root of the analyzed project" } }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sonarqube::client::TextRange;
+
+    const SONAR_URL: &str = "http://localhost:9000";
+
+    fn issue(key: &str, rule: &str, severity: &str, component: &str, line: Option<i32>) -> Issue {
+        Issue {
+            key: key.to_string(),
+            rule: rule.to_string(),
+            severity: severity.to_string(),
+            component: component.to_string(),
+            project: "demo".to_string(),
+            line,
+            message: format!("Issue {}", key),
+            effort: Some("5min".to_string()),
+            debt: None,
+            status: "OPEN".to_string(),
+            resolution: None,
+            hash: Some(format!("hash-{}", key)),
+            author: None,
+            creation_date: "2024-12-01T10:00:00+0000".to_string(),
+            update_date: "2024-12-01T10:00:00+0000".to_string(),
+            tags: vec!["unused".to_string()],
+            issue_type: "CODE_SMELL".to_string(),
+            text_range: None,
+        }
+    }
+
+    fn rule(key: &str, name: &str) -> IssueRule {
+        IssueRule {
+            key: key.to_string(),
+            name: name.to_string(),
+            lang: Some("rust".to_string()),
+            lang_name: Some("Rust".to_string()),
+        }
+    }
+
+    fn string<'a>(value: &'a Value, path: &str) -> &'a str {
+        value.pointer(path).and_then(Value::as_str).unwrap_or_else(|| panic!("{} must be a string in {}", path, value))
+    }
+
+    fn array<'a>(value: &'a Value, path: &str) -> &'a Vec<Value> {
+        value.pointer(path).and_then(Value::as_array).unwrap_or_else(|| panic!("{} must be an array in {}", path, value))
+    }
+
+    fn positive(value: &Value, path: &str) -> i64 {
+        let number = value.pointer(path).and_then(Value::as_i64).unwrap_or_else(|| panic!("{} must be an integer in {}", path, value));
+        assert!(number >= 1, "{} must be at least 1 in {}", path, value);
+        number
+    }
+
+    /// Checks the constraints of the SARIF 2.1.0 schema the log relies on. No JSON schema
+    /// validator is available to the build, so the required properties are checked one by one.
+    fn assert_valid_sarif(log: &Value) {
+        assert_eq!(string(log, "/version"), "2.1.0");
+        assert_eq!(string(log, "/$schema"), SARIF_SCHEMA);
+
+        for run in array(log, "/runs") {
+            assert!(!string(run, "/tool/driver/name").is_empty());
+            let rules = array(run, "/tool/driver/rules");
+            let mut ids: Vec<&str> = rules.iter().map(|rule| string(rule, "/id")).collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), rules.len(), "rule ids must be unique");
+            for rule in rules {
+                string(rule, "/shortDescription/text");
+                assert!(["none", "note", "warning", "error"].contains(&string(rule, "/defaultConfiguration/level")));
+            }
+
+            for result in array(run, "/results") {
+                string(result, "/message/text");
+                assert!(["none", "note", "warning", "error"].contains(&string(result, "/level")));
+                let rule_index = result.pointer("/ruleIndex").and_then(Value::as_u64).expect("ruleIndex must be an integer") as usize;
+                assert_eq!(string(&rules[rule_index], "/id"), string(result, "/ruleId"), "ruleIndex must point to the result's rule");
+                for tag in array(result, "/properties/tags") {
+                    assert!(tag.is_string(), "tags must be strings");
+                }
+                if let Some(fingerprints) = result.get("partialFingerprints") {
+                    assert!(fingerprints.as_object().unwrap().values().all(Value::is_string));
+                }
+                if let Some(locations) = result.get("locations") {
+                    for location in locations.as_array().expect("locations must be an array") {
+                        string(location, "/physicalLocation/artifactLocation/uri");
+                        if let Some(region) = location.pointer("/physicalLocation/region") {
+                            let start_line = positive(region, "/startLine");
+                            if region.get("endLine").is_some() {
+                                assert!(positive(region, "/endLine") >= start_line);
+                            }
+                            for column in ["/startColumn", "/endColumn"] {
+                                if region.pointer(column).is_some() {
+                                    positive(region, column);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn builds_a_valid_log() {
+        let mut ranged = issue("a", "rust:S1481", "MAJOR", "demo:src/main.rs", Some(3));
+        ranged.text_range = Some(TextRange { start_line: 3, end_line: 3, start_offset: Some(0), end_offset: Some(12) });
+        let issues = vec![
+            ranged,
+            issue("b", "rust:S2201", "BLOCKER", "demo:src/lib.rs", Some(7)),
+            issue("c", "rust:S1481", "MINOR", "demo:src/lib.rs", None),
+            issue("d", "rust:S3776", "INFO", "demo", None),
+        ];
+        let rules = vec![rule("rust:S1481", "Unused local variables should be removed")];
+
+        let log = log(SONAR_URL, "demo", &issues, &rules);
+
+        assert_valid_sarif(&log);
+        assert_eq!(array(&log, "/runs/0/results").len(), 4);
+        assert_eq!(string(&log, "/runs/0/tool/driver/rules/0/name"), "Unused local variables should be removed");
+        assert_eq!(string(&log, "/runs/0/tool/driver/rules/0/properties/language"), "Rust");
+        // Rules missing from SonarQube's metadata are named after their key
+        assert_eq!(string(&log, "/runs/0/tool/driver/rules/1/name"), "rust:S2201");
+        assert_eq!(
+            string(&log, "/runs/0/tool/driver/rules/0/helpUri"),
+            "http://localhost:9000/coding_rules?open=rust:S1481&rule_key=rust:S1481"
+        );
+    }
+
+    #[test]
+    fn lists_each_rule_once() {
+        let issues = vec![
+            issue("a", "rust:S1481", "MAJOR", "demo:src/main.rs", Some(1)),
+            issue("b", "rust:S2201", "MAJOR", "demo:src/main.rs", Some(2)),
+            issue("c", "rust:S1481", "MAJOR", "demo:src/lib.rs", Some(3)),
+        ];
+
+        let log = log(SONAR_URL, "demo", &issues, &[]);
+
+        let ids: Vec<&str> = array(&log, "/runs/0/tool/driver/rules").iter().map(|rule| string(rule, "/id")).collect();
+        assert_eq!(ids, ["rust:S1481", "rust:S2201"]);
+        let indexes: Vec<u64> = array(&log, "/runs/0/results").iter().map(|result| result["ruleIndex"].as_u64().unwrap()).collect();
+        assert_eq!(indexes, [0, 1, 0]);
+    }
+
+    #[test]
+    fn maps_severities_to_levels() {
+        assert_eq!(level("BLOCKER"), "error");
+        assert_eq!(level("CRITICAL"), "error");
+        assert_eq!(level("MAJOR"), "warning");
+        assert_eq!(level("MINOR"), "note");
+        assert_eq!(level("INFO"), "note");
+
+        let issues = vec![issue("a", "rust:S1481", "CRITICAL", "demo:src/main.rs", Some(1))];
+        let log = log(SONAR_URL, "demo", &issues, &[]);
+        assert_eq!(string(&log, "/runs/0/results/0/level"), "error");
+        assert_eq!(string(&log, "/runs/0/tool/driver/rules/0/defaultConfiguration/level"), "error");
+    }
+
+    #[test]
+    fn converts_text_ranges_to_one_based_columns() {
+        let mut ranged = issue("a", "rust:S1481", "MAJOR", "demo:src/main.rs", Some(3));
+        ranged.text_range = Some(TextRange { start_line: 3, end_line: 4, start_offset: Some(0), end_offset: Some(12) });
+
+        let log = log(SONAR_URL, "demo", &[ranged], &[]);
+
+        let location = &log["runs"][0]["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"], json!({ "uri": "src/main.rs", "uriBaseId": "%SRCROOT%" }));
+        assert_eq!(location["region"], json!({ "startLine": 3, "endLine": 4, "startColumn": 1, "endColumn": 13 }));
+    }
+
+    #[test]
+    fn locates_issues_without_a_line() {
+        let issues = vec![
+            issue("file", "rust:S1481", "MAJOR", "demo:src/lib.rs", None),
+            issue("zero", "rust:S1481", "MAJOR", "demo:src/lib.rs", Some(0)),
+            issue("project", "rust:S1481", "MAJOR", "demo", None),
+        ];
+
+        let log = log(SONAR_URL, "demo", &issues, &[]);
+
+        assert_valid_sarif(&log);
+        let results = array(&log, "/runs/0/results");
+        // File-level issues point at the file without a region
+        for result in &results[..2] {
+            let location = &result["locations"][0]["physicalLocation"];
+            assert_eq!(location["artifactLocation"]["uri"], "src/lib.rs");
+            assert!(location.get("region").is_none());
+        }
+        // Project-level issues have no location at all
+        assert!(results[2].get("locations").is_none());
+    }
+}
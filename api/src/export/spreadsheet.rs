@@ -0,0 +1,93 @@
+use crate::report;
+use crate::sonarqube::client::Issue;
+use anyhow::Result;
+use rust_xlsxwriter::{Format, Workbook};
+
+pub const COLUMNS: [(&str, f64); 12] = [
+    ("Key", 24.0),
+    ("Rule", 22.0),
+    ("Severity", 11.0),
+    ("Type", 16.0),
+    ("File", 50.0),
+    ("Line", 8.0),
+    ("Message", 70.0),
+    ("Author", 24.0),
+    ("Effort", 10.0),
+    ("Status", 12.0),
+    ("Tags", 24.0),
+    ("Created", 22.0),
+];
+
+fn row(project_key: &str, issue: &Issue) -> [String; 12] {
+    [
+        issue.key.clone(),
+        issue.rule.clone(),
+        issue.severity.clone(),
+        issue.issue_type.clone(),
+        report::issue_location(project_key, &issue.component),
+        issue.line.map(|l| l.to_string()).unwrap_or_default(),
+        issue.message.clone(),
+        issue.author.clone().unwrap_or_default(),
+        issue.effort.clone().unwrap_or_default(),
+        issue.status.clone(),
+        issue.tags.join(","),
+        issue.creation_date.clone(),
+    ]
+}
+
+/// Keeps spreadsheet apps from evaluating cells that start like a formula.
+fn csv_safe(value: String) -> String {
+    if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value
+    }
+}
+
+/// Writes issues as CSV rows. The first chunk of an export starts with a UTF-8 BOM and the
+/// header row, so Excel detects the encoding.
+pub fn csv_chunk(project_key: &str, issues: &[Issue], with_header: bool) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    if with_header {
+        buffer.extend_from_slice("\u{feff}".as_bytes());
+    }
+
+    let mut writer = csv::Writer::from_writer(buffer);
+    if with_header {
+        writer.write_record(COLUMNS.iter().map(|(title, _)| *title))?;
+    }
+    for issue in issues {
+        writer.write_record(row(project_key, issue).map(csv_safe))?;
+    }
+
+    writer.into_inner().map_err(|e| anyhow::anyhow!("Failed to write CSV: {}", e))
+}
+
+/// Builds an XLSX workbook with one filterable `Issues` sheet.
+pub fn xlsx(project_key: &str, issues: &[Issue]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    sheet.set_name("Issues")?;
+
+    let header = Format::new().set_bold().set_background_color("#428BCA").set_font_color("#FFFFFF");
+    for (col, (title, width)) in COLUMNS.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, &header)?;
+        sheet.set_column_width(col as u16, *width)?;
+    }
+
+    for (index, issue) in issues.iter().enumerate() {
+        let row_num = index as u32 + 1;
+        for (col, value) in row(project_key, issue).into_iter().enumerate() {
+            match (col, issue.line) {
+                // Numeric line numbers sort correctly
+                (5, Some(line)) => sheet.write_number(row_num, col as u16, line)?,
+                _ => sheet.write_string(row_num, col as u16, value)?,
+            };
+        }
+    }
+
+    sheet.autofilter(0, 0, issues.len() as u32, COLUMNS.len() as u16 - 1)?;
+    sheet.set_freeze_panes(1, 0)?;
+
+    Ok(workbook.save_to_buffer()?)
+}
@@ -0,0 +1,44 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::database::service::ProjectService;
+use crate::git;
+
+pub async fn get_project_git_metadata(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+) -> Result<HttpResponse> {
+    let project = match project_service.get_project_by_id(path.into_inner()).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require(&project, Permission::ViewResults) {
+        return Ok(response);
+    }
+
+    // Computing the working tree status reads the whole checkout
+    let project_path = project.project_path.clone();
+    match web::block(move || git::read_metadata(&project_path)).await {
+        Ok(Ok(Some(metadata))) => Ok(HttpResponse::Ok().json(metadata)),
+        Ok(Ok(None)) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": format!("{} is not inside a git repository", project.project_path),
+            "suggestion": "Scans and snapshots of this project are recorded without a commit"
+        }))),
+        Ok(Err(e)) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to read git metadata: {}", e)
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to read git metadata: {}", e)
+        }))),
+    }
+}
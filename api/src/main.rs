@@ -2,8 +2,20 @@ mod web;
 mod config;
 mod database;
 mod sonarqube;
+mod scheduler;
+mod notification;
+mod report;
+mod export;
+mod scanner;
+mod coverage;
+mod detect;
+mod git;
+mod auth;
+mod audit;
+
+use clap::Parser;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    web::server::start().await
+    web::server::start(config::ServerArgs::parse()).await
 }
@@ -0,0 +1,339 @@
+use crate::config::SmtpSettings;
+use crate::database::notification_service::ChannelResponse;
+use crate::sonarqube::client::Condition;
+use anyhow::{anyhow, Result};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use minijinja::Environment;
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+use tracing::warn;
+
+pub const CHANNEL_TYPES: [&str; 4] = ["WEBHOOK", "SLACK", "TEAMS", "EMAIL"];
+
+pub const DEFAULT_SUBJECT: &str = "[SonarCute] Quality gate {{status}}: {{project_name}}";
+
+pub const DEFAULT_TEMPLATE: &str = "Quality gate for {{project_name}} ({{project_key}}) changed from {{previous_status}} to {{status}}.
+
+Failed conditions:
+{{conditions}}
+
+{{dashboard_url}}";
+
+/// Everything a template can refer to. Serialized as-is for generic webhook channels.
+#[derive(Debug, Serialize)]
+pub struct QualityGateEvent {
+    pub event: String,
+    pub project_id: i32,
+    pub project_key: String,
+    pub project_name: String,
+    pub status: String,
+    pub previous_status: Option<String>,
+    pub analysis_key: Option<String>,
+    pub failed_conditions: Vec<Condition>,
+    pub dashboard_url: String,
+}
+
+/// One line per failed condition, e.g. `- new_coverage: 64.2 (LT 80)`.
+pub fn format_conditions(conditions: &[Condition]) -> String {
+    if conditions.is_empty() {
+        return "- none reported".to_string();
+    }
+
+    conditions
+        .iter()
+        .map(|c| format!(
+            "- {}: {} ({} {})",
+            c.metric_key,
+            c.actual_value.as_deref().unwrap_or("n/a"),
+            c.comparator,
+            c.error_threshold.as_deref().unwrap_or("n/a")
+        ))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Template context: the event, with `previous_status` shown as NONE before the first analysis and
+/// `conditions` formatted one line per failed condition.
+fn context(event: &QualityGateEvent) -> serde_json::Value {
+    let mut context = serde_json::to_value(event).unwrap_or_default();
+    context["previous_status"] = serde_json::json!(event.previous_status.as_deref().unwrap_or("NONE"));
+    context["analysis_key"] = serde_json::json!(event.analysis_key.as_deref().unwrap_or(""));
+    context["conditions"] = serde_json::json!(format_conditions(&event.failed_conditions));
+    context
+}
+
+/// Renders a minijinja template, e.g. `{{ project_name }}` or a `{% for %}` loop over `failed_conditions`.
+pub fn render(template: &str, event: &QualityGateEvent) -> Result<String> {
+    let mut environment = Environment::new();
+    environment.set_keep_trailing_newline(true);
+    Ok(environment.render_str(template, context(event))?)
+}
+
+/// Checks that a custom template parses, so mistakes are reported when the channel is saved.
+pub fn check_template(template: &str) -> Result<()> {
+    Environment::new().template_from_str(template)?;
+    Ok(())
+}
+
+/// Subject and body of a notification. A custom template that fails to render falls back to
+/// the default one, so the notification still goes out.
+pub fn render_message(template: Option<&str>, event: &QualityGateEvent) -> (String, String) {
+    let subject = render(DEFAULT_SUBJECT, event).unwrap_or_default();
+    let body = match template.map(|template| render(template, event)) {
+        Some(Ok(body)) => body,
+        Some(Err(e)) => {
+            warn!("Notification template for {} failed to render, using the default: {}", event.project_key, e);
+            render(DEFAULT_TEMPLATE, event).unwrap_or_default()
+        }
+        None => render(DEFAULT_TEMPLATE, event).unwrap_or_default(),
+    };
+    (subject, body)
+}
+
+/// Sends a rendered message through a channel.
+pub async fn send(channel: &ChannelResponse, smtp: &SmtpSettings, subject: &str, body: &str, payload: &serde_json::Value) -> Result<()> {
+    match channel.channel_type.as_str() {
+        "WEBHOOK" => {
+            let mut payload = payload.clone();
+            if let Some(object) = payload.as_object_mut() {
+                object.insert("subject".to_string(), serde_json::json!(subject));
+                object.insert("text".to_string(), serde_json::json!(body));
+            }
+            post_json(&channel.target, &payload).await
+        }
+        // Slack and Mattermost incoming webhooks
+        "SLACK" => post_json(&channel.target, &serde_json::json!({
+            "text": format!("*{}*\n{}", subject, body)
+        })).await,
+        // Microsoft Teams incoming webhook connector
+        "TEAMS" => post_json(&channel.target, &serde_json::json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "summary": subject,
+            "themeColor": "D03B3B",
+            "title": subject,
+            "text": body.replace('\n', "  \n")
+        })).await,
+        "EMAIL" => send_email(smtp, &channel.target, subject, body).await,
+        other => Err(anyhow!("Unsupported channel type: {}", other)),
+    }
+}
+
+async fn post_json(url: &str, payload: &serde_json::Value) -> Result<()> {
+    let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
+    let response = client.post(url).json(payload).send().await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Webhook returned {}: {}", status, error_text));
+    }
+
+    Ok(())
+}
+
+/// Sends a plain text email through the configured SMTP server.
+async fn send_email(smtp: &SmtpSettings, recipients: &str, subject: &str, body: &str) -> Result<()> {
+    let host = smtp.host.as_deref().ok_or_else(|| anyhow!("SMTP_HOST is not configured"))?;
+
+    let mut builder = match smtp.tls.as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+        "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+        // Plain SMTP, e.g. a local MailHog or Mailpit instance
+        _ => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    };
+    if let Some(port) = smtp.port {
+        builder = builder.port(port);
+    }
+    if let Some(username) = &smtp.username {
+        let password = smtp.password.clone().unwrap_or_default();
+        builder = builder.credentials(Credentials::new(username.clone(), password));
+    }
+    let mailer = builder.timeout(Some(Duration::from_secs(15))).build();
+
+    mailer.send(email_message(&smtp.from, recipients, subject, body)?).await?;
+    Ok(())
+}
+
+/// Plain text message to the comma-separated `recipients`.
+fn email_message(from: &str, recipients: &str, subject: &str, body: &str) -> Result<Message> {
+    let mut message = Message::builder()
+        .from(from.parse()?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for recipient in recipients.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        message = message.to(recipient.parse()?);
+    }
+    Ok(message.body(body.to_string())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    fn event() -> QualityGateEvent {
+        QualityGateEvent {
+            event: "QUALITY_GATE_FAILED".to_string(),
+            project_id: 1,
+            project_key: "my-project".to_string(),
+            project_name: "My Project".to_string(),
+            status: "ERROR".to_string(),
+            previous_status: None,
+            analysis_key: Some("AYbbb".to_string()),
+            failed_conditions: vec![Condition {
+                status: "ERROR".to_string(),
+                metric_key: "new_coverage".to_string(),
+                comparator: "LT".to_string(),
+                error_threshold: Some("80".to_string()),
+                actual_value: Some("64.2".to_string()),
+                period_index: None,
+            }],
+            dashboard_url: "http://localhost:9000/dashboard?id=my-project".to_string(),
+        }
+    }
+
+    fn channel(channel_type: &str, target: String) -> ChannelResponse {
+        let now = Utc::now().naive_utc();
+        ChannelResponse {
+            id: 1,
+            name: "test".to_string(),
+            project_id: Some(1),
+            channel_type: channel_type.to_string(),
+            target,
+            template: None,
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Answers one HTTP request on a local port with `status` and hands over its JSON body.
+    fn listen_once(status: u16) -> (String, mpsc::Receiver<serde_json::Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(stream, "HTTP/1.1 {} Test\r\nContent-Length: 4\r\nConnection: close\r\n\r\nnope", status).unwrap();
+            sender.send(serde_json::from_slice(&body).unwrap()).unwrap();
+        });
+
+        (url, receiver)
+    }
+
+    #[test]
+    fn renders_default_templates() {
+        let event = event();
+        assert_eq!(render(DEFAULT_SUBJECT, &event).unwrap(), "[SonarCute] Quality gate ERROR: My Project");
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, &event).unwrap(),
+            "Quality gate for My Project (my-project) changed from NONE to ERROR.\n\nFailed conditions:\n- new_coverage: 64.2 (LT 80)\n\nhttp://localhost:9000/dashboard?id=my-project"
+        );
+    }
+
+    #[test]
+    fn renders_loops_over_failed_conditions() {
+        let template = "{% for c in failed_conditions %}{{ c.metricKey }}={{ c.actualValue }}{% endfor %} ({{ analysis_key }})";
+        assert_eq!(render(template, &event()).unwrap(), "new_coverage=64.2 (AYbbb)");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert!(check_template("{{ project_name }}").is_ok());
+        assert!(check_template("{% for c in failed_conditions %}").is_err());
+        assert!(render("{{ project_name", &event()).is_err());
+    }
+
+    #[test]
+    fn falls_back_to_the_default_template() {
+        let (_, body) = render_message(Some("{% if %}"), &event());
+        assert!(body.starts_with("Quality gate for My Project"));
+
+        let (_, body) = render_message(Some("{{ project_key }} failed"), &event());
+        assert_eq!(body, "my-project failed");
+    }
+
+    #[actix_web::test]
+    async fn webhook_posts_the_event_with_subject_and_text() {
+        let (url, received) = listen_once(200);
+        let payload = serde_json::to_value(event()).unwrap();
+
+        send(&channel("WEBHOOK", url), &SmtpSettings::default(), "subject", "body", &payload).await.unwrap();
+
+        let body = received.recv().unwrap();
+        assert_eq!(body["project_key"], "my-project");
+        assert_eq!(body["failed_conditions"][0]["metricKey"], "new_coverage");
+        assert_eq!(body["subject"], "subject");
+        assert_eq!(body["text"], "body");
+    }
+
+    #[actix_web::test]
+    async fn slack_posts_bold_subject_and_body() {
+        let (url, received) = listen_once(200);
+
+        send(&channel("SLACK", url), &SmtpSettings::default(), "subject", "body", &serde_json::json!({})).await.unwrap();
+
+        assert_eq!(received.recv().unwrap(), serde_json::json!({ "text": "*subject*\nbody" }));
+    }
+
+    #[actix_web::test]
+    async fn error_status_fails_the_delivery() {
+        let (url, received) = listen_once(500);
+
+        let error = send(&channel("SLACK", url), &SmtpSettings::default(), "subject", "body", &serde_json::json!({})).await.unwrap_err();
+
+        assert!(error.to_string().contains("500"), "{}", error);
+        assert!(error.to_string().contains("nope"), "{}", error);
+        received.recv().unwrap();
+    }
+
+    #[test]
+    fn builds_plain_text_emails_for_every_recipient() {
+        let message = email_message("sonarcute@localhost", "dev@example.com, ops@example.com,", "subject", "line 1\nline 2").unwrap();
+
+        let recipients: Vec<String> = message.envelope().to().iter().map(|r| r.to_string()).collect();
+        assert_eq!(recipients, vec!["dev@example.com", "ops@example.com"]);
+        assert_eq!(message.envelope().from().unwrap().to_string(), "sonarcute@localhost");
+
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("From: sonarcute@localhost\r\n"), "{}", formatted);
+        assert!(formatted.contains("To: dev@example.com, ops@example.com\r\n"), "{}", formatted);
+        assert!(formatted.contains("Subject: subject\r\n"), "{}", formatted);
+        assert!(formatted.contains("Content-Type: text/plain; charset=utf-8\r\n"), "{}", formatted);
+        assert!(formatted.ends_with("\r\n\r\nline 1\r\nline 2"), "{}", formatted);
+    }
+
+    #[test]
+    fn rejects_invalid_email_addresses() {
+        assert!(email_message("sonarcute@localhost", "not an address", "s", "b").is_err());
+        assert!(email_message("sonarcute@localhost", "", "s", "b").is_err());
+    }
+
+    #[actix_web::test]
+    async fn unsupported_channel_type_fails() {
+        let error = send(&channel("PIGEON", String::new()), &SmtpSettings::default(), "s", "b", &serde_json::json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("PIGEON"));
+    }
+}
@@ -0,0 +1,258 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::audit::Audit;
+use crate::auth::permissions::{Access, Permission};
+use crate::database::notification_service::{ChannelResponse, CreateChannelRequest, NotificationService, UpdateChannelRequest};
+use crate::database::service::ProjectService;
+use crate::notification::channels::{self, QualityGateEvent};
+use crate::sonarqube::client::Condition;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelsQuery {
+    pub project_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliveriesQuery {
+    pub channel_id: Option<i32>,
+    pub status: Option<String>,
+    pub limit: Option<u64>,
+}
+
+fn check_template(template: Option<&str>) -> std::result::Result<(), HttpResponse> {
+    match template.map(channels::check_template) {
+        Some(Err(e)) => Err(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid template: {}", e),
+            "suggestion": "Templates use minijinja syntax, e.g. {{ project_name }} failed: {{ conditions }}"
+        }))),
+        _ => Ok(()),
+    }
+}
+
+/// Checks `permission` on a channel's project, or through the global role for channels of every project.
+async fn require_scope(
+    access: &Access,
+    project_service: &ProjectService,
+    project_id: Option<i32>,
+    permission: Permission,
+) -> std::result::Result<(), HttpResponse> {
+    match project_id {
+        Some(project_id) => access.require_project(project_service, project_id, permission).await.map(|_| ()),
+        None => access.require_global(permission),
+    }
+}
+
+/// Loads a channel the caller may manage. Channel targets can hold webhook secrets, so every
+/// channel operation needs the right to manage its project.
+async fn require_channel(
+    channel_id: i32,
+    access: &Access,
+    project_service: &ProjectService,
+    notification_service: &NotificationService,
+) -> std::result::Result<ChannelResponse, HttpResponse> {
+    let channel = match notification_service.get_channel_by_id(channel_id).await {
+        Ok(Some(channel)) => channel,
+        Ok(None) => {
+            return Err(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Notification channel not found"
+            })));
+        }
+        Err(e) => {
+            return Err(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    require_scope(access, project_service, channel.project_id, Permission::ManageProjects).await?;
+    Ok(channel)
+}
+
+pub async fn get_channels(
+    query: web::Query<ChannelsQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = require_scope(&access, &project_service, query.project_id, Permission::ManageProjects).await {
+        return Ok(response);
+    }
+
+    match notification_service.get_channels(query.project_id).await {
+        Ok(channels) => Ok(HttpResponse::Ok().json(channels)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn create_channel(
+    req: web::Json<CreateChannelRequest>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let mut request = req.into_inner();
+    if let Err(response) = require_scope(&access, &project_service, request.project_id, Permission::ManageProjects).await {
+        return Ok(response);
+    }
+    request.channel_type = request.channel_type.to_uppercase();
+
+    if !channels::CHANNEL_TYPES.contains(&request.channel_type.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unsupported channel type: {}", request.channel_type),
+            "suggestion": format!("Use one of: {}", channels::CHANNEL_TYPES.join(", "))
+        })));
+    }
+
+    if request.target.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Target is required",
+            "suggestion": "Provide a webhook URL, or comma separated email addresses for EMAIL channels"
+        })));
+    }
+
+    if let Err(response) = check_template(request.template.as_deref()) {
+        return Ok(response);
+    }
+
+    match notification_service.create_channel(request).await {
+        Ok(channel) => Ok(HttpResponse::Created().json(channel)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn update_channel(
+    path: web::Path<i32>,
+    req: web::Json<UpdateChannelRequest>,
+    access: Access,
+    audit: Audit,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let channel_id = path.into_inner();
+    match require_channel(channel_id, &access, &project_service, &notification_service).await {
+        Ok(channel) => audit.before(&channel),
+        Err(response) => return Ok(response),
+    }
+    if let Err(response) = check_template(req.template.as_deref()) {
+        return Ok(response);
+    }
+
+    match notification_service.update_channel(channel_id, req.into_inner()).await {
+        Ok(Some(channel)) => Ok(HttpResponse::Ok().json(channel)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Notification channel not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn delete_channel(
+    path: web::Path<i32>,
+    access: Access,
+    audit: Audit,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let channel_id = path.into_inner();
+    match require_channel(channel_id, &access, &project_service, &notification_service).await {
+        Ok(channel) => audit.before(&channel),
+        Err(response) => return Ok(response),
+    }
+
+    match notification_service.delete_channel(channel_id).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Notification channel deleted successfully"
+        }))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Notification channel not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+/// Sends a sample quality gate failure through the channel, bypassing the delivery queue.
+pub async fn test_channel(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let channel = match require_channel(path.into_inner(), &access, &project_service, &notification_service).await {
+        Ok(channel) => channel,
+        Err(response) => return Ok(response),
+    };
+
+    let event = QualityGateEvent {
+        event: "TEST".to_string(),
+        project_id: channel.project_id.unwrap_or(0),
+        project_key: "sonarcute-test".to_string(),
+        project_name: "SonarCute test notification".to_string(),
+        status: "ERROR".to_string(),
+        previous_status: Some("OK".to_string()),
+        analysis_key: None,
+        failed_conditions: vec![Condition {
+            status: "ERROR".to_string(),
+            metric_key: "new_coverage".to_string(),
+            comparator: "LT".to_string(),
+            error_threshold: Some("80".to_string()),
+            actual_value: Some("64.2".to_string()),
+            period_index: None,
+        }],
+        dashboard_url: String::new(),
+    };
+
+    // Unlike queued notifications, a test reports template errors instead of using the default
+    let subject = channels::render(channels::DEFAULT_SUBJECT, &event).unwrap_or_default();
+    let body = match channels::render(channel.template.as_deref().unwrap_or(channels::DEFAULT_TEMPLATE), &event) {
+        Ok(body) => body,
+        Err(e) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Failed to render template: {}", e)
+            })));
+        }
+    };
+    let payload = serde_json::to_value(&event).unwrap_or(serde_json::json!({}));
+
+    match channels::send(&channel, notification_service.smtp(), &subject, &body, &payload).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Test notification sent",
+            "subject": subject,
+            "body": body
+        }))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(serde_json::json!({
+            "error": format!("Failed to send test notification: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_deliveries(
+    query: web::Query<DeliveriesQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let allowed = match query.channel_id {
+        Some(channel_id) => require_channel(channel_id, &access, &project_service, &notification_service).await.map(|_| ()),
+        None => access.require_global(Permission::ManageProjects),
+    };
+    if let Err(response) = allowed {
+        return Ok(response);
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+    match notification_service.get_deliveries(query.channel_id, query.status.as_deref(), limit).await {
+        Ok(deliveries) => Ok(HttpResponse::Ok().json(deliveries)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
@@ -0,0 +1,159 @@
+pub mod channels;
+pub mod handlers;
+
+use crate::database::notification_service::{NewDelivery, NotificationService};
+use crate::database::service::ProjectResponse;
+use crate::database::snapshot_service::SnapshotResponse;
+use crate::sonarqube::client::Condition;
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use channels::QualityGateEvent;
+use tracing::{info, warn};
+
+/// Attempts per delivery before it is marked FAILED.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// How long a claimed delivery is hidden from other senders.
+const CLAIM_LEASE_SECS: i64 = 300;
+
+const DELIVERY_BATCH: u64 = 100;
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct DeliveryStats {
+    pub sent: usize,
+    pub retrying: usize,
+    pub failed: usize,
+}
+
+pub fn build_event(project: &ProjectResponse, previous: Option<&SnapshotResponse>, snapshot: &SnapshotResponse) -> QualityGateEvent {
+    let conditions: Vec<Condition> = serde_json::from_value(snapshot.quality_gate_conditions.clone()).unwrap_or_default();
+
+    QualityGateEvent {
+        event: "QUALITY_GATE_FAILED".to_string(),
+        project_id: project.id,
+        project_key: project.project_key.clone(),
+        project_name: project.project_name.clone(),
+        status: snapshot.quality_gate_status.clone(),
+        previous_status: previous.map(|p| p.quality_gate_status.clone()),
+        analysis_key: snapshot.analysis_key.clone(),
+        failed_conditions: conditions.into_iter().filter(|c| c.status == "ERROR").collect(),
+        dashboard_url: format!("{}/dashboard?id={}", project.sonar_host_url.trim_end_matches('/'), project.project_key),
+    }
+}
+
+/// Queues a notification on every matching channel when a new snapshot's quality gate
+/// turns ERROR, then starts delivering in the background. Returns the number of queued deliveries.
+pub async fn notify_quality_gate_change(
+    notification_service: &NotificationService,
+    project: &ProjectResponse,
+    previous: Option<&SnapshotResponse>,
+    snapshot: &SnapshotResponse,
+) -> Result<usize> {
+    let was_failing = previous.is_some_and(|p| p.quality_gate_status == "ERROR");
+    if snapshot.quality_gate_status != "ERROR" || was_failing {
+        return Ok(0);
+    }
+
+    let event = build_event(project, previous, snapshot);
+    let payload = serde_json::to_value(&event)?;
+    // One event per analysis, so re-captures and webhook retries don't notify twice
+    let event_key = format!(
+        "quality-gate:{}:{}",
+        project.id,
+        snapshot.analysis_key.clone().unwrap_or_else(|| format!("snapshot-{}", snapshot.id))
+    );
+
+    let mut queued = 0;
+    for channel in notification_service.get_channels_for_project(project.id).await? {
+        let (subject, body) = channels::render_message(channel.template.as_deref(), &event);
+        let delivery = NewDelivery {
+            channel_id: channel.id,
+            project_id: project.id,
+            event_key: event_key.clone(),
+            subject,
+            body,
+            payload: payload.clone(),
+        };
+        if notification_service.enqueue_delivery(delivery).await?.is_some() {
+            queued += 1;
+        }
+    }
+
+    if queued > 0 {
+        info!("Queued {} quality gate notification(s) for {}", queued, project.project_key);
+        // Sending can take a while per channel; callers such as the webhook handler should not wait for it
+        let notification_service = notification_service.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = deliver_due(&notification_service).await {
+                warn!("Failed to deliver notifications: {}", e);
+            }
+        });
+    }
+
+    Ok(queued)
+}
+
+/// When a delivery that failed `attempts` times is retried: 1, 2, 4 then 8 minutes later, and
+/// never once `MAX_ATTEMPTS` is reached.
+pub fn retry_at(attempts: i32, failed_at: NaiveDateTime) -> Option<NaiveDateTime> {
+    (attempts < MAX_ATTEMPTS).then(|| failed_at + chrono::Duration::minutes(1 << (attempts - 1)))
+}
+
+/// Sends pending deliveries whose next attempt is due. Failures are retried with
+/// exponential backoff (1, 2, 4, 8 minutes) until `MAX_ATTEMPTS` is reached.
+pub async fn deliver_due(notification_service: &NotificationService) -> Result<DeliveryStats> {
+    let mut stats = DeliveryStats::default();
+
+    for delivery in notification_service.get_due_deliveries(DELIVERY_BATCH).await? {
+        let now = Utc::now().naive_utc();
+        if !notification_service.claim_delivery(delivery.id, now + chrono::Duration::seconds(CLAIM_LEASE_SECS)).await? {
+            continue; // Claimed by another sender
+        }
+
+        let result = match notification_service.get_channel_by_id(delivery.channel_id).await? {
+            Some(channel) => channels::send(&channel, notification_service.smtp(), &delivery.subject, &delivery.body, &delivery.payload).await,
+            None => Err(anyhow::anyhow!("Channel {} no longer exists", delivery.channel_id)),
+        };
+
+        match result {
+            Ok(()) => {
+                notification_service.mark_delivery_sent(delivery.id).await?;
+                stats.sent += 1;
+            }
+            Err(e) => {
+                let attempts = delivery.attempts + 1;
+                let retry_at = retry_at(attempts, now);
+                warn!("Notification delivery {} failed (attempt {}): {}", delivery.id, attempts, e);
+                notification_service.mark_delivery_failed(delivery.id, &e.to_string(), retry_at).await?;
+                if retry_at.is_some() {
+                    stats.retrying += 1;
+                } else {
+                    stats.failed += 1;
+                }
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_with_exponential_backoff() {
+        let failed_at = Utc::now().naive_utc();
+        let delays: Vec<i64> = (1..MAX_ATTEMPTS)
+            .map(|attempts| (retry_at(attempts, failed_at).unwrap() - failed_at).num_minutes())
+            .collect();
+        assert_eq!(delays, [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let failed_at = Utc::now().naive_utc();
+        assert_eq!(retry_at(MAX_ATTEMPTS, failed_at), None);
+        assert_eq!(retry_at(MAX_ATTEMPTS + 1, failed_at), None);
+    }
+}
@@ -0,0 +1,155 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::config::AppConfig;
+use crate::database::service::ProjectService;
+use crate::report::{self, pdf, template, ReportData, ReportOptions};
+use crate::sonarqube::admin_client::project_client;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportQuery {
+    pub sections: Option<String>,
+    pub branch: Option<String>,
+    pub title: Option<String>,
+    pub company: Option<String>,
+    pub color: Option<String>,
+    pub download: Option<bool>,
+}
+
+impl ReportQuery {
+    /// Applies the query on top of the configured defaults.
+    fn options(&self, config: &AppConfig) -> anyhow::Result<ReportOptions> {
+        let mut options = ReportOptions::from_config(&config.report);
+        if let Some(sections) = &self.sections {
+            options.set_sections(sections)?;
+        }
+        if let Some(title) = &self.title {
+            options.title = title.clone();
+        }
+        if let Some(company) = &self.company {
+            options.company = Some(company.clone()).filter(|c| !c.is_empty());
+        }
+        if let Some(color) = &self.color {
+            options.primary_color = color.clone();
+        }
+        options.primary_rgb()?;
+        Ok(options)
+    }
+}
+
+/// Resolves options, project and SonarQube data shared by all report formats, for callers who may view the project.
+/// Errors are returned as the response to send.
+async fn load_report(
+    project_id: i32,
+    query: &ReportQuery,
+    access: &Access,
+    project_service: &ProjectService,
+    config: &AppConfig,
+) -> std::result::Result<(ReportData, ReportOptions), HttpResponse> {
+    let options = query.options(config).map_err(|e| {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "error": e.to_string(),
+            "suggestion": format!("Sections: {}; colors use #RRGGBB", report::SECTIONS.join(", "))
+        }))
+    })?;
+
+    let (project, sonar_client) = project_client(project_id, Permission::ViewResults, access, project_service, config).await?;
+
+    match report::collect(&sonar_client, project, query.branch.as_deref()).await {
+        Ok(data) => Ok((data, options)),
+        Err(e) => Err(HttpResponse::BadGateway().json(serde_json::json!({
+            "error": format!("Failed to fetch report data: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_project_report_pdf(
+    path: web::Path<i32>,
+    query: web::Query<ReportQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (data, options) = match load_report(path.into_inner(), &query, &access, &project_service, &config).await {
+        Ok(report) => report,
+        Err(response) => return Ok(response),
+    };
+
+    match pdf::render(&data, &options) {
+        Ok(bytes) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", report::file_name(&data, "pdf"))))
+            .body(bytes)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to render PDF: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_project_report_html(
+    path: web::Path<i32>,
+    query: web::Query<ReportQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    render_template(path.into_inner(), &query, &access, &project_service, &config, &HTML_FORMAT).await
+}
+
+pub async fn get_project_report_markdown(
+    path: web::Path<i32>,
+    query: web::Query<ReportQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    render_template(path.into_inner(), &query, &access, &project_service, &config, &MARKDOWN_FORMAT).await
+}
+
+/// A report format rendered from a template.
+struct TemplateFormat {
+    template: &'static str,
+    content_type: &'static str,
+    extension: &'static str,
+}
+
+const HTML_FORMAT: TemplateFormat = TemplateFormat {
+    template: template::HTML_TEMPLATE,
+    content_type: "text/html; charset=utf-8",
+    extension: "html",
+};
+
+const MARKDOWN_FORMAT: TemplateFormat = TemplateFormat {
+    template: template::MARKDOWN_TEMPLATE,
+    content_type: "text/markdown; charset=utf-8",
+    extension: "md",
+};
+
+async fn render_template(
+    project_id: i32,
+    query: &ReportQuery,
+    access: &Access,
+    project_service: &ProjectService,
+    config: &AppConfig,
+    format: &TemplateFormat,
+) -> Result<HttpResponse> {
+    let (data, options) = match load_report(project_id, query, access, project_service, config).await {
+        Ok(report) => report,
+        Err(response) => return Ok(response),
+    };
+
+    match template::render(format.template, &data, &options) {
+        Ok(body) => {
+            let mut response = HttpResponse::Ok();
+            response.content_type(format.content_type);
+            // Inline by default so the HTML report opens in the browser
+            if query.download.unwrap_or(false) {
+                response.insert_header(("Content-Disposition", format!("attachment; filename=\"{}\"", report::file_name(&data, format.extension))));
+            }
+            Ok(response.body(body))
+        }
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to render {} template: {}", format.template, e)
+        }))),
+    }
+}
@@ -0,0 +1,198 @@
+pub mod handlers;
+pub mod pdf;
+pub mod template;
+
+use crate::config::ReportSettings;
+use crate::database::service::ProjectResponse;
+use crate::sonarqube::client::{Condition, Issue, SonarQubeClient, MAX_SEARCH_RESULTS};
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDateTime, Utc};
+use serde::Serialize;
+use tracing::warn;
+
+pub const SECTIONS: [&str; 4] = ["summary", "quality_gate", "coverage", "issues"];
+
+pub const SEVERITY_ORDER: [&str; 5] = ["BLOCKER", "CRITICAL", "MAJOR", "MINOR", "INFO"];
+
+const COVERAGE_METRICS: [(&str, &str); 5] = [
+    ("coverage", "Overall Coverage"),
+    ("line_coverage", "Line Coverage"),
+    ("branch_coverage", "Branch Coverage"),
+    ("lines_to_cover", "Lines to Cover"),
+    ("uncovered_lines", "Uncovered Lines"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct ReportMetric {
+    pub metric: String,
+    pub label: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeverityCount {
+    pub severity: String,
+    pub count: usize,
+}
+
+/// Everything a report shows, fetched from SonarQube at generation time.
+#[derive(Debug, Serialize)]
+pub struct ReportData {
+    pub project: ProjectResponse,
+    pub branch: Option<String>,
+    pub generated_at: NaiveDateTime,
+    pub quality_gate_status: Option<String>,
+    pub conditions: Vec<Condition>,
+    pub coverage: Vec<ReportMetric>,
+    /// Unresolved issues SonarQube counted, which can exceed the issues listed
+    pub issues_total: usize,
+    /// Whether the search limit cut off the issue list, as in `IssueCount::truncated`
+    pub truncated: bool,
+    pub issues_by_severity: Vec<SeverityCount>,
+    pub issues: Vec<Issue>,
+}
+
+/// Which sections to render and how to brand them. Branding defaults come from the
+/// `[report]` settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportOptions {
+    pub sections: Vec<String>,
+    pub title: String,
+    pub company: Option<String>,
+    pub primary_color: String,
+    /// Custom templates, which replace the built-in ones
+    #[serde(skip)]
+    pub templates_dir: Option<String>,
+}
+
+impl ReportOptions {
+    pub fn from_config(settings: &ReportSettings) -> Self {
+        Self {
+            sections: SECTIONS.iter().map(|s| s.to_string()).collect(),
+            title: settings.title.clone(),
+            company: settings.company.clone(),
+            primary_color: settings.primary_color.clone(),
+            templates_dir: settings.templates_dir.clone(),
+        }
+    }
+
+    pub fn includes(&self, section: &str) -> bool {
+        self.sections.iter().any(|s| s == section)
+    }
+
+    /// Parses a comma separated section list, rejecting unknown names.
+    pub fn set_sections(&mut self, sections: &str) -> Result<()> {
+        let sections: Vec<String> = sections
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        if let Some(unknown) = sections.iter().find(|s| !SECTIONS.contains(&s.as_str())) {
+            return Err(anyhow!("Unknown report section: {}", unknown));
+        }
+
+        self.sections = sections;
+        Ok(())
+    }
+
+    /// The primary color as RGB, from `#RRGGBB` or `RRGGBB`.
+    pub fn primary_rgb(&self) -> Result<(u8, u8, u8)> {
+        parse_hex_color(&self.primary_color)
+    }
+}
+
+pub fn parse_hex_color(value: &str) -> Result<(u8, u8, u8)> {
+    let hex = value.trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(anyhow!("Invalid color '{}', expected #RRGGBB", value));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| anyhow!("Invalid color '{}', expected #RRGGBB", value));
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Collects the report content for a project, optionally for a branch other than main.
+/// Missing coverage or quality gate data (e.g. before the first analysis) leaves those sections empty.
+pub async fn collect(sonar_client: &SonarQubeClient, project: ProjectResponse, branch: Option<&str>) -> Result<ReportData> {
+    let search = sonar_client.get_all_issues(&project.project_key, branch).await?;
+    let truncated = search.truncated();
+    let issues_total = search.total.max(0) as usize;
+    let issues = search.issues;
+
+    let metric_keys: Vec<&str> = COVERAGE_METRICS.iter().map(|(key, _)| *key).collect();
+    let coverage = match sonar_client.get_project_measures(&project.project_key, &metric_keys.join(","), branch).await {
+        Ok(response) => COVERAGE_METRICS
+            .iter()
+            .filter_map(|(key, label)| {
+                response.component.measures.iter().find(|m| m.metric == *key).map(|m| ReportMetric {
+                    metric: key.to_string(),
+                    label: label.to_string(),
+                    value: m.value.clone(),
+                })
+            })
+            .collect(),
+        Err(e) => {
+            warn!("No coverage for {}: {}", project.project_key, e);
+            Vec::new()
+        }
+    };
+
+    let quality_gate = match branch {
+        Some(branch) => sonar_client.get_branch_quality_gate(&project.project_key, branch).await,
+        None => sonar_client.get_project_quality_gate(&project.project_key).await,
+    };
+    let (quality_gate_status, conditions) = match quality_gate {
+        Ok(response) => (Some(response.project_status.status), response.project_status.conditions),
+        Err(e) => {
+            warn!("No quality gate status for {}: {}", project.project_key, e);
+            (None, Vec::new())
+        }
+    };
+
+    let issues_by_severity = SEVERITY_ORDER
+        .iter()
+        .map(|severity| SeverityCount {
+            severity: severity.to_string(),
+            count: issues.iter().filter(|i| i.severity == *severity).count(),
+        })
+        .filter(|c| c.count > 0)
+        .collect();
+
+    Ok(ReportData {
+        project,
+        branch: branch.map(str::to_string),
+        generated_at: Utc::now().naive_utc(),
+        quality_gate_status,
+        conditions,
+        coverage,
+        issues_total,
+        truncated,
+        issues_by_severity,
+        issues,
+    })
+}
+
+/// File path of an issue relative to the project, e.g. `src/main.rs` for `my-project:src/main.rs`.
+pub fn issue_location(project_key: &str, component: &str) -> String {
+    component
+        .strip_prefix(project_key)
+        .and_then(|c| c.strip_prefix(':'))
+        .unwrap_or(component)
+        .to_string()
+}
+
+/// Shown above the issue list when the search limit cut it off.
+pub fn truncation_note(data: &ReportData) -> String {
+    format!(
+        "Showing the first {} of {} issues; SonarQube returns at most {} per search.",
+        data.issues.len(),
+        data.issues_total,
+        MAX_SEARCH_RESULTS
+    )
+}
+
+/// Filename for a downloaded report, e.g. `my-project_report_2024-12-01.pdf`.
+pub fn file_name(data: &ReportData, extension: &str) -> String {
+    format!("{}_report_{}.{}", data.project.project_key, data.generated_at.format("%Y-%m-%d"), extension)
+}
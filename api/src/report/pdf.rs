@@ -0,0 +1,351 @@
+use crate::report::{self, ReportData, ReportOptions};
+use anyhow::Result;
+use printpdf::path::PaintMode;
+use printpdf::*;
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+const CONTENT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const FOOTER_HEIGHT: f32 = 18.0;
+const PT_TO_MM: f32 = 0.3528;
+
+type Rgb8 = (u8, u8, u8);
+
+const BLACK: Rgb8 = (0, 0, 0);
+const WHITE: Rgb8 = (255, 255, 255);
+const GRAY_TEXT: Rgb8 = (100, 100, 100);
+const LIGHT_GRAY: Rgb8 = (248, 249, 250);
+const BORDER: Rgb8 = (200, 200, 200);
+
+/// Same palette as the browser export (`web/src/utils/pdfExport.ts`).
+fn severity_color(severity: &str) -> Rgb8 {
+    match severity {
+        "BLOCKER" | "CRITICAL" => (220, 53, 69),
+        "MAJOR" => (255, 193, 7),
+        "MINOR" => (255, 235, 59),
+        "INFO" => (13, 202, 240),
+        _ => (108, 117, 125),
+    }
+}
+
+fn status_color(status: &str) -> Rgb8 {
+    match status {
+        "OK" => (34, 197, 94),
+        "WARN" => (251, 191, 36),
+        "ERROR" => (239, 68, 68),
+        _ => (156, 163, 175),
+    }
+}
+
+fn color(rgb: Rgb8) -> Color {
+    Color::Rgb(Rgb::new(rgb.0 as f32 / 255.0, rgb.1 as f32 / 255.0, rgb.2 as f32 / 255.0, None))
+}
+
+/// Greedy word wrap using an average Helvetica glyph width, which is close enough for table cells.
+fn wrap(text: &str, width: f32, font_size: f32) -> Vec<String> {
+    let max_chars = ((width / (font_size * PT_TO_MM * 0.52)) as usize).max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        // Paths and identifiers without spaces are split hard
+        while word.chars().count() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let split: String = word.chars().take(max_chars).collect();
+            word = word.chars().skip(max_chars).collect();
+            lines.push(split);
+        }
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+struct Column {
+    title: &'static str,
+    width: f32,
+}
+
+struct Writer {
+    doc: PdfDocumentReference,
+    layers: Vec<PdfLayerReference>,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    oblique: IndirectFontRef,
+    primary: Rgb8,
+    /// Distance of the cursor from the top of the current page, in mm
+    y: f32,
+}
+
+impl Writer {
+    fn new(title: &str, primary: Rgb8) -> Result<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+        let oblique = doc.add_builtin_font(BuiltinFont::HelveticaOblique)?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Self { doc, layers: vec![layer], regular, bold, oblique, primary, y: MARGIN })
+    }
+
+    fn layer(&self) -> &PdfLayerReference {
+        &self.layers[self.layers.len() - 1]
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Layer 1");
+        self.layers.push(self.doc.get_page(page).get_layer(layer));
+        self.y = MARGIN;
+    }
+
+    /// Starts a new page unless `height` mm still fit above the footer.
+    fn ensure_space(&mut self, height: f32) -> bool {
+        if self.y + height > PAGE_HEIGHT - FOOTER_HEIGHT {
+            self.new_page();
+            return true;
+        }
+        false
+    }
+
+    fn rect(&self, x: f32, y: f32, width: f32, height: f32, fill: Rgb8) {
+        self.layer().set_fill_color(color(fill));
+        self.layer().add_rect(Rect::new(Mm(x), Mm(PAGE_HEIGHT - y - height), Mm(x + width), Mm(PAGE_HEIGHT - y)));
+    }
+
+    fn border(&self, x: f32, y: f32, width: f32, height: f32) {
+        self.layer().set_outline_color(color(BORDER));
+        self.layer().set_outline_thickness(0.5);
+        self.layer().add_rect(
+            Rect::new(Mm(x), Mm(PAGE_HEIGHT - y - height), Mm(x + width), Mm(PAGE_HEIGHT - y)).with_mode(PaintMode::Stroke),
+        );
+    }
+
+    /// Draws text with its baseline `y` mm from the top of the page.
+    fn text(&self, text: &str, size: f32, x: f32, y: f32, font: &IndirectFontRef, rgb: Rgb8) {
+        self.layer().set_fill_color(color(rgb));
+        self.layer().use_text(text, size, Mm(x), Mm(PAGE_HEIGHT - y), font);
+    }
+
+    fn heading(&mut self, title: &str) {
+        self.ensure_space(16.0);
+        self.y += 8.0;
+        self.text(title, 14.0, MARGIN, self.y, &self.bold, BLACK);
+        self.y += 4.0;
+    }
+
+    fn table_header(&mut self, columns: &[Column]) {
+        self.rect(MARGIN, self.y, CONTENT_WIDTH, 8.0, self.primary);
+        let mut x = MARGIN;
+        for column in columns {
+            self.text(column.title, 9.0, x + 2.0, self.y + 5.5, &self.bold, WHITE);
+            x += column.width;
+        }
+        self.y += 8.0;
+    }
+
+    /// Draws one table row. `highlight` fills a column with a color and prints it in white.
+    fn table_row(&mut self, columns: &[Column], cells: &[String], highlight: Option<(usize, Rgb8)>) {
+        const FONT_SIZE: f32 = 8.0;
+        const LINE_HEIGHT: f32 = 3.6;
+
+        let wrapped: Vec<Vec<String>> = columns
+            .iter()
+            .zip(cells)
+            .map(|(column, cell)| wrap(cell, column.width - 4.0, FONT_SIZE))
+            .collect();
+        let lines = wrapped.iter().map(Vec::len).max().unwrap_or(1);
+        let height = lines as f32 * LINE_HEIGHT + 3.0;
+
+        if self.ensure_space(height) {
+            self.table_header(columns);
+        }
+
+        let mut x = MARGIN;
+        for (index, (column, cell_lines)) in columns.iter().zip(&wrapped).enumerate() {
+            let (font, rgb) = match highlight {
+                Some((highlighted, fill)) if highlighted == index => {
+                    self.rect(x, self.y, column.width, height, fill);
+                    (&self.bold, WHITE)
+                }
+                _ => (&self.regular, BLACK),
+            };
+            for (i, line) in cell_lines.iter().enumerate() {
+                self.text(line, FONT_SIZE, x + 2.0, self.y + 4.5 + i as f32 * LINE_HEIGHT, font, rgb);
+            }
+            self.border(x, self.y, column.width, height);
+            x += column.width;
+        }
+
+        self.y += height;
+    }
+
+    fn footers(&self, options: &ReportOptions, generated: &str) {
+        let pages = self.layers.len();
+        for (index, layer) in self.layers.iter().enumerate() {
+            layer.set_fill_color(color(LIGHT_GRAY));
+            layer.add_rect(Rect::new(Mm(0.0), Mm(0.0), Mm(PAGE_WIDTH), Mm(FOOTER_HEIGHT - 4.0)));
+
+            let by = match &options.company {
+                Some(company) => format!("Generated by SonarCute for {}", company),
+                None => "Generated by SonarCute Code Check Report".to_string(),
+            };
+            layer.set_fill_color(color(GRAY_TEXT));
+            layer.use_text(by, 8.0, Mm(MARGIN), Mm(8.0), &self.oblique);
+            layer.use_text(format!("Generated on: {}", generated), 8.0, Mm(MARGIN), Mm(4.0), &self.oblique);
+            layer.use_text(format!("Page {} of {}", index + 1, pages), 8.0, Mm(PAGE_WIDTH - MARGIN - 20.0), Mm(6.0), &self.regular);
+        }
+    }
+}
+
+/// Renders a report as PDF, with the same layout as the browser export.
+pub fn render(data: &ReportData, options: &ReportOptions) -> Result<Vec<u8>> {
+    let primary = options.primary_rgb()?;
+    let generated = data.generated_at.format("%Y-%m-%d %H:%M UTC").to_string();
+    let mut w = Writer::new(&options.title, primary)?;
+
+    // Header band
+    w.rect(0.0, 0.0, PAGE_WIDTH, 40.0, primary);
+    w.text(&options.title, 22.0, MARGIN, 17.0, &w.bold, WHITE);
+    w.text(&data.project.project_name, 14.0, MARGIN, 27.0, &w.regular, WHITE);
+    if let Some(company) = &options.company {
+        w.text(company, 10.0, MARGIN, 34.0, &w.regular, WHITE);
+    }
+
+    // Project details
+    w.y = 46.0;
+    w.rect(MARGIN, w.y, CONTENT_WIDTH, 22.0, LIGHT_GRAY);
+    w.border(MARGIN, w.y, CONTENT_WIDTH, 22.0);
+    let details = [
+        (format!("Project Key: {}", data.project.project_key), format!("Language: {}", data.project.language.to_uppercase())),
+        (format!("Branch: {}", data.branch.as_deref().unwrap_or("main")), format!("Generated: {}", generated)),
+        (format!("Project Path: {}", data.project.project_path), String::new()),
+    ];
+    for (i, (left, right)) in details.iter().enumerate() {
+        let y = w.y + 6.5 + i as f32 * 6.0;
+        w.text(left, 10.0, MARGIN + 5.0, y, &w.regular, BLACK);
+        w.text(right, 10.0, MARGIN + 100.0, y, &w.regular, BLACK);
+    }
+    w.y += 24.0;
+
+    if options.includes("summary") {
+        w.heading("Summary");
+
+        if let Some(status) = &data.quality_gate_status {
+            let badge_y = w.y - 10.0;
+            w.rect(PAGE_WIDTH - MARGIN - 55.0, badge_y, 55.0, 9.0, status_color(status));
+            let text_color = if status == "WARN" { BLACK } else { WHITE };
+            w.text(&format!("Quality Gate: {}", status), 10.0, PAGE_WIDTH - MARGIN - 51.0, badge_y + 6.0, &w.bold, text_color);
+        }
+
+        w.y += 6.0;
+        w.text(&format!("Total Issues: {}", data.issues_total), 12.0, MARGIN, w.y, &w.bold, BLACK);
+        w.y += 8.0;
+
+        for (i, count) in data.issues_by_severity.iter().enumerate() {
+            let x = MARGIN + (i % 4) as f32 * 45.0;
+            if i > 0 && i % 4 == 0 {
+                w.y += 7.0;
+            }
+            w.rect(x, w.y - 3.0, 4.0, 4.0, severity_color(&count.severity));
+            w.text(&format!("{}: {}", count.severity, count.count), 10.0, x + 6.0, w.y, &w.regular, BLACK);
+        }
+        w.y += 4.0;
+    }
+
+    if options.includes("quality_gate") {
+        w.heading("Quality Gate Conditions");
+
+        if data.conditions.is_empty() {
+            w.y += 6.0;
+            w.text("No quality gate conditions available.", 10.0, MARGIN, w.y, &w.regular, BLACK);
+        } else {
+            let columns = [
+                Column { title: "Metric", width: 60.0 },
+                Column { title: "Status", width: 25.0 },
+                Column { title: "Actual", width: 30.0 },
+                Column { title: "Comparator", width: 30.0 },
+                Column { title: "Threshold", width: 35.0 },
+            ];
+            w.table_header(&columns);
+            for condition in &data.conditions {
+                let cells = [
+                    condition.metric_key.clone(),
+                    condition.status.clone(),
+                    condition.actual_value.clone().unwrap_or_else(|| "-".to_string()),
+                    condition.comparator.clone(),
+                    condition.error_threshold.clone().unwrap_or_else(|| "-".to_string()),
+                ];
+                w.table_row(&columns, &cells, Some((1, status_color(&condition.status))));
+            }
+        }
+        w.y += 2.0;
+    }
+
+    if options.includes("coverage") {
+        w.heading("Coverage");
+
+        if data.coverage.is_empty() {
+            w.y += 6.0;
+            w.text("No coverage data available for this project.", 10.0, MARGIN, w.y, &w.regular, BLACK);
+        }
+        for metric in &data.coverage {
+            w.ensure_space(6.0);
+            w.y += 6.0;
+            let unit = if metric.metric.ends_with("coverage") { "%" } else { "" };
+            w.text(&format!("{}: {}{}", metric.label, metric.value, unit), 10.0, MARGIN + 5.0, w.y, &w.regular, BLACK);
+        }
+        w.y += 2.0;
+    }
+
+    if options.includes("issues") {
+        w.heading("Issues");
+
+        if data.issues.is_empty() {
+            w.y += 6.0;
+            w.text("No issues found in this scan.", 10.0, MARGIN, w.y, &w.regular, BLACK);
+        } else {
+            if data.truncated {
+                w.y += 5.0;
+                w.text(&report::truncation_note(data), 9.0, MARGIN, w.y, &w.regular, GRAY_TEXT);
+                w.y += 3.0;
+            }
+            let columns = [
+                Column { title: "Type", width: 24.0 },
+                Column { title: "Severity", width: 22.0 },
+                Column { title: "Issue Details", width: 70.0 },
+                Column { title: "File Location", width: 50.0 },
+                Column { title: "Line", width: 14.0 },
+            ];
+            w.table_header(&columns);
+            for issue in &data.issues {
+                let cells = [
+                    issue.issue_type.replace('_', " "),
+                    issue.severity.clone(),
+                    issue.message.clone(),
+                    report::issue_location(&data.project.project_key, &issue.component),
+                    issue.line.map(|l| l.to_string()).unwrap_or_else(|| "-".to_string()),
+                ];
+                w.table_row(&columns, &cells, Some((1, severity_color(&issue.severity))));
+            }
+        }
+    }
+
+    w.footers(options, &generated);
+
+    let Writer { doc, layers, .. } = w;
+    drop(layers);
+    Ok(doc.save_to_bytes()?)
+}
@@ -0,0 +1,137 @@
+use crate::report::{self, ReportData, ReportOptions};
+use anyhow::Result;
+use minijinja::Environment;
+use std::path::Path;
+
+pub const HTML_TEMPLATE: &str = "report.html";
+pub const MARKDOWN_TEMPLATE: &str = "report.md";
+
+const DEFAULT_HTML: &str = include_str!("../../templates/report.html");
+const DEFAULT_MARKDOWN: &str = include_str!("../../templates/report.md");
+
+/// Loads a template from the templates directory when the file exists there, otherwise the built-in one.
+fn load(name: &str, templates_dir: Option<&str>) -> Result<String> {
+    if let Some(dir) = templates_dir {
+        let path = Path::new(dir).join(name);
+        if path.is_file() {
+            return Ok(std::fs::read_to_string(path)?);
+        }
+    }
+
+    match name {
+        HTML_TEMPLATE => Ok(DEFAULT_HTML.to_string()),
+        MARKDOWN_TEMPLATE => Ok(DEFAULT_MARKDOWN.to_string()),
+        other => Err(anyhow::anyhow!("Unknown report template: {}", other)),
+    }
+}
+
+/// Escapes text for a Markdown table cell, including HTML that wikis would otherwise render.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('|', "\\|")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace(['\r', '\n'], " ")
+}
+
+/// Template context: the report data plus `options`, a `location` per issue, a `truncation_note`
+/// when the issue list was cut off, and without the project token.
+fn context(data: &ReportData, options: &ReportOptions) -> Result<serde_json::Value> {
+    let mut context = serde_json::to_value(data)?;
+    context["generated_at"] = serde_json::json!(data.generated_at.format("%Y-%m-%d %H:%M UTC").to_string());
+    context["options"] = serde_json::to_value(options)?;
+    if data.truncated {
+        context["truncation_note"] = serde_json::json!(report::truncation_note(data));
+    }
+
+    if let Some(project) = context["project"].as_object_mut() {
+        project.remove("sonar_token");
+    }
+    if let Some(issues) = context["issues"].as_array_mut() {
+        for (issue, source) in issues.iter_mut().zip(&data.issues) {
+            issue["location"] = serde_json::json!(report::issue_location(&data.project.project_key, &source.component));
+        }
+    }
+
+    Ok(context)
+}
+
+/// Renders the named report template. `.html` templates are auto-escaped.
+pub fn render(name: &str, data: &ReportData, options: &ReportOptions) -> Result<String> {
+    let mut environment = Environment::new();
+    environment.set_keep_trailing_newline(true);
+    environment.add_filter("md", |value: String| markdown_cell(&value));
+    environment.add_template_owned(name.to_string(), load(name, options.templates_dir.as_deref())?)?;
+
+    let template = environment.get_template(name)?;
+    Ok(template.render(context(data, options)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::SeverityCount;
+    use chrono::NaiveDate;
+
+    fn data(issues_total: usize) -> ReportData {
+        let now = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap().and_hms_opt(10, 0, 0).unwrap();
+        let issues = vec![serde_json::from_value(serde_json::json!({
+            "key": "AY1", "rule": "rust:S1481", "severity": "MAJOR", "component": "demo:src/main.rs",
+            "project": "demo", "line": 3, "message": "Remove <b>|unused</b>", "status": "OPEN",
+            "creationDate": "2024-12-01T10:00:00+0000", "updateDate": "2024-12-01T10:00:00+0000",
+            "tags": [], "type": "CODE_SMELL"
+        })).unwrap()];
+        ReportData {
+            project: serde_json::from_value(serde_json::json!({
+                "id": 1, "project_key": "demo", "project_name": "Demo", "project_path": "/src/demo",
+                "sonar_token": "squ_secret", "sonar_host_url": "http://localhost:9000", "language": "rust",
+                "sources_path": "src", "tests_path": "tests", "coverage_report_path": null,
+                "created_at": now, "updated_at": now
+            })).unwrap(),
+            branch: None,
+            generated_at: now,
+            quality_gate_status: Some("ERROR".to_string()),
+            conditions: Vec::new(),
+            coverage: Vec::new(),
+            issues_total,
+            truncated: issues_total > issues.len(),
+            issues_by_severity: vec![SeverityCount { severity: "MAJOR".to_string(), count: 1 }],
+            issues,
+        }
+    }
+
+    fn options() -> ReportOptions {
+        ReportOptions {
+            sections: report::SECTIONS.iter().map(|s| s.to_string()).collect(),
+            title: "Report".to_string(),
+            company: None,
+            primary_color: "#428BCA".to_string(),
+            templates_dir: None,
+        }
+    }
+
+    #[test]
+    fn markdown_shows_the_total_and_escapes_cells() {
+        let markdown = render(MARKDOWN_TEMPLATE, &data(1), &options()).unwrap();
+
+        assert!(markdown.contains("**Total Issues: 1**"), "{}", markdown);
+        assert!(markdown.contains("| CODE SMELL | MAJOR | Remove &lt;b&gt;\\|unused&lt;/b&gt; | `src/main.rs` | 3 |"), "{}", markdown);
+        assert!(!markdown.contains("Showing the first"), "{}", markdown);
+        assert!(!markdown.contains("squ_secret"));
+    }
+
+    #[test]
+    fn truncated_reports_say_so() {
+        let data = data(12_000);
+        let note = "Showing the first 1 of 12000 issues; SonarQube returns at most 10000 per search.";
+
+        let markdown = render(MARKDOWN_TEMPLATE, &data, &options()).unwrap();
+        assert!(markdown.contains("**Total Issues: 12000**"), "{}", markdown);
+        assert!(markdown.contains(&format!("_{}_", note)), "{}", markdown);
+
+        let html = render(HTML_TEMPLATE, &data, &options()).unwrap();
+        assert!(html.contains("Total Issues: 12000"), "{}", html);
+        assert!(html.contains(&format!("<p class=\"note\">{}</p>", note)), "{}", html);
+        assert!(html.contains("Remove &lt;b&gt;|unused&lt;&#x2f;b&gt;"), "{}", html);
+    }
+}
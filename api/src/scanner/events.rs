@@ -0,0 +1,128 @@
+use crate::database::scan_service::ScanRunSummary;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Events kept per run for clients reconnecting with `Last-Event-ID`.
+const MAX_EVENTS: usize = 10_000;
+/// How long the events of a finished run stay available.
+const RETAIN_FINISHED: Duration = Duration::from_secs(300);
+/// Logs of runs that stopped publishing without finishing, e.g. after a failed database update.
+const RETAIN_IDLE: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone)]
+pub struct ScanEvent {
+    pub id: Option<u64>,
+    pub event: &'static str, // "status", "log" or "done"
+    pub data: Value,
+}
+
+impl ScanEvent {
+    pub fn new(event: &'static str, data: Value) -> Self {
+        Self { id: None, event, data }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.event == "done"
+    }
+
+    /// The event in `text/event-stream` format.
+    pub fn to_sse(&self) -> String {
+        match self.id {
+            Some(id) => format!("id: {}\nevent: {}\ndata: {}\n\n", id, self.event, self.data),
+            None => format!("event: {}\ndata: {}\n\n", self.event, self.data),
+        }
+    }
+}
+
+struct RunLog {
+    events: VecDeque<ScanEvent>,
+    next_id: u64,
+    sender: Option<broadcast::Sender<ScanEvent>>,
+    updated: Instant,
+}
+
+pub struct Subscription {
+    pub backlog: VecDeque<ScanEvent>,
+    pub receiver: broadcast::Receiver<ScanEvent>,
+}
+
+/// Output lines and status changes of the scans running in this process. Events are numbered
+/// per run, so a client can resume after the last event it received.
+#[derive(Clone, Default)]
+pub struct ScanEvents {
+    logs: Arc<Mutex<HashMap<i32, RunLog>>>,
+}
+
+impl ScanEvents {
+    pub fn status(&self, run_id: i32, status: &str, phase: Option<&str>, ce_task_id: Option<&str>) {
+        self.publish(run_id, ScanEvent::new("status", json!({ "status": status, "phase": phase, "ce_task_id": ce_task_id })));
+    }
+
+    pub fn line(&self, run_id: i32, stream: &str, line: &str) {
+        self.publish(run_id, ScanEvent::new("log", json!({ "stream": stream, "line": line })));
+    }
+
+    pub fn done(&self, run_id: i32, run: &ScanRunSummary) {
+        self.publish(run_id, ScanEvent::new("done", json!(run)));
+        self.close(run_id);
+    }
+
+    /// Stops publishing for a run. Subscribers see the channel close and fall back to the database.
+    pub fn close(&self, run_id: i32) {
+        let mut logs = self.logs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(log) = logs.get_mut(&run_id) {
+            log.updated = Instant::now();
+            log.sender = None;
+        }
+    }
+
+    fn publish(&self, run_id: i32, mut event: ScanEvent) {
+        let mut logs = self.logs.lock().unwrap_or_else(|e| e.into_inner());
+        if !logs.contains_key(&run_id) {
+            logs.retain(|_, log| log.updated.elapsed() < if log.sender.is_none() { RETAIN_FINISHED } else { RETAIN_IDLE });
+        }
+
+        let log = logs.entry(run_id).or_insert_with(|| RunLog {
+            events: VecDeque::new(),
+            next_id: 1,
+            sender: Some(broadcast::channel(1024).0),
+            updated: Instant::now(),
+        });
+        let Some(sender) = log.sender.clone() else {
+            return;
+        };
+
+        event.id = Some(log.next_id);
+        log.next_id += 1;
+        log.updated = Instant::now();
+        if log.events.len() == MAX_EVENTS {
+            log.events.pop_front();
+        }
+        // No receivers is fine; the event stays in the backlog
+        let _ = sender.send(event.clone());
+        log.events.push_back(event);
+    }
+
+    /// Events after `after` plus a receiver for new ones, or `None` when the run is not running
+    /// in this process and has not recently finished here.
+    pub fn subscribe(&self, run_id: i32, after: Option<u64>) -> Option<Subscription> {
+        let logs = self.logs.lock().unwrap_or_else(|e| e.into_inner());
+        let log = logs.get(&run_id)?;
+
+        let backlog = log
+            .events
+            .iter()
+            .filter(|e| after.is_none_or(|after| e.id.is_some_and(|id| id > after)))
+            .cloned()
+            .collect();
+        let receiver = match &log.sender {
+            Some(sender) => sender.subscribe(),
+            // A receiver whose sender is already dropped reports the channel as closed
+            None => broadcast::channel(1).1,
+        };
+        Some(Subscription { backlog, receiver })
+    }
+}
@@ -0,0 +1,530 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse, Result};
+use crate::auth::permissions::{Access, Permission};
+use crate::auth::{AuthConfig, AuthUser};
+use crate::config::AppConfig;
+use crate::database::scan_service::{ScanRunResponse, ScanRunSummary, ScanService, ACTIVE_STATUSES};
+use crate::database::service::ProjectService;
+use crate::scanner::events::{ScanEvent, ScanEvents};
+use crate::scanner::{mask_token, ScanRunner};
+use crate::sonarqube::command;
+use futures_util::stream;
+use sea_orm::SqlErr;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::warn;
+
+/// How often a stream reads a run from the database when it is not running in this process.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Idle streams get a comment line this often so that proxies keep the connection open.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanRunsQuery {
+    pub limit: Option<u64>,
+}
+
+pub async fn start_scan(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+    scan_runner: web::Data<ScanRunner>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    if !scan_runner.is_enabled() {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "error": "The scan runner is disabled",
+            "suggestion": "Set SCAN_RUNNER_ENABLED=true on a SonarCute instance that can access the project paths"
+        })));
+    }
+
+    let project = match project_service.get_project_by_id(path.into_inner()).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require(&project, Permission::RunAnalysis) {
+        return Ok(response);
+    }
+
+    match scan_service.get_active_run(project.id).await {
+        Ok(Some(active)) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("A scan of {} is already {}", project.project_key, active.status.to_lowercase()),
+                "run": active
+            })));
+        }
+        Ok(None) => {}
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    }
+
+    // Stored for display only; the runner builds the command again with the token and the commit checked out when it starts
+    let display_command = mask_token(&command::scan_command(&project, None, config.sonar.branch_analysis), &project.sonar_token);
+
+    match scan_service.create_run(project.id, display_command).await {
+        Ok(run) => {
+            scan_runner.notify();
+            Ok(HttpResponse::Accepted().json(run))
+        }
+        // Another request queued a run since the check above
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("A scan of {} is already queued or running", project.project_key)
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_project_scans(
+    path: web::Path<i32>,
+    query: web::Query<ScanRunsQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+) -> Result<HttpResponse> {
+    let project_id = path.into_inner();
+    if let Err(response) = access.require_project(&project_service, project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+
+    match scan_service.get_runs(project_id, limit).await {
+        Ok(runs) => Ok(HttpResponse::Ok().json(runs)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_scan(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+) -> Result<HttpResponse> {
+    match scan_service.get_run(path.into_inner()).await {
+        Ok(Some(run)) => match access.require_project(&project_service, run.project_id, Permission::ViewResults).await {
+            Ok(_) => Ok(HttpResponse::Ok().json(run)),
+            Err(response) => Ok(response),
+        },
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Scan run not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn cancel_scan(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+) -> Result<HttpResponse> {
+    let run_id = path.into_inner();
+
+    let run = match scan_service.get_run(run_id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Scan run not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require_project(&project_service, run.project_id, Permission::RunAnalysis).await {
+        return Ok(response);
+    }
+
+    if !ACTIVE_STATUSES.contains(&run.status.as_str()) {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": format!("Scan run {} already finished with status {}", run.id, run.status)
+        })));
+    }
+
+    match scan_service.request_cancel(run_id).await {
+        Ok(Some(run)) => Ok(HttpResponse::Ok().json(run)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Scan run not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanEventsQuery {
+    /// Alternative to the `Last-Event-ID` header for clients that cannot set headers.
+    pub last_event_id: Option<u64>,
+}
+
+enum EventSource {
+    /// Events published by the runner in this process.
+    Live {
+        backlog: VecDeque<ScanEvent>,
+        receiver: broadcast::Receiver<ScanEvent>,
+    },
+    /// The run as stored in the database, for runs queued or running on another replica.
+    /// `attach` switches to live events once this process starts the run. Output stored since
+    /// the last poll is sent from `sent`; `None` skips what was stored before the first poll.
+    Poll {
+        status: Option<(String, Option<String>)>,
+        attach: bool,
+        polled: bool,
+        sent: Option<SentOutput>,
+        pending: VecDeque<ScanEvent>,
+    },
+    /// A fixed list of events, e.g. the stored output of a finished run.
+    Replay(VecDeque<ScanEvent>),
+    Finished,
+}
+
+/// How much of a stream's stored output was sent: its length in bytes and the last line sent,
+/// used to find the position again once the runner cut older output to the output limit.
+#[derive(Debug, Clone, Default)]
+struct SentLines {
+    len: usize,
+    last_line: String,
+}
+
+impl SentLines {
+    /// Complete lines of `output` not sent yet, marked as sent.
+    fn take<'a>(&mut self, output: &'a str) -> Vec<&'a str> {
+        let start = match output.get(..self.len) {
+            Some(sent) if sent.ends_with(&self.last_line) => self.len,
+            // Older output was dropped since the last poll: continue after the last line sent, or
+            // from the start when it was dropped too
+            _ => output.rfind(&format!("\n{}", self.last_line)).map(|i| i + 1 + self.last_line.len()).unwrap_or(0),
+        };
+        let Some(end) = output[start..].rfind('\n').map(|i| start + i + 1) else {
+            return Vec::new();
+        };
+
+        let lines: Vec<&str> = output[start..end].lines().collect();
+        self.len = end;
+        if let Some(last) = lines.last() {
+            self.last_line = format!("{}\n", last);
+        }
+        lines
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SentOutput {
+    stdout: SentLines,
+    stderr: SentLines,
+}
+
+impl SentOutput {
+    /// Everything stored so far counts as sent.
+    fn up_to(run: &ScanRunResponse) -> Self {
+        let mut sent = Self::default();
+        sent.log_events(run);
+        sent
+    }
+
+    /// `log` events for the output stored since the last call.
+    fn log_events(&mut self, run: &ScanRunResponse) -> VecDeque<ScanEvent> {
+        let mut events = VecDeque::new();
+        for (stream, sent, output) in [("stdout", &mut self.stdout, &run.stdout), ("stderr", &mut self.stderr, &run.stderr)] {
+            for line in sent.take(output) {
+                events.push_back(ScanEvent::new("log", serde_json::json!({ "stream": stream, "line": line })));
+            }
+        }
+        events
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stdout.len == 0 && self.stderr.len == 0
+    }
+}
+
+struct EventStream {
+    run_id: i32,
+    last_id: Option<u64>,
+    source: EventSource,
+    events: ScanEvents,
+    scan_service: ScanService,
+    idle_since: Instant,
+}
+
+impl EventStream {
+    /// Polls the database. A stream that already sent live events only continues with output
+    /// stored after its first poll.
+    fn poll(attach: bool, sent: Option<SentOutput>) -> EventSource {
+        EventSource::Poll { status: None, attach, polled: false, sent, pending: VecDeque::new() }
+    }
+
+    /// The next chunk of the `text/event-stream` body, or `None` once the run finished.
+    async fn next_chunk(&mut self) -> Option<String> {
+        loop {
+            match &mut self.source {
+                EventSource::Finished => return None,
+                EventSource::Replay(events) => match events.pop_front() {
+                    Some(event) => return Some(self.emit(event)),
+                    None => self.source = EventSource::Finished,
+                },
+                EventSource::Live { backlog, receiver } => {
+                    if let Some(event) = backlog.pop_front() {
+                        return Some(self.emit(event));
+                    }
+                    match tokio::time::timeout(KEEP_ALIVE, receiver.recv()).await {
+                        Ok(Ok(event)) => {
+                            if event.id.is_some_and(|id| self.last_id.is_some_and(|last| id <= last)) {
+                                continue;
+                            }
+                            return Some(self.emit(event));
+                        }
+                        // Too slow to keep up; continue from the backlog
+                        Ok(Err(RecvError::Lagged(_))) => {
+                            self.source = match self.events.subscribe(self.run_id, self.last_id) {
+                                Some(subscription) => EventSource::Live { backlog: subscription.backlog, receiver: subscription.receiver },
+                                None => Self::poll(false, None),
+                            };
+                        }
+                        // The runner stopped publishing without a result; the database has it
+                        Ok(Err(RecvError::Closed)) => self.source = Self::poll(false, None),
+                        Err(_) => return Some(self.keep_alive()),
+                    }
+                }
+                EventSource::Poll { status, attach, polled, sent, pending } => {
+                    if let Some(event) = pending.pop_front() {
+                        return Some(self.emit(event));
+                    }
+                    if *polled {
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                    }
+                    *polled = true;
+
+                    // Live events repeat the output from the start, so only attach before any was sent
+                    if *attach
+                        && sent.as_ref().is_none_or(SentOutput::is_empty)
+                        && let Some(subscription) = self.events.subscribe(self.run_id, self.last_id)
+                    {
+                        self.source = EventSource::Live { backlog: subscription.backlog, receiver: subscription.receiver };
+                        continue;
+                    }
+
+                    match self.scan_service.get_run(self.run_id).await {
+                        Ok(Some(run)) => {
+                            let sent = sent.get_or_insert_with(|| SentOutput::up_to(&run));
+                            pending.extend(sent.log_events(&run));
+                            let current = (run.status.clone(), run.phase.clone());
+                            if !ACTIVE_STATUSES.contains(&run.status.as_str()) {
+                                pending.push_back(done_event(run));
+                            } else if status.as_ref() != Some(&current) {
+                                *status = Some(current);
+                                pending.push_back(status_event(&run));
+                            }
+                            if !pending.is_empty() {
+                                continue;
+                            }
+                        }
+                        // Deleted together with its project
+                        Ok(None) => self.source = EventSource::Finished,
+                        Err(e) => warn!("Failed to read scan run {}: {}", self.run_id, e),
+                    }
+
+                    if self.idle_since.elapsed() >= KEEP_ALIVE {
+                        return Some(self.keep_alive());
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, event: ScanEvent) -> String {
+        if event.id.is_some() {
+            self.last_id = event.id;
+        }
+        if event.is_done() {
+            self.source = EventSource::Finished;
+        }
+        self.idle_since = Instant::now();
+        event.to_sse()
+    }
+
+    fn keep_alive(&mut self) -> String {
+        self.idle_since = Instant::now();
+        ": keep-alive\n\n".to_string()
+    }
+}
+
+fn status_event(run: &ScanRunResponse) -> ScanEvent {
+    ScanEvent::new("status", serde_json::json!({ "status": run.status, "phase": run.phase, "ce_task_id": run.ce_task_id }))
+}
+
+fn done_event(run: ScanRunResponse) -> ScanEvent {
+    ScanEvent::new("done", serde_json::json!(ScanRunSummary::from(run)))
+}
+
+/// A short-lived token that opens the event stream of a scan as `?token=`, for browsers'
+/// `EventSource`, which cannot send an Authorization header.
+pub async fn create_scan_events_token(
+    path: web::Path<i32>,
+    user: AuthUser,
+    access: Access,
+    auth_config: web::Data<AuthConfig>,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+) -> Result<HttpResponse> {
+    let run_id = path.into_inner();
+
+    let run = match scan_service.get_run(run_id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Scan run not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require_project(&project_service, run.project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    match auth_config.scan_events_token(&user, run_id) {
+        Ok((token, expires_at)) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "token": token,
+            "expires_at": expires_at
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to create token: {}", e)
+        }))),
+    }
+}
+
+/// Streams a scan's output lines and status changes as Server-Sent Events until it finishes.
+/// Reconnecting clients send `Last-Event-ID` to continue after the last event they received.
+pub async fn stream_scan_events(
+    req: HttpRequest,
+    path: web::Path<i32>,
+    query: web::Query<ScanEventsQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    scan_service: web::Data<ScanService>,
+    scan_runner: web::Data<ScanRunner>,
+) -> Result<HttpResponse> {
+    let run_id = path.into_inner();
+
+    let run = match scan_service.get_run(run_id).await {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Scan run not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require_project(&project_service, run.project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    let last_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(query.last_event_id);
+
+    let events = scan_runner.events().clone();
+    let source = if let Some(subscription) = events.subscribe(run_id, last_id) {
+        EventSource::Live { backlog: subscription.backlog, receiver: subscription.receiver }
+    } else if ACTIVE_STATUSES.contains(&run.status.as_str()) {
+        // A reconnecting client already has the output stored so far
+        EventStream::poll(true, last_id.is_none().then(SentOutput::default))
+    } else {
+        // Finished and no longer in memory: replay the stored output, unless the client already saw it
+        let mut replay = match last_id {
+            None => SentOutput::default().log_events(&run),
+            Some(_) => VecDeque::new(),
+        };
+        replay.push_back(done_event(run));
+        EventSource::Replay(replay)
+    };
+
+    let state = EventStream {
+        run_id,
+        last_id,
+        source,
+        events,
+        scan_service: scan_service.get_ref().clone(),
+        idle_since: Instant::now(),
+    };
+    let body = stream::unfold(state, |mut state| async move {
+        let chunk = state.next_chunk().await?;
+        Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), state))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Stops nginx from buffering the stream
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_complete_lines_once() {
+        let mut sent = SentLines::default();
+        assert_eq!(sent.take("line 1\nline 2\nline"), vec!["line 1", "line 2"]);
+        assert_eq!(sent.take("line 1\nline 2\nline"), Vec::<&str>::new());
+        assert_eq!(sent.take("line 1\nline 2\nline 3\n"), vec!["line 3"]);
+    }
+
+    #[test]
+    fn continues_after_the_last_line_sent_once_older_output_was_dropped() {
+        let mut sent = SentLines::default();
+        assert_eq!(sent.take("line 1\nline 2\n"), vec!["line 1", "line 2"]);
+
+        let cut = "[earlier output truncated]\nline 2\nline 3\nline 4\n";
+        assert_eq!(sent.take(cut), vec!["line 3", "line 4"]);
+    }
+
+    #[test]
+    fn starts_over_when_the_last_line_sent_was_dropped() {
+        let mut sent = SentLines::default();
+        assert_eq!(sent.take("line 1\n"), vec!["line 1"]);
+
+        let cut = "[earlier output truncated]\nline 5\nline 6\n";
+        assert_eq!(sent.take(cut), vec!["[earlier output truncated]", "line 5", "line 6"]);
+    }
+}
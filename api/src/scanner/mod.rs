@@ -0,0 +1,558 @@
+pub mod events;
+pub mod handlers;
+
+use crate::config::AppConfig;
+use crate::database::notification_service::NotificationService;
+use crate::database::scan_service::{OutputUpdate, ScanRunOutcome, ScanRunResponse, ScanService};
+use crate::database::service::{ProjectResponse, ProjectService};
+use crate::database::snapshot_service::SnapshotService;
+use crate::git;
+use crate::sonarqube::admin_client::admin_client;
+use crate::sonarqube::{command, snapshot};
+use events::ScanEvents;
+use anyhow::Result;
+use chrono::Utc;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, Notify, OwnedSemaphorePermit, Semaphore};
+use tracing::{error, info, warn};
+
+/// How often new output is persisted and cancellation is checked while a scan runs.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// How long to wait for the last output lines after the scanner exited.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest wait for a single Compute Engine task request.
+const CE_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Running scans without a heartbeat for this long are failed.
+const STALE_AFTER_SECS: i64 = 60;
+
+const MASK: &str = "****";
+
+/// Scanner log line printed once the analysis is done and the report is being sent.
+const REPORT_GENERATED: &str = "Analysis report generated";
+/// Scanner log lines link to the Compute Engine task processing the uploaded report.
+const CE_TASK_URL: &str = "/api/ce/task?id=";
+
+#[derive(Debug, Clone, Copy)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn name(self) -> &'static str {
+        match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Captured output of one stream. Only the last `limit` bytes are kept, since the end of a
+/// scanner log is where failures are reported. Lines not yet persisted are also kept in `pending`,
+/// so a heartbeat only sends what is new.
+struct OutputBuffer {
+    text: String,
+    pending: String,
+    limit: usize,
+    truncated: bool,
+    /// Lines were dropped since the last update, so the stored output must be replaced.
+    trimmed: bool,
+}
+
+impl OutputBuffer {
+    fn new(limit: usize) -> Self {
+        Self { text: String::new(), pending: String::new(), limit, truncated: false, trimmed: false }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+        if !self.trimmed {
+            self.pending.push_str(line);
+            self.pending.push('\n');
+        }
+
+        if self.text.len() > self.limit {
+            let mut cut = self.text.len() - self.limit;
+            while !self.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            let cut = self.text[cut..].find('\n').map(|i| cut + i + 1).unwrap_or(cut);
+            self.text.drain(..cut);
+            self.pending.clear();
+            self.truncated = true;
+            self.trimmed = true;
+        }
+    }
+
+    /// What changed since the last call: the new lines, or the whole output once older lines
+    /// were dropped, so the stored output never exceeds the limit either.
+    fn take_update(&mut self) -> OutputUpdate {
+        if std::mem::take(&mut self.trimmed) {
+            OutputUpdate::Replace(self.contents())
+        } else {
+            OutputUpdate::Append(std::mem::take(&mut self.pending))
+        }
+    }
+
+    fn contents(&self) -> String {
+        if self.truncated {
+            format!("[earlier output truncated]\n{}", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// Output of a running scan and the progress it reveals.
+struct ScanOutput {
+    stdout: OutputBuffer,
+    stderr: OutputBuffer,
+    phase: &'static str,
+    ce_task_id: Option<String>,
+}
+
+impl ScanOutput {
+    fn new(limit: usize) -> Self {
+        Self {
+            stdout: OutputBuffer::new(limit),
+            stderr: OutputBuffer::new(limit),
+            phase: "SCANNING",
+            ce_task_id: None,
+        }
+    }
+
+    /// Stores a line and returns whether it moved the scan to a new phase or named its
+    /// Compute Engine task.
+    fn push(&mut self, stream: Stream, line: &str) -> bool {
+        match stream {
+            Stream::Stdout => self.stdout.push_line(line),
+            Stream::Stderr => self.stderr.push_line(line),
+        }
+
+        let mut changed = false;
+        if self.phase == "SCANNING" && line.contains(REPORT_GENERATED) {
+            self.phase = "UPLOADING";
+            changed = true;
+        }
+        if self.ce_task_id.is_none()
+            && let Some(start) = line.find(CE_TASK_URL)
+        {
+            let id: String = line[start + CE_TASK_URL.len()..]
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+                .collect();
+            if !id.is_empty() {
+                self.ce_task_id = Some(id);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Runs queued scans as child processes in each project's directory.
+///
+/// Runs live in the `scan_runs` table, so any replica can pick them up; each replica runs at most
+/// `SCAN_CONCURRENCY` scans at a time. Disabled unless `SCAN_RUNNER_ENABLED=true`, because scans
+/// execute build tools on the API host.
+#[derive(Clone)]
+pub struct ScanRunner {
+    scan_service: ScanService,
+    project_service: ProjectService,
+    snapshot_service: SnapshotService,
+    notification_service: NotificationService,
+    events: ScanEvents,
+    config: Arc<AppConfig>,
+    enabled: bool,
+    runner_id: String,
+    permits: Arc<Semaphore>,
+    wake: Arc<Notify>,
+    timeout: Duration,
+    max_output_bytes: usize,
+    poll: Duration,
+}
+
+impl ScanRunner {
+    pub fn new(
+        scan_service: ScanService,
+        project_service: ProjectService,
+        snapshot_service: SnapshotService,
+        notification_service: NotificationService,
+        config: Arc<AppConfig>,
+    ) -> Self {
+        let settings = &config.scanner;
+
+        Self {
+            scan_service,
+            project_service,
+            snapshot_service,
+            notification_service,
+            events: ScanEvents::default(),
+            enabled: settings.enabled,
+            runner_id: format!("{}-{}", config.server.instance_name, std::process::id()),
+            permits: Arc::new(Semaphore::new(settings.concurrency)),
+            wake: Arc::new(Notify::new()),
+            timeout: Duration::from_secs(settings.timeout_secs),
+            max_output_bytes: settings.max_output_bytes,
+            poll: Duration::from_secs(10),
+            config,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn events(&self) -> &ScanEvents {
+        &self.events
+    }
+
+    /// Starts the dispatch loop when the runner is enabled.
+    pub fn start(&self) {
+        if !self.enabled {
+            return;
+        }
+
+        info!("Starting scan runner {} ({} concurrent scans)", self.runner_id, self.permits.available_permits());
+        let runner = self.clone();
+        actix_web::rt::spawn(async move {
+            loop {
+                if let Err(e) = runner.dispatch().await {
+                    warn!("Scan dispatch failed: {}", e);
+                }
+                // Woken by new runs, finished runs, or the poll interval for runs queued on other replicas
+                let _ = tokio::time::timeout(runner.poll, runner.wake.notified()).await;
+            }
+        });
+    }
+
+    /// Signals the dispatch loop that a run was queued.
+    pub fn notify(&self) {
+        self.wake.notify_one();
+    }
+
+    async fn dispatch(&self) -> Result<()> {
+        let stale_before = Utc::now().naive_utc() - chrono::Duration::seconds(STALE_AFTER_SECS);
+        let failed = self.scan_service.fail_stale_runs(stale_before).await?;
+        if failed > 0 {
+            warn!("Failed {} scan run(s) abandoned by their runner", failed);
+        }
+
+        while let Ok(permit) = self.permits.clone().try_acquire_owned() {
+            let Some(run) = self.scan_service.claim_next(&self.runner_id).await? else {
+                break;
+            };
+
+            let runner = self.clone();
+            actix_web::rt::spawn(async move {
+                runner.execute(run, permit).await;
+                runner.notify();
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, run: ScanRunResponse, _permit: OwnedSemaphorePermit) {
+        info!("Starting scan run {} for project {}", run.id, run.project_id);
+        let timer = Instant::now();
+        self.events.status(run.id, "RUNNING", Some("SCANNING"), None);
+
+        let mut outcome = match self.project_service.get_project_by_id(run.project_id).await {
+            Ok(Some(project)) => {
+                let mut outcome = self.run_scan(&run, &project).await;
+                if outcome.status == "SUCCEEDED"
+                    && let Some(task_id) = outcome.ce_task_id.clone()
+                {
+                    self.wait_for_processing(&run, &project, &task_id, &mut outcome, timer).await;
+                }
+                outcome
+            }
+            Ok(None) => failed("Project no longer exists"),
+            Err(e) => failed(&format!("Database error: {}", e)),
+        };
+        outcome.duration_ms = timer.elapsed().as_millis() as i64;
+
+        info!("Scan run {} finished: {}", run.id, outcome.status);
+        match self.scan_service.finish_run(run.id, outcome).await {
+            Ok(Some(summary)) => self.events.done(run.id, &summary),
+            Ok(None) => self.events.close(run.id),
+            Err(e) => {
+                error!("Failed to record result of scan run {}: {}", run.id, e);
+                self.events.close(run.id);
+            }
+        }
+    }
+
+    async fn run_scan(&self, run: &ScanRunResponse, project: &ProjectResponse) -> ScanRunOutcome {
+        if !Path::new(&project.project_path).is_dir() {
+            return failed(&format!("Project path {} does not exist on the SonarCute host", project.project_path));
+        }
+
+        // The commit is read as the scan starts, since the checkout may have moved while the run was queued
+        let git = git::lookup(&project.project_path).await;
+        let display_command = mask_token(&command::scan_command(project, git.as_ref(), self.config.sonar.branch_analysis), &project.sonar_token);
+        if let Err(e) = self.scan_service.set_git_metadata(run.id, git.as_ref(), display_command).await {
+            warn!("Failed to record git metadata of scan run {}: {}", run.id, e);
+        }
+
+        // Relative programs such as `./gradlew` are resolved against the project, not our working directory.
+        // The scanner leads a new process group, so the workers it forks can be killed along with it.
+        let args = command::scan_args(project, git.as_ref(), self.config.sonar.branch_analysis);
+        let program = Path::new(&project.project_path).join(&args[0]);
+        let spawned = Command::new(program)
+            .args(&args[1..])
+            .env("SONAR_TOKEN", &project.sonar_token)
+            .current_dir(&project.project_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => return failed(&format!("Failed to start scanner: {}", e)),
+        };
+        let process_group = child.id();
+
+        let (sender, mut lines) = mpsc::unbounded_channel();
+        if let Some(stdout) = child.stdout.take() {
+            read_lines(stdout, Stream::Stdout, sender.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            read_lines(stderr, Stream::Stderr, sender.clone());
+        }
+        drop(sender);
+
+        let mut output = ScanOutput::new(self.max_output_bytes);
+
+        let deadline = tokio::time::sleep(self.timeout);
+        tokio::pin!(deadline);
+        let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+
+        let (status, exit_code, error) = loop {
+            tokio::select! {
+                Some((stream, line)) = lines.recv() => self.record(run.id, project, &mut output, stream, &line).await,
+                exit = child.wait() => {
+                    break match exit {
+                        Ok(exit) if exit.success() => ("SUCCEEDED", exit.code(), None),
+                        Ok(exit) => ("FAILED", exit.code(), Some(format!("Scanner exited with {}", exit))),
+                        Err(e) => ("FAILED", None, Some(format!("Failed to wait for scanner: {}", e))),
+                    };
+                }
+                _ = &mut deadline => {
+                    kill(&mut child, process_group).await;
+                    break ("TIMED_OUT", None, Some(format!("Scan exceeded the {}s timeout", self.timeout.as_secs())));
+                }
+                _ = flush.tick() => {
+                    match self.scan_service.heartbeat(run.id, &output.stdout.take_update(), &output.stderr.take_update()).await {
+                        Ok(true) => {
+                            kill(&mut child, process_group).await;
+                            break ("CANCELED", None, Some("Canceled by request".to_string()));
+                        }
+                        Ok(false) => {}
+                        Err(e) => warn!("Failed to store output of scan run {}: {}", run.id, e),
+                    }
+                }
+            }
+        };
+
+        // Collect output written just before exit. Processes forked by the scanner (e.g. a Gradle
+        // daemon) can keep the pipes open, so stop once nothing arrives for a while.
+        while let Ok(Some((stream, line))) = tokio::time::timeout(DRAIN_TIMEOUT, lines.recv()).await {
+            self.record(run.id, project, &mut output, stream, &line).await;
+        }
+        // Shown while SonarQube processes the report
+        if let Err(e) = self.scan_service.heartbeat(run.id, &output.stdout.take_update(), &output.stderr.take_update()).await {
+            warn!("Failed to store output of scan run {}: {}", run.id, e);
+        }
+
+        ScanRunOutcome {
+            status: status.to_string(),
+            exit_code,
+            error,
+            ce_task_id: output.ce_task_id,
+            quality_gate_status: None,
+            stdout: output.stdout.contents(),
+            stderr: output.stderr.contents(),
+            duration_ms: 0,
+        }
+    }
+
+    /// Stores and publishes an output line, and records a phase change it reveals.
+    async fn record(&self, run_id: i32, project: &ProjectResponse, output: &mut ScanOutput, stream: Stream, line: &str) {
+        let line = mask_token(line, &project.sonar_token);
+        self.events.line(run_id, stream.name(), &line);
+
+        if output.push(stream, &line) {
+            self.events.status(run_id, "RUNNING", Some(output.phase), output.ce_task_id.as_deref());
+            if let Err(e) = self.scan_service.set_phase(run_id, output.phase, output.ce_task_id.as_deref()).await {
+                warn!("Failed to record phase of scan run {}: {}", run_id, e);
+            }
+        }
+    }
+
+    /// Waits for SonarQube to process the uploaded report, then captures a snapshot of the
+    /// analysis. A failed Compute Engine task fails the run.
+    async fn wait_for_processing(&self, run: &ScanRunResponse, project: &ProjectResponse, task_id: &str, outcome: &mut ScanRunOutcome, timer: Instant) {
+        self.events.status(run.id, "RUNNING", Some("PROCESSING"), Some(task_id));
+        if let Err(e) = self.scan_service.set_phase(run.id, "PROCESSING", Some(task_id)).await {
+            warn!("Failed to record phase of scan run {}: {}", run.id, e);
+        }
+
+        let sonar_host_url = self.config.sonar.host_url.clone();
+        let sonar_client = match admin_client(&self.project_service, &sonar_host_url).await {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Not waiting for the analysis of scan run {}: {}", run.id, e);
+                return;
+            }
+        };
+
+        loop {
+            if timer.elapsed() >= self.timeout {
+                outcome.status = "TIMED_OUT".to_string();
+                outcome.error = Some(format!("Compute Engine task {} was not processed within the {}s timeout", task_id, self.timeout.as_secs()));
+                return;
+            }
+
+            let no_output = OutputUpdate::Append(String::new());
+            match self.scan_service.heartbeat(run.id, &no_output, &no_output).await {
+                Ok(true) => {
+                    outcome.status = "CANCELED".to_string();
+                    outcome.error = Some("Canceled by request".to_string());
+                    return;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Failed to update scan run {}: {}", run.id, e),
+            }
+
+            // A hanging request must not hold the run past its timeout
+            let request_timeout = self.timeout.saturating_sub(timer.elapsed()).min(CE_REQUEST_TIMEOUT);
+            let task = match tokio::time::timeout(request_timeout, sonar_client.get_ce_task(task_id)).await {
+                Ok(task) => task,
+                Err(_) => Err(anyhow::anyhow!("no response within {}s", request_timeout.as_secs())),
+            };
+            match task {
+                Ok(task) if task.status == "SUCCESS" => {
+                    match snapshot::capture_snapshot(&sonar_client, &self.snapshot_service, &self.notification_service, project, "SCAN").await {
+                        Ok(snapshot) => outcome.quality_gate_status = Some(snapshot.quality_gate_status),
+                        Err(e) => warn!("Failed to capture snapshot for scan run {}: {}", run.id, e),
+                    }
+                    return;
+                }
+                Ok(task) if task.is_finished() => {
+                    outcome.status = "FAILED".to_string();
+                    outcome.error = Some(task.error_message.unwrap_or_else(|| format!("Analysis {}", task.status.to_lowercase())));
+                    return;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to fetch Compute Engine task {}: {}", task_id, e),
+            }
+
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+        }
+    }
+}
+
+/// Hides the project token in a command or output line.
+pub fn mask_token(text: &str, token: &str) -> String {
+    if token.is_empty() {
+        text.to_string()
+    } else {
+        text.replace(token, MASK)
+    }
+}
+
+/// Kills the scanner's process group, which holds the scanner and every process it forked, then
+/// the scanner itself in case the group is already gone.
+async fn kill(child: &mut Child, process_group: Option<u32>) {
+    if let Some(pgid) = process_group.and_then(|id| libc::pid_t::try_from(id).ok()) {
+        // SAFETY: killpg only sends a signal and has no memory effects
+        unsafe {
+            libc::killpg(pgid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+fn read_lines<R: AsyncRead + Unpin + Send + 'static>(reader: R, stream: Stream, sender: mpsc::UnboundedSender<(Stream, String)>) {
+    actix_web::rt::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if sender.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn failed(error: &str) -> ScanRunOutcome {
+    ScanRunOutcome {
+        status: "FAILED".to_string(),
+        exit_code: None,
+        error: Some(error.to_string()),
+        ce_task_id: None,
+        quality_gate_status: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        duration_ms: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_lines_within_the_limit() {
+        let mut buffer = OutputBuffer::new(12);
+        for line in ["line 1", "line 2", "line 3"] {
+            buffer.push_line(line);
+        }
+
+        assert_eq!(buffer.contents(), "[earlier output truncated]\nline 3\n");
+    }
+
+    #[test]
+    fn cuts_multibyte_output_on_a_char_boundary() {
+        let mut buffer = OutputBuffer::new(8);
+        buffer.push_line("ééé");
+        buffer.push_line("ü");
+
+        assert_eq!(buffer.contents(), "[earlier output truncated]\nü\n");
+    }
+
+    #[test]
+    fn appends_each_line_once() {
+        let mut buffer = OutputBuffer::new(1024);
+        buffer.push_line("line 1");
+        assert_eq!(buffer.take_update(), OutputUpdate::Append("line 1\n".to_string()));
+
+        buffer.push_line("line 2");
+        assert_eq!(buffer.take_update(), OutputUpdate::Append("line 2\n".to_string()));
+        assert_eq!(buffer.take_update(), OutputUpdate::Append(String::new()));
+        assert_eq!(buffer.contents(), "line 1\nline 2\n");
+    }
+
+    #[test]
+    fn replaces_the_stored_output_once_lines_are_dropped() {
+        let mut buffer = OutputBuffer::new(14);
+        buffer.push_line("line 1");
+        assert_eq!(buffer.take_update(), OutputUpdate::Append("line 1\n".to_string()));
+
+        buffer.push_line("line 2");
+        buffer.push_line("line 3");
+        assert_eq!(buffer.take_update(), OutputUpdate::Replace("[earlier output truncated]\nline 3\n".to_string()));
+
+        buffer.push_line("line 4");
+        assert_eq!(buffer.take_update(), OutputUpdate::Append("line 4\n".to_string()));
+    }
+}
@@ -0,0 +1,103 @@
+use actix_web::{web, HttpResponse, Result};
+use crate::audit::Audit;
+use crate::auth::permissions::{Access, Permission};
+use crate::database::job_service::{JobService, UpdateJobRequest};
+use crate::scheduler;
+
+pub async fn get_jobs(
+    access: Access,
+    job_service: web::Data<JobService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::ViewResults) {
+        return Ok(response);
+    }
+
+    match job_service.get_all_jobs().await {
+        Ok(jobs) => Ok(HttpResponse::Ok().json(jobs)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_job(
+    path: web::Path<String>,
+    access: Access,
+    job_service: web::Data<JobService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::ViewResults) {
+        return Ok(response);
+    }
+
+    match job_service.get_job_by_name(&path.into_inner()).await {
+        Ok(Some(job)) => Ok(HttpResponse::Ok().json(job)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Job not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn update_job(
+    path: web::Path<String>,
+    req: web::Json<UpdateJobRequest>,
+    access: Access,
+    audit: Audit,
+    job_service: web::Data<JobService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    if let Some(expression) = &req.cron_expression
+        && let Err(e) = scheduler::parse_cron(expression)
+    {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Invalid cron expression '{}': {}", expression, e),
+            "suggestion": "Use 5 fields (min hour day month weekday) or 6 fields with seconds first, e.g. '0 */15 * * * *'"
+        })));
+    }
+
+    let name = path.into_inner();
+    if let Ok(Some(job)) = job_service.get_job_by_name(&name).await {
+        audit.before(&job);
+    }
+
+    match job_service.update_job(&name, req.into_inner()).await {
+        Ok(Some(job)) => Ok(HttpResponse::Ok().json(job)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Job not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn run_job(
+    path: web::Path<String>,
+    access: Access,
+    job_service: web::Data<JobService>,
+) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::Administer) {
+        return Ok(response);
+    }
+
+    let name = path.into_inner();
+
+    // The run goes through the normal lease, so it still executes once across replicas
+    match job_service.schedule_now(&name).await {
+        Ok(true) => Ok(HttpResponse::Accepted().json(serde_json::json!({
+            "message": "Job scheduled to run on the next scheduler tick",
+            "name": name
+        }))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Job not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
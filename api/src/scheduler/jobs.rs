@@ -0,0 +1,171 @@
+use crate::config::AppConfig;
+use crate::database::audit_service::AuditService;
+use crate::database::notification_service::NotificationService;
+use crate::database::service::ProjectService;
+use crate::database::snapshot_service::SnapshotService;
+use crate::notification;
+use crate::sonarqube::client::SonarQubeClient;
+use crate::sonarqube::snapshot;
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashSet;
+use tracing::warn;
+
+pub struct JobContext<'a> {
+    pub project_service: &'a ProjectService,
+    pub snapshot_service: &'a SnapshotService,
+    pub notification_service: &'a NotificationService,
+    pub audit_service: &'a AuditService,
+    pub config: &'a AppConfig,
+}
+
+pub struct JobOutput {
+    pub message: String,
+    pub result: Option<serde_json::Value>,
+}
+
+pub async fn run(job_type: &str, context: &JobContext<'_>) -> Result<JobOutput> {
+    match job_type {
+        "REFRESH_RESULTS" => refresh_results(context).await,
+        "CHECK_TOKENS" => check_tokens(context).await,
+        "RECONCILE_PROJECTS" => reconcile_projects(context).await,
+        "DELIVER_NOTIFICATIONS" => deliver_notifications(context).await,
+        "PURGE_AUDIT_EVENTS" => purge_audit_events(context).await,
+        other => Err(anyhow::anyhow!("Unknown job type: {}", other)),
+    }
+}
+
+async fn refresh_results(context: &JobContext<'_>) -> Result<JobOutput> {
+    let captured = snapshot::capture_all_projects(
+        &context.config.sonar.host_url,
+        context.project_service,
+        context.snapshot_service,
+        context.notification_service,
+        "SCHEDULED",
+    ).await?;
+
+    Ok(JobOutput {
+        message: format!("Captured snapshots for {} project(s)", captured),
+        result: Some(serde_json::json!({ "captured": captured })),
+    })
+}
+
+/// Validates every stored token. A token that cannot be checked (e.g. SonarQube unreachable) is
+/// logged and reported as unchecked rather than failing the whole job.
+async fn check_tokens(context: &JobContext<'_>) -> Result<JobOutput> {
+    let mut invalid_admin_tokens = Vec::new();
+    let mut unchecked = Vec::new();
+    for token in context.project_service.get_all_admin_tokens().await? {
+        let sonar_client = SonarQubeClient::new(token.sonar_host_url.clone(), token.token_value.clone());
+        match sonar_client.validate_token().await {
+            Ok(true) => {}
+            Ok(false) => invalid_admin_tokens.push(serde_json::json!({
+                "id": token.id,
+                "token_name": token.token_name,
+                "token_type": token.token_type,
+                "sonar_host_url": token.sonar_host_url
+            })),
+            Err(e) => {
+                warn!("Failed to check admin token {}: {}", token.token_name, e);
+                unchecked.push(serde_json::json!({ "admin_token": token.token_name, "error": e.to_string() }));
+            }
+        }
+    }
+
+    let mut invalid_project_tokens = Vec::new();
+    for project in context.project_service.get_all_projects().await? {
+        if project.sonar_token.is_empty() {
+            invalid_project_tokens.push(project.project_key);
+            continue;
+        }
+        let sonar_client = SonarQubeClient::new(project.sonar_host_url.clone(), project.sonar_token.clone());
+        match sonar_client.validate_token().await {
+            Ok(true) => {}
+            Ok(false) => invalid_project_tokens.push(project.project_key),
+            Err(e) => {
+                warn!("Failed to check the token of project {}: {}", project.project_key, e);
+                unchecked.push(serde_json::json!({ "project": project.project_key, "error": e.to_string() }));
+            }
+        }
+    }
+
+    let invalid = invalid_admin_tokens.len() + invalid_project_tokens.len();
+    let message = if invalid == 0 && unchecked.is_empty() {
+        "All tokens are valid".to_string()
+    } else if unchecked.is_empty() {
+        format!("{} invalid token(s) found", invalid)
+    } else {
+        format!("{} invalid token(s) found, {} could not be checked", invalid, unchecked.len())
+    };
+
+    Ok(JobOutput {
+        message,
+        result: Some(serde_json::json!({
+            "invalid_admin_tokens": invalid_admin_tokens,
+            "invalid_project_tokens": invalid_project_tokens,
+            "unchecked": unchecked
+        })),
+    })
+}
+
+async fn reconcile_projects(context: &JobContext<'_>) -> Result<JobOutput> {
+    let sonar_host_url = context.config.sonar.host_url.clone();
+
+    // Listing every project requires admin rights
+    let admin_token = context.project_service
+        .get_admin_token_by_type(&sonar_host_url, "USER_TOKEN")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("No USER_TOKEN found for {}", sonar_host_url))?;
+
+    let sonar_client = SonarQubeClient::new(sonar_host_url, admin_token);
+
+    let sonar_keys: HashSet<String> = sonar_client.search_projects().await?.into_iter().map(|p| p.key).collect();
+    let local_keys: HashSet<String> = context.project_service.get_all_projects().await?.into_iter().map(|p| p.project_key).collect();
+
+    let mut missing_in_sonarqube: Vec<&String> = local_keys.difference(&sonar_keys).collect();
+    let mut unregistered: Vec<&String> = sonar_keys.difference(&local_keys).collect();
+    missing_in_sonarqube.sort();
+    unregistered.sort();
+
+    Ok(JobOutput {
+        message: format!(
+            "{} project(s) missing in SonarQube, {} SonarQube project(s) not registered",
+            missing_in_sonarqube.len(),
+            unregistered.len()
+        ),
+        result: Some(serde_json::json!({
+            "missing_in_sonarqube": missing_in_sonarqube,
+            "unregistered_in_sonarcute": unregistered
+        })),
+    })
+}
+
+async fn deliver_notifications(context: &JobContext<'_>) -> Result<JobOutput> {
+    let stats = notification::deliver_due(context.notification_service).await?;
+
+    Ok(JobOutput {
+        message: format!(
+            "{} notification(s) sent, {} retrying, {} failed",
+            stats.sent, stats.retrying, stats.failed
+        ),
+        result: Some(serde_json::to_value(&stats)?),
+    })
+}
+
+async fn purge_audit_events(context: &JobContext<'_>) -> Result<JobOutput> {
+    let retention_days = context.config.audit.retention_days;
+    if retention_days <= 0 {
+        return Ok(JobOutput {
+            message: "Audit events are kept forever".to_string(),
+            result: None,
+        });
+    }
+
+    let cutoff = Utc::now().naive_utc() - chrono::Duration::days(retention_days);
+    let deleted = context.audit_service.delete_before(cutoff).await?;
+
+    Ok(JobOutput {
+        message: format!("Deleted {} audit event(s) older than {} day(s)", deleted, retention_days),
+        result: Some(serde_json::json!({ "deleted": deleted, "cutoff": cutoff })),
+    })
+}
@@ -0,0 +1,222 @@
+pub mod handlers;
+pub mod jobs;
+
+use crate::config::AppConfig;
+use crate::database::audit_service::AuditService;
+use crate::database::job_service::{JobResponse, JobRunOutcome, JobService};
+use crate::database::notification_service::NotificationService;
+use crate::database::service::ProjectService;
+use crate::database::snapshot_service::SnapshotService;
+use chrono::{NaiveDateTime, Utc};
+use cron::Schedule;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Parses a cron expression. Standard 5-field expressions are accepted and run at second 0;
+/// 6 and 7 field expressions include seconds (and year).
+pub fn parse_cron(expression: &str) -> Result<Schedule, cron::error::Error> {
+    let fields = expression.split_whitespace().count();
+    if fields == 5 {
+        Schedule::from_str(&format!("0 {}", expression))
+    } else {
+        Schedule::from_str(expression)
+    }
+}
+
+pub fn next_run_after(expression: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+    let schedule = parse_cron(expression).ok()?;
+    schedule.after(&after.and_utc()).next().map(|dt| dt.naive_utc())
+}
+
+#[derive(Clone)]
+pub struct Scheduler {
+    job_service: JobService,
+    project_service: ProjectService,
+    snapshot_service: SnapshotService,
+    notification_service: NotificationService,
+    audit_service: AuditService,
+    config: Arc<AppConfig>,
+    instance_id: String,
+    tick: Duration,
+    lease: Duration,
+}
+
+impl Scheduler {
+    pub fn new(
+        job_service: JobService,
+        project_service: ProjectService,
+        snapshot_service: SnapshotService,
+        notification_service: NotificationService,
+        audit_service: AuditService,
+        config: Arc<AppConfig>,
+    ) -> Self {
+        Self {
+            job_service,
+            project_service,
+            snapshot_service,
+            notification_service,
+            audit_service,
+            instance_id: format!("{}-{}", config.server.instance_name, std::process::id()),
+            tick: Duration::from_secs(config.scheduler.tick_secs),
+            lease: Duration::from_secs(config.scheduler.lease_secs),
+            config,
+        }
+    }
+
+    pub fn start(self) {
+        info!("Starting job scheduler {} (tick {:?})", self.instance_id, self.tick);
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(self.tick);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_due_jobs().await {
+                    warn!("Scheduler tick failed: {}", e);
+                }
+            }
+        });
+    }
+
+    async fn run_due_jobs(&self) -> anyhow::Result<()> {
+        let now = Utc::now().naive_utc();
+
+        for job in self.job_service.get_all_jobs().await? {
+            if !job.enabled {
+                continue;
+            }
+
+            let Some(next_run_at) = job.next_run_at else {
+                // New or rescheduled job: plan its first run without executing it
+                let next = next_run_after(&job.cron_expression, now);
+                if next.is_none() {
+                    warn!("Job {} has an invalid cron expression: {}", job.name, job.cron_expression);
+                }
+                self.job_service.set_next_run(job.id, next).await?;
+                continue;
+            };
+
+            if next_run_at > now {
+                continue;
+            }
+
+            let lease_until = now + chrono::Duration::from_std(self.lease)?;
+            let next = next_run_after(&job.cron_expression, now);
+            if !self.job_service.try_acquire(job.id, &self.instance_id, lease_until, next).await? {
+                continue; // Another replica owns this run
+            }
+
+            let scheduler = self.clone();
+            actix_web::rt::spawn(async move {
+                scheduler.execute(job, next).await;
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, job: JobResponse, claimed_next_run_at: Option<NaiveDateTime>) {
+        info!("Running job {} ({})", job.name, job.job_type);
+        let started_at = Utc::now().naive_utc();
+        let timer = Instant::now();
+
+        let context = jobs::JobContext {
+            project_service: &self.project_service,
+            snapshot_service: &self.snapshot_service,
+            notification_service: &self.notification_service,
+            audit_service: &self.audit_service,
+            config: &self.config,
+        };
+        // Renew the lease at half its length so a long run is not picked up by another replica
+        let run = jobs::run(&job.job_type, &context);
+        tokio::pin!(run);
+        let mut renew = actix_web::rt::time::interval(self.lease / 2);
+        renew.tick().await;
+        let output = loop {
+            tokio::select! {
+                output = &mut run => break output,
+                _ = renew.tick() => self.renew_lease(&job).await,
+            }
+        };
+
+        let (status, message, result) = match output {
+            Ok(output) => ("SUCCESS", output.message, output.result),
+            Err(e) => {
+                error!("Job {} failed: {}", job.name, e);
+                ("FAILED", e.to_string(), None)
+            }
+        };
+
+        let outcome = JobRunOutcome {
+            status: status.to_string(),
+            message,
+            result,
+            started_at,
+            duration_ms: timer.elapsed().as_millis() as i64,
+            next_run_at: next_run_after(&job.cron_expression, Utc::now().naive_utc()),
+        };
+
+        match self.job_service.complete_run(job.id, &self.instance_id, claimed_next_run_at, outcome).await {
+            Ok(true) => {}
+            Ok(false) => warn!("Job {} finished after another replica took over its lease; its outcome was not recorded", job.name),
+            Err(e) => error!("Failed to record run of job {}: {}", job.name, e),
+        }
+    }
+
+    async fn renew_lease(&self, job: &JobResponse) {
+        let lease_until = Utc::now().naive_utc() + chrono::Duration::from_std(self.lease).unwrap_or_default();
+        match self.job_service.renew_lease(job.id, &self.instance_id, lease_until).await {
+            Ok(true) => {}
+            Ok(false) => warn!("Lost the lease of job {} while it was running", job.name),
+            Err(e) => warn!("Failed to renew the lease of job {}: {}", job.name, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::new(date.parse::<NaiveDate>().unwrap(), time.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_five_field_expressions_at_second_zero() {
+        let schedule = parse_cron("30 6 * * *").unwrap();
+        let next = schedule.after(&at("2024-10-01", "00:00:00").and_utc()).next().unwrap();
+        assert_eq!(next.naive_utc(), at("2024-10-01", "06:30:00"));
+    }
+
+    #[test]
+    fn parses_six_and_seven_field_expressions() {
+        assert!(parse_cron("15 30 6 * * *").is_ok());
+        assert!(parse_cron("0 30 6 * * * 2030").is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert!(parse_cron("").is_err());
+        assert!(parse_cron("not a cron").is_err());
+        assert!(parse_cron("61 * * * *").is_err());
+    }
+
+    #[test]
+    fn next_run_is_strictly_after_the_given_time() {
+        let hourly = "0 0 * * * *";
+        assert_eq!(next_run_after(hourly, at("2024-10-01", "10:00:00")), Some(at("2024-10-01", "11:00:00")));
+        assert_eq!(next_run_after(hourly, at("2024-10-01", "10:59:59")), Some(at("2024-10-01", "11:00:00")));
+    }
+
+    #[test]
+    fn next_run_rolls_over_to_the_next_day() {
+        assert_eq!(next_run_after("0 2 * * *", at("2024-12-31", "03:00:00")), Some(at("2025-01-01", "02:00:00")));
+    }
+
+    #[test]
+    fn next_run_of_an_invalid_or_exhausted_schedule_is_none() {
+        assert_eq!(next_run_after("bogus", at("2024-10-01", "00:00:00")), None);
+        assert_eq!(next_run_after("0 0 0 1 1 * 2020", at("2024-10-01", "00:00:00")), None);
+    }
+}
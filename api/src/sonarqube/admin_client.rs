@@ -0,0 +1,63 @@
+use actix_web::HttpResponse;
+use crate::auth::permissions::{Access, Permission};
+use crate::config::AppConfig;
+use crate::database::service::{ProjectResponse, ProjectService};
+use crate::sonarqube::client::SonarQubeClient;
+use sea_orm::DbErr;
+use std::fmt;
+
+/// Why no admin client could be built for a SonarQube instance.
+#[derive(Debug)]
+pub enum AdminClientError {
+    MissingToken(String),
+    Database(DbErr),
+}
+
+impl fmt::Display for AdminClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminClientError::MissingToken(sonar_host_url) => write!(f, "No GLOBAL_ANALYSIS_TOKEN found for {}", sonar_host_url),
+            AdminClientError::Database(e) => write!(f, "Database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AdminClientError {}
+
+impl AdminClientError {
+    pub fn response(&self) -> HttpResponse {
+        match self {
+            AdminClientError::MissingToken(_) => HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "No GLOBAL_ANALYSIS_TOKEN found for this SonarQube instance. Please create a GLOBAL_ANALYSIS_TOKEN first.",
+                "suggestion": "Use POST /api/admin-token with token_type: 'GLOBAL_ANALYSIS_TOKEN'"
+            })),
+            AdminClientError::Database(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })),
+        }
+    }
+}
+
+/// SonarQube client authenticated with the instance's GLOBAL_ANALYSIS_TOKEN, used to fetch issues,
+/// measures and analyses.
+pub async fn admin_client(project_service: &ProjectService, sonar_host_url: &str) -> Result<SonarQubeClient, AdminClientError> {
+    match project_service.get_admin_token_by_type(sonar_host_url, "GLOBAL_ANALYSIS_TOKEN").await {
+        Ok(Some(token)) => Ok(SonarQubeClient::new(sonar_host_url.to_string(), token)),
+        Ok(None) => Err(AdminClientError::MissingToken(sonar_host_url.to_string())),
+        Err(e) => Err(AdminClientError::Database(e)),
+    }
+}
+
+/// Looks up a project the caller holds `permission` on and an admin client for its SonarQube
+/// instance. Errors are returned as the response to send.
+pub async fn project_client(
+    project_id: i32,
+    permission: Permission,
+    access: &Access,
+    project_service: &ProjectService,
+    config: &AppConfig,
+) -> Result<(ProjectResponse, SonarQubeClient), HttpResponse> {
+    let project = access.require_project(project_service, project_id, permission).await?;
+    let sonar_client = admin_client(project_service, &config.sonar.host_url).await.map_err(|e| e.response())?;
+    Ok((project, sonar_client))
+}
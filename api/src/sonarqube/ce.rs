@@ -0,0 +1,142 @@
+use crate::sonarqube::client::{CeTask, SonarQubeClient};
+use actix_web::rt::time::sleep;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Default and maximum time a wait request is held open before answering with the pending task.
+pub const DEFAULT_WAIT_SECS: u64 = 30;
+pub const MAX_WAIT_SECS: u64 = 120;
+
+/// How long to hold a wait request open for the requested number of seconds.
+pub fn wait_timeout(requested_secs: Option<u64>) -> Duration {
+    Duration::from_secs(requested_secs.unwrap_or(DEFAULT_WAIT_SECS).min(MAX_WAIT_SECS))
+}
+
+/// Where a Compute Engine task stands once a wait returns.
+#[derive(Debug)]
+pub enum TaskState {
+    /// `PENDING` or `IN_PROGRESS`: the wait timed out first
+    Pending(CeTask),
+    /// `SUCCESS`: the analysis can be read
+    Succeeded(CeTask),
+    /// `FAILED` or `CANCELED`
+    Failed(CeTask),
+}
+
+impl From<CeTask> for TaskState {
+    fn from(task: CeTask) -> Self {
+        match task.status.as_str() {
+            "SUCCESS" => TaskState::Succeeded(task),
+            _ if task.is_finished() => TaskState::Failed(task),
+            _ => TaskState::Pending(task),
+        }
+    }
+}
+
+/// Polls a Compute Engine task until it is finished or `timeout` elapses, returning its last state.
+pub async fn wait_for_task(sonar_client: &SonarQubeClient, task_id: &str, timeout: Duration) -> Result<CeTask> {
+    poll_task(sonar_client, task_id, timeout, POLL_INTERVAL).await
+}
+
+async fn poll_task(sonar_client: &SonarQubeClient, task_id: &str, timeout: Duration, interval: Duration) -> Result<CeTask> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let task = sonar_client.get_ce_task(task_id).await?;
+        if task.is_finished() || Instant::now() + interval > deadline {
+            return Ok(task);
+        }
+        sleep(interval).await;
+    }
+}
+
+/// Polls a project's Compute Engine queue until it is empty, then returns the last processed task.
+/// When `timeout` elapses first, the oldest queued task is returned instead. `None` means the
+/// project has never had a report submitted.
+pub async fn wait_for_project(sonar_client: &SonarQubeClient, project_key: &str, timeout: Duration) -> Result<Option<CeTask>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let component = sonar_client.get_ce_component(project_key).await?;
+        if component.queue.is_empty() {
+            return Ok(component.current);
+        }
+        if Instant::now() + POLL_INTERVAL > deadline {
+            return Ok(component.queue.into_iter().min_by(|a, b| a.submitted_at.cmp(&b.submitted_at)));
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    fn task(status: &str) -> serde_json::Value {
+        serde_json::json!({ "id": "AX1", "type": "REPORT", "componentKey": "demo", "status": status })
+    }
+
+    /// Stand-in SonarQube answering successive `api/ce/task` requests with `statuses`, one per request.
+    fn sonarqube(statuses: &[&str]) -> SonarQubeClient {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let bodies: Vec<String> = statuses.iter().map(|status| serde_json::json!({ "task": task(status) }).to_string()).collect();
+
+        std::thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+            }
+        });
+
+        SonarQubeClient::new(base_url, "token".to_string())
+    }
+
+    fn state(status: &str) -> TaskState {
+        TaskState::from(serde_json::from_value::<CeTask>(task(status)).unwrap())
+    }
+
+    #[test]
+    fn clamps_the_wait_to_the_maximum() {
+        assert_eq!(wait_timeout(None), Duration::from_secs(DEFAULT_WAIT_SECS));
+        assert_eq!(wait_timeout(Some(0)), Duration::ZERO);
+        assert_eq!(wait_timeout(Some(45)), Duration::from_secs(45));
+        assert_eq!(wait_timeout(Some(MAX_WAIT_SECS + 1)), Duration::from_secs(MAX_WAIT_SECS));
+        assert_eq!(wait_timeout(Some(u64::MAX)), Duration::from_secs(MAX_WAIT_SECS));
+    }
+
+    #[test]
+    fn classifies_task_states() {
+        assert!(matches!(state("PENDING"), TaskState::Pending(_)));
+        assert!(matches!(state("IN_PROGRESS"), TaskState::Pending(_)));
+        assert!(matches!(state("SUCCESS"), TaskState::Succeeded(_)));
+        assert!(matches!(state("FAILED"), TaskState::Failed(_)));
+        assert!(matches!(state("CANCELED"), TaskState::Failed(_)));
+    }
+
+    #[actix_web::test]
+    async fn polls_until_the_task_is_finished() {
+        let sonar_client = sonarqube(&["PENDING", "IN_PROGRESS", "FAILED"]);
+
+        let task = poll_task(&sonar_client, "AX1", Duration::from_secs(10), Duration::from_millis(10)).await.unwrap();
+
+        assert_eq!(task.status, "FAILED");
+        assert!(matches!(TaskState::from(task), TaskState::Failed(_)));
+    }
+
+    #[actix_web::test]
+    async fn returns_the_pending_task_when_the_wait_times_out() {
+        let sonar_client = sonarqube(&["PENDING"]);
+
+        let task = poll_task(&sonar_client, "AX1", Duration::ZERO, Duration::from_millis(10)).await.unwrap();
+
+        assert!(matches!(TaskState::from(task), TaskState::Pending(_)));
+    }
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use anyhow::Result;
 use base64::{Engine as _, engine::general_purpose};
 use tracing::info;
+use crate::sonarqube::history;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenResponse {
@@ -14,6 +15,9 @@ pub struct TokenResponse {
 pub struct ProjectIssuesResponse {
     pub issues: Vec<Issue>,
     pub paging: Paging,
+    /// Rules of the returned issues, present when requested with `additionalFields=rules`.
+    #[serde(default)]
+    pub rules: Vec<IssueRule>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,6 +42,30 @@ pub struct Issue {
     pub tags: Vec<String>,
     #[serde(rename = "type")]
     pub issue_type: String,
+    #[serde(rename = "textRange", default, skip_serializing_if = "Option::is_none")]
+    pub text_range: Option<TextRange>,
+}
+
+/// Location of an issue within its file. Lines are 1-based, offsets 0-based.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextRange {
+    #[serde(rename = "startLine")]
+    pub start_line: i32,
+    #[serde(rename = "endLine")]
+    pub end_line: i32,
+    #[serde(rename = "startOffset")]
+    pub start_offset: Option<i32>,
+    #[serde(rename = "endOffset")]
+    pub end_offset: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueRule {
+    pub key: String,
+    pub name: String,
+    pub lang: Option<String>,
+    #[serde(rename = "langName")]
+    pub lang_name: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -49,6 +77,42 @@ pub struct Paging {
     pub total: i32,
 }
 
+/// SonarQube's issues/search returns at most 10,000 results (20 pages of 500), whatever the total.
+pub const MAX_SEARCH_RESULTS: i32 = 10_000;
+
+impl Paging {
+    /// Whether another page follows within the search limit.
+    pub fn has_more(&self) -> bool {
+        let fetched = self.page_index * self.page_size;
+        fetched < self.total && fetched < MAX_SEARCH_RESULTS
+    }
+}
+
+/// Unresolved issues collected from every page of a search. `total` is what SonarQube counted,
+/// which exceeds the fetched issues when the search limit was hit.
+#[derive(Debug, Default)]
+pub struct IssueSearch {
+    pub issues: Vec<Issue>,
+    pub rules: Vec<IssueRule>,
+    pub total: i32,
+}
+
+impl IssueSearch {
+    pub fn truncated(&self) -> bool {
+        self.total as usize > self.issues.len()
+    }
+}
+
+/// Optional `api/issues/search` filters. Comma separated values, as SonarQube expects them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IssueFilter {
+    pub branch: Option<String>,
+    pub severities: Option<String>,
+    pub types: Option<String>,
+    pub rules: Option<String>,
+    pub tags: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CoverageResponse {
     pub component: Component,
@@ -117,19 +181,170 @@ pub struct Period {
     pub date: String,
 }
 
-// Quality Gates list types
+// Component tree (per-file / per-directory measures) types
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentTreeResponse {
+    pub paging: Paging,
+    #[serde(rename = "baseComponent")]
+    pub base_component: TreeComponent,
+    pub components: Vec<TreeComponent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeComponent {
+    pub key: String,
+    pub name: String,
+    pub qualifier: String, // "TRK", "DIR", "FIL", "UTS"
+    pub path: Option<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub measures: Vec<TreeMeasure>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeMeasure {
+    pub metric: String,
+    // Absent for metrics that only carry a new-code period value
+    pub value: Option<String>,
+    #[serde(rename = "bestValue")]
+    pub best_value: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct ComponentTreeOptions {
+    pub component: String,
+    pub metric_keys: String,
+    pub strategy: String,   // "all", "children", "leaves"
+    pub qualifiers: String, // e.g. "FIL,UTS"
+    pub sort: Option<String>,
+    pub asc: bool,
+    pub page: u32,
+    pub page_size: u32,
+    pub query: Option<String>,
+}
+
+// Measures history and project analyses types
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MeasuresHistoryResponse {
+    pub paging: Paging,
+    pub measures: Vec<MetricHistory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricHistory {
+    pub metric: String,
+    pub history: Vec<HistoryPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    pub date: String,
+    // Missing when the metric was not computed by that analysis
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectAnalysesResponse {
+    pub paging: Paging,
+    pub analyses: Vec<Analysis>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Analysis {
+    pub key: String,
+    pub date: String,
+    #[serde(rename = "projectVersion")]
+    pub project_version: Option<String>,
+    pub revision: Option<String>,
+    #[serde(default)]
+    pub events: Vec<AnalysisEvent>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalysisEvent {
+    pub key: String,
+    pub category: String, // "VERSION", "QUALITY_GATE", "QUALITY_PROFILE", "OTHER", ...
+    pub name: String,
+    pub description: Option<String>,
+}
+
+// Issue facet counts (issues/search with facets and no issue bodies)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueFacetsResponse {
+    pub total: i32,
+    #[serde(default)]
+    pub facets: Vec<Facet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Facet {
+    pub property: String,
+    pub values: Vec<FacetValue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FacetValue {
+    pub val: String,
+    pub count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenValidationResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectsSearchResponse {
+    pub paging: Paging,
+    pub components: Vec<ProjectComponent>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
-pub struct QualityGateItem {
-    pub id: i64,
+pub struct ProjectComponent {
+    pub key: String,
     pub name: String,
-    #[serde(rename = "isDefault")]
-    pub is_default: Option<bool>,
+    #[serde(rename = "lastAnalysisDate")]
+    pub last_analysis_date: Option<String>,
+}
+
+/// Compute Engine task that processes an uploaded analysis report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CeTask {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub task_type: String,
+    #[serde(rename = "componentKey")]
+    pub component_key: Option<String>,
+    pub status: String, // "PENDING", "IN_PROGRESS", "SUCCESS", "FAILED", "CANCELED"
+    #[serde(rename = "analysisId")]
+    pub analysis_id: Option<String>,
+    #[serde(rename = "submittedAt")]
+    pub submitted_at: Option<String>,
+    #[serde(rename = "executedAt")]
+    pub executed_at: Option<String>,
+    #[serde(rename = "executionTimeMs")]
+    pub execution_time_ms: Option<i64>,
+    #[serde(rename = "errorMessage")]
+    pub error_message: Option<String>,
+}
+
+impl CeTask {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "SUCCESS" | "FAILED" | "CANCELED")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CeTaskResponse {
+    pub task: CeTask,
 }
 
+/// Queued tasks of a project and its last processed one.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct QualityGatesListResponse {
-    #[serde(rename = "qualitygates")]
-    pub quality_gates: Vec<QualityGateItem>,
+pub struct CeComponentResponse {
+    #[serde(default)]
+    pub queue: Vec<CeTask>,
+    pub current: Option<CeTask>,
 }
 
 pub struct SonarQubeClient {
@@ -147,6 +362,10 @@ impl SonarQubeClient {
         }
     }
 
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     pub async fn create_project(&self, project_key: &str, project_name: &str) -> Result<()> {
         let url = format!("{}/api/projects/create", self.base_url);
         
@@ -222,6 +441,158 @@ impl SonarQubeClient {
         Ok(issues_response)
     }
 
+    /// All unresolved issues of a branch, or of the main branch. Check `truncated()` before treating the result as complete.
+    pub async fn get_all_issues(&self, project_key: &str, branch: Option<&str>) -> Result<IssueSearch> {
+        let filter = IssueFilter {
+            branch: branch.map(str::to_string),
+            ..Default::default()
+        };
+        self.search_all_issues(project_key, &filter).await
+    }
+
+    /// Fetches one page (500 issues) of unresolved issues matching `filter`, with their rules.
+    pub async fn search_issues_page(&self, project_key: &str, filter: &IssueFilter, page: i32) -> Result<ProjectIssuesResponse> {
+        let url = format!("{}/api/issues/search", self.base_url);
+
+        let mut params = vec![
+            ("componentKeys", project_key.to_string()),
+            ("resolved", "false".to_string()),
+            ("p", page.to_string()),
+            ("ps", "500".to_string()),
+            ("additionalFields", "rules".to_string()),
+        ];
+        let optional = [
+            ("branch", &filter.branch),
+            ("severities", &filter.severities),
+            ("types", &filter.types),
+            ("rules", &filter.rules),
+            ("tags", &filter.tags),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+                params.push((name, value.clone()));
+            }
+        }
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get project issues: {}", error_text));
+        }
+
+        let page_response: ProjectIssuesResponse = response.json().await?;
+        Ok(page_response)
+    }
+
+    /// All unresolved issues matching `filter` up to the search limit, plus the distinct rules they were raised by.
+    pub async fn search_all_issues(&self, project_key: &str, filter: &IssueFilter) -> Result<IssueSearch> {
+        let mut search = IssueSearch::default();
+        let mut page = 1;
+
+        loop {
+            let page_response = self.search_issues_page(project_key, filter, page).await?;
+            let has_more = page_response.paging.has_more();
+            search.total = page_response.paging.total;
+            search.issues.extend(page_response.issues);
+            for rule in page_response.rules {
+                if !search.rules.iter().any(|r| r.key == rule.key) {
+                    search.rules.push(rule);
+                }
+            }
+
+            if !has_more {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(search)
+    }
+
+    pub async fn get_issue_facets(&self, project_key: &str, facets: &str) -> Result<IssueFacetsResponse> {
+        let url = format!("{}/api/issues/search", self.base_url);
+
+        // ps=1 keeps the payload small, totals and facets cover every unresolved issue
+        let params = [
+            ("componentKeys", project_key),
+            ("resolved", "false"),
+            ("facets", facets),
+            ("ps", "1"),
+        ];
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get issue facets: {}", error_text));
+        }
+
+        let facets_response: IssueFacetsResponse = response.json().await?;
+        Ok(facets_response)
+    }
+
+    pub async fn get_project_measures(&self, project_key: &str, metric_keys: &str, branch: Option<&str>) -> Result<CoverageResponse> {
+        let url = format!("{}/api/measures/component", self.base_url);
+
+        let mut params = vec![
+            ("component", project_key),
+            ("metricKeys", metric_keys),
+        ];
+        if let Some(branch) = branch {
+            params.push(("branch", branch));
+        }
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get project measures: {}", error_text));
+        }
+
+        let measures_response: CoverageResponse = response.json().await?;
+        Ok(measures_response)
+    }
+
+    pub async fn get_branch_quality_gate(&self, project_key: &str, branch: &str) -> Result<QualityGateResponse> {
+        let url = format!("{}/api/qualitygates/project_status", self.base_url);
+
+        let params = [
+            ("projectKey", project_key),
+            ("branch", branch),
+        ];
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get branch quality gate: {}", error_text));
+        }
+
+        let quality_gate_response: QualityGateResponse = response.json().await?;
+        Ok(quality_gate_response)
+    }
+
     pub async fn get_project_coverage(&self, project_key: &str) -> Result<CoverageResponse> {
         let url = format!("{}/api/measures/component", self.base_url);
         
@@ -269,6 +640,192 @@ pub async fn get_project_quality_gate(&self, project_key: &str) -> Result<Qualit
         Ok(quality_gate_response)
 }
 
+    pub async fn get_component_tree(&self, options: &ComponentTreeOptions) -> Result<ComponentTreeResponse> {
+        let url = format!("{}/api/measures/component_tree", self.base_url);
+
+        let mut params = vec![
+            ("component", options.component.clone()),
+            ("metricKeys", options.metric_keys.clone()),
+            ("strategy", options.strategy.clone()),
+            ("qualifiers", options.qualifiers.clone()),
+            ("asc", options.asc.to_string()),
+            ("p", options.page.to_string()),
+            ("ps", options.page_size.to_string()),
+        ];
+
+        // "name", "path" and "qualifier" are native sort fields, anything else is treated as a metric key
+        match options.sort.as_deref() {
+            Some(field @ ("name" | "path" | "qualifier")) => params.push(("s", field.to_string())),
+            Some(metric) => {
+                params.push(("s", "metric".to_string()));
+                params.push(("metricSort", metric.to_string()));
+                params.push(("metricSortFilter", "withMeasuresOnly".to_string()));
+            }
+            None => {}
+        }
+
+        if let Some(query) = &options.query {
+            params.push(("q", query.clone()));
+        }
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get component tree: {}", error_text));
+        }
+
+        let tree_response: ComponentTreeResponse = response.json().await?;
+        Ok(tree_response)
+    }
+
+    pub async fn get_measures_history(&self, project_key: &str, metrics: &str, from: Option<&str>, to: Option<&str>) -> Result<MeasuresHistoryResponse> {
+        let url = format!("{}/api/measures/search_history", self.base_url);
+
+        let mut history: Option<MeasuresHistoryResponse> = None;
+        let mut page = 1;
+
+        // History is paged by analysis date, so walk every page and merge the points per metric
+        loop {
+            let params = history::search_params(project_key, metrics, from, to, page);
+
+            let response = self.client
+                .get(&url)
+                .query(&params)
+                .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(anyhow::anyhow!("Failed to get measures history: {}", error_text));
+            }
+
+            let page_response: MeasuresHistoryResponse = response.json().await?;
+            let more = history::has_more(&page_response.paging);
+            history::merge_page(&mut history, page_response);
+
+            if !more {
+                break;
+            }
+            page += 1;
+        }
+
+        history.ok_or_else(|| anyhow::anyhow!("Failed to get measures history: empty response"))
+    }
+
+    pub async fn get_project_analyses(&self, project_key: &str, from: Option<&str>, to: Option<&str>) -> Result<Vec<Analysis>> {
+        let url = format!("{}/api/project_analyses/search", self.base_url);
+
+        let mut analyses = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let mut params = vec![
+                ("project", project_key.to_string()),
+                ("p", page.to_string()),
+                ("ps", "500".to_string()),
+            ];
+            if let Some(from) = from {
+                params.push(("from", from.to_string()));
+            }
+            if let Some(to) = to {
+                params.push(("to", to.to_string()));
+            }
+
+            let response = self.client
+                .get(&url)
+                .query(&params)
+                .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(anyhow::anyhow!("Failed to get project analyses: {}", error_text));
+            }
+
+            let page_response: ProjectAnalysesResponse = response.json().await?;
+            let fetched = page_response.paging.page_index * page_response.paging.page_size;
+            let total = page_response.paging.total;
+            analyses.extend(page_response.analyses);
+
+            if fetched >= total {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(analyses)
+    }
+
+    pub async fn get_latest_analysis(&self, project_key: &str) -> Result<Option<Analysis>> {
+        let url = format!("{}/api/project_analyses/search", self.base_url);
+
+        let params = [
+            ("project", project_key),
+            ("ps", "1"),
+        ];
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get latest analysis: {}", error_text));
+        }
+
+        let analyses_response: ProjectAnalysesResponse = response.json().await?;
+        Ok(analyses_response.analyses.into_iter().next())
+    }
+
+    pub async fn get_ce_task(&self, task_id: &str) -> Result<CeTask> {
+        let url = format!("{}/api/ce/task", self.base_url);
+
+        let response = self.client
+            .get(&url)
+            .query(&[("id", task_id)])
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get Compute Engine task: {}", error_text));
+        }
+
+        let task_response: CeTaskResponse = response.json().await?;
+        Ok(task_response.task)
+    }
+
+    pub async fn get_ce_component(&self, project_key: &str) -> Result<CeComponentResponse> {
+        let url = format!("{}/api/ce/component", self.base_url);
+
+        let response = self.client
+            .get(&url)
+            .query(&[("component", project_key)])
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get Compute Engine tasks: {}", error_text));
+        }
+
+        let component_response: CeComponentResponse = response.json().await?;
+        Ok(component_response)
+    }
+
     pub async fn generate_admin_token(&self, username: &str, password: &str, token_name: &str, token_type: &str) -> Result<String> {
         let url = format!("{}/api/user_tokens/generate", self.base_url);
         
@@ -301,6 +858,90 @@ pub async fn get_project_quality_gate(&self, project_key: &str) -> Result<Qualit
         Ok(token_response.token)
     }
 
+    pub async fn create_webhook(&self, name: &str, webhook_url: &str, project_key: &str, secret: Option<&str>) -> Result<()> {
+        let url = format!("{}/api/webhooks/create", self.base_url);
+
+        let mut params = vec![
+            ("name", name.to_string()),
+            ("url", webhook_url.to_string()),
+            ("project", project_key.to_string()),
+        ];
+        if let Some(secret) = secret {
+            params.push(("secret", secret.to_string()));
+        }
+
+        let response = self.client
+            .post(&url)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .form(&params)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to create webhook: {}", error_text));
+        }
+
+        Ok(())
+    }
+
+    pub async fn validate_token(&self) -> Result<bool> {
+        let url = format!("{}/api/authentication/validate", self.base_url);
+
+        let response = self.client
+            .get(&url)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to validate token: {}", error_text));
+        }
+
+        let validation: TokenValidationResponse = response.json().await?;
+        Ok(validation.valid)
+    }
+
+    pub async fn search_projects(&self) -> Result<Vec<ProjectComponent>> {
+        let url = format!("{}/api/projects/search", self.base_url);
+
+        let mut projects = Vec::new();
+        let mut page = 1;
+
+        loop {
+            let params = [
+                ("qualifiers", "TRK".to_string()),
+                ("p", page.to_string()),
+                ("ps", "500".to_string()),
+            ];
+
+            let response = self.client
+                .get(&url)
+                .query(&params)
+                .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(anyhow::anyhow!("Failed to search projects: {}", error_text));
+            }
+
+            let page_response: ProjectsSearchResponse = response.json().await?;
+            let fetched = page_response.paging.page_index * page_response.paging.page_size;
+            let total = page_response.paging.total;
+            projects.extend(page_response.components);
+
+            if fetched >= total {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(projects)
+    }
+
     pub async fn delete_project(&self, project_key: &str) -> Result<()> {
         let url = format!("{}/api/projects/delete", self.base_url);
         
@@ -510,4 +1151,57 @@ pub async fn get_project_quality_gate(&self, project_key: &str) -> Result<Qualit
 
         Ok(())
     }
+
+    /// A single issue by key, in any status.
+    pub async fn get_issue(&self, issue_key: &str) -> Result<Option<Issue>> {
+        let url = format!("{}/api/issues/search", self.base_url);
+        let params = [("issues", issue_key)];
+
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .header("Authorization", format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token))))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to get issue: {}", error_text));
+        }
+
+        let issues_response: ProjectIssuesResponse = response.json().await?;
+        Ok(issues_response.issues.into_iter().next())
+    }
+
+    /// Applies a workflow transition such as "confirm", "resolve" or "falsepositive" to an issue,
+    /// with an optional comment. Returns the updated issue.
+    pub async fn transition_issue(&self, issue_key: &str, transition: &str, comment: Option<&str>) -> Result<Issue> {
+        let auth = format!("Basic {}", general_purpose::STANDARD.encode(format!("{}:", self.admin_token)));
+
+        if let Some(comment) = comment {
+            let url = format!("{}/api/issues/add_comment", self.base_url);
+            let params = [("issue", issue_key), ("text", comment)];
+            let response = self.client.post(&url).header("Authorization", &auth).form(&params).send().await?;
+            if !response.status().is_success() {
+                let error_text = response.text().await?;
+                return Err(anyhow::anyhow!("Failed to comment on issue: {}", error_text));
+            }
+        }
+
+        let url = format!("{}/api/issues/do_transition", self.base_url);
+        let params = [("issue", issue_key), ("transition", transition)];
+        let response = self.client.post(&url).header("Authorization", &auth).form(&params).send().await?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await?;
+            return Err(anyhow::anyhow!("Failed to transition issue: {}", error_text));
+        }
+
+        #[derive(Deserialize)]
+        struct TransitionResponse {
+            issue: Issue,
+        }
+        let transition_response: TransitionResponse = response.json().await?;
+        Ok(transition_response.issue)
+    }
 }
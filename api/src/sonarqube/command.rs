@@ -0,0 +1,51 @@
+use crate::coverage;
+use crate::database::service::ProjectResponse;
+use crate::git::GitMetadata;
+
+/// The Gradle scanner command for a project, as shown by `POST /api/generate-command`. The token is
+/// passed in the `SONAR_TOKEN` environment variable, which the scanner reads, so it stays out of
+/// the process list.
+pub fn scan_command(project: &ProjectResponse, git: Option<&GitMetadata>, branch_analysis: bool) -> String {
+    format!("SONAR_TOKEN={} {}", project.sonar_token, scan_args(project, git, branch_analysis).join(" "))
+}
+
+/// The scanner program and its arguments, without the token. The scan runner executes these
+/// directly rather than through a shell, so values such as a project name with spaces need no quoting.
+/// With `git`, the analysis is tied to the checked out commit, and with `branch_analysis`
+/// to the checked out branch.
+pub fn scan_args(project: &ProjectResponse, git: Option<&GitMetadata>, branch_analysis: bool) -> Vec<String> {
+    let mut args = vec![
+        "./gradlew".to_string(),
+        "test".to_string(),
+        "sonar".to_string(),
+        format!("-Dsonar.host.url={}", project.sonar_host_url),
+        format!("-Dsonar.projectKey={}", project.project_key),
+        format!("-Dsonar.projectName={}", project.project_name),
+    ];
+
+    // Add coverage report path if available, as the property SonarQube reads its format from
+    if let Some(coverage_path) = &project.coverage_report_path {
+        args.push(format!("-D{}={}", coverage::report_property(project, coverage_path), coverage_path));
+    }
+
+    // Add language
+    args.push(format!("-Dsonar.language={}", project.language));
+
+    // Add sources and tests paths
+    args.push(format!("-Dsonar.sources={}", project.sources_path));
+    args.push(format!("-Dsonar.tests={}", project.tests_path));
+
+    if let Some(git) = git {
+        if let Some(commit) = &git.commit {
+            args.push(format!("-Dsonar.scm.revision={}", commit));
+        }
+        // Community Edition rejects branch analysis, so it is opt-in
+        if branch_analysis
+            && let Some(branch) = &git.branch
+        {
+            args.push(format!("-Dsonar.branch.name={}", branch));
+        }
+    }
+
+    args
+}
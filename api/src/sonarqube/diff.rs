@@ -0,0 +1,259 @@
+use crate::database::snapshot_service::SnapshotIssue;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeverityChange {
+    pub issue: SnapshotIssue,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub base: Option<f64>,
+    pub head: Option<f64>,
+    pub delta: Option<f64>,
+}
+
+/// Issues on one side of a comparison. SonarQube returns at most 10,000 issues per search, so
+/// larger projects are compared on the issues fetched only.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueCount {
+    pub total: i32,
+    pub fetched: usize,
+    pub truncated: bool,
+}
+
+impl IssueCount {
+    pub fn new(total: i32, fetched: usize) -> Self {
+        Self { total, fetched, truncated: total.max(0) as usize > fetched }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IssueDiff {
+    pub new_issues: Vec<SnapshotIssue>,
+    pub fixed_issues: Vec<SnapshotIssue>,
+    pub severity_changes: Vec<SeverityChange>,
+    pub unchanged: usize,
+}
+
+// Issue keys differ between branches, so unmatched issues fall back to a content fingerprint
+fn fingerprint(issue: &SnapshotIssue) -> String {
+    match &issue.hash {
+        Some(hash) => format!("{}|{}|{}", issue.rule, issue.component, hash),
+        None => format!("{}|{}|{}|{}", issue.rule, issue.component, issue.line.unwrap_or(0), issue.message),
+    }
+}
+
+/// Compares two issue lists, matching on issue `key` first and `hash` second.
+pub fn diff_issues(base: Vec<SnapshotIssue>, head: Vec<SnapshotIssue>) -> IssueDiff {
+    let mut diff = IssueDiff::default();
+
+    let base_keys: HashMap<String, usize> = base.iter().enumerate().map(|(i, issue)| (issue.key.clone(), i)).collect();
+    let mut base_matched = vec![false; base.len()];
+    let mut unmatched_head = Vec::new();
+
+    for issue in head {
+        match base_keys.get(&issue.key) {
+            Some(&i) => {
+                base_matched[i] = true;
+                record_match(&mut diff, &base[i], issue);
+            }
+            None => unmatched_head.push(issue),
+        }
+    }
+
+    let mut base_fingerprints: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, issue) in base.iter().enumerate() {
+        if !base_matched[i] {
+            base_fingerprints.entry(fingerprint(issue)).or_default().push(i);
+        }
+    }
+
+    for issue in unmatched_head {
+        match base_fingerprints.get_mut(&fingerprint(&issue)).and_then(|candidates| candidates.pop()) {
+            Some(i) => {
+                base_matched[i] = true;
+                record_match(&mut diff, &base[i], issue);
+            }
+            None => diff.new_issues.push(issue),
+        }
+    }
+
+    diff.fixed_issues = base
+        .into_iter()
+        .zip(base_matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(issue, _)| issue)
+        .collect();
+
+    diff
+}
+
+fn record_match(diff: &mut IssueDiff, base: &SnapshotIssue, head: SnapshotIssue) {
+    if base.severity != head.severity {
+        diff.severity_changes.push(SeverityChange {
+            from: base.severity.clone(),
+            to: head.severity.clone(),
+            issue: head,
+        });
+    } else {
+        diff.unchanged += 1;
+    }
+}
+
+fn metric_value(measures: &serde_json::Value, metric: &str) -> Option<f64> {
+    match measures.get(metric)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Computes `head - base` for every metric present in either measure map.
+pub fn diff_measures(base: &serde_json::Value, head: &serde_json::Value) -> Vec<MetricDelta> {
+    let mut metrics: Vec<String> = [base, head]
+        .iter()
+        .filter_map(|m| m.as_object())
+        .flat_map(|m| m.keys().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    metrics.sort();
+
+    metrics
+        .into_iter()
+        .map(|metric| {
+            let base_value = metric_value(base, &metric);
+            let head_value = metric_value(head, &metric);
+            MetricDelta {
+                delta: base_value.zip(head_value).map(|(b, h)| h - b),
+                base: base_value,
+                head: head_value,
+                metric,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn issue(key: &str, hash: Option<&str>, line: i32, severity: &str) -> SnapshotIssue {
+        SnapshotIssue {
+            key: key.to_string(),
+            hash: hash.map(str::to_string),
+            rule: "rust:S1481".to_string(),
+            severity: severity.to_string(),
+            issue_type: "CODE_SMELL".to_string(),
+            component: "demo:src/main.rs".to_string(),
+            line: Some(line),
+            message: "Remove this unused variable".to_string(),
+        }
+    }
+
+    fn keys(issues: &[SnapshotIssue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.key.as_str()).collect()
+    }
+
+    #[test]
+    fn reports_new_fixed_and_unchanged_issues() {
+        let base = vec![issue("a", Some("h1"), 10, "MAJOR"), issue("b", Some("h2"), 20, "MAJOR")];
+        let head = vec![issue("a", Some("h1"), 10, "MAJOR"), issue("c", Some("h3"), 30, "MAJOR")];
+
+        let diff = diff_issues(base, head);
+
+        assert_eq!(keys(&diff.new_issues), ["c"]);
+        assert_eq!(keys(&diff.fixed_issues), ["b"]);
+        assert!(diff.severity_changes.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn reports_severity_changes_of_matched_issues() {
+        let diff = diff_issues(vec![issue("a", Some("h1"), 10, "MAJOR")], vec![issue("a", Some("h1"), 10, "CRITICAL")]);
+
+        assert_eq!(diff.severity_changes.len(), 1);
+        assert_eq!(diff.severity_changes[0].from, "MAJOR");
+        assert_eq!(diff.severity_changes[0].to, "CRITICAL");
+        assert_eq!(diff.unchanged, 0);
+    }
+
+    #[test]
+    fn matches_moved_issue_by_key() {
+        let diff = diff_issues(vec![issue("a", None, 10, "MAJOR")], vec![issue("a", None, 42, "MAJOR")]);
+
+        assert!(diff.new_issues.is_empty());
+        assert!(diff.fixed_issues.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn matches_moved_issue_across_branches_by_hash() {
+        // Branches give the same issue different keys; the line hash survives the move
+        let diff = diff_issues(vec![issue("main-a", Some("h1"), 10, "MAJOR")], vec![issue("feature-a", Some("h1"), 42, "MAJOR")]);
+
+        assert!(diff.new_issues.is_empty());
+        assert!(diff.fixed_issues.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn issue_without_hash_that_moved_across_branches_is_new_and_fixed() {
+        let diff = diff_issues(vec![issue("main-a", None, 10, "MAJOR")], vec![issue("feature-a", None, 42, "MAJOR")]);
+
+        assert_eq!(keys(&diff.new_issues), ["feature-a"]);
+        assert_eq!(keys(&diff.fixed_issues), ["main-a"]);
+        assert_eq!(diff.unchanged, 0);
+    }
+
+    #[test]
+    fn duplicate_fingerprints_match_one_to_one() {
+        let base = vec![issue("main-a", Some("h1"), 10, "MAJOR")];
+        let head = vec![issue("feature-a", Some("h1"), 10, "MAJOR"), issue("feature-b", Some("h1"), 11, "MAJOR")];
+
+        let diff = diff_issues(base, head);
+
+        assert_eq!(diff.new_issues.len(), 1);
+        assert!(diff.fixed_issues.is_empty());
+        assert_eq!(diff.unchanged, 1);
+    }
+
+    #[test]
+    fn measures_delta_is_head_minus_base() {
+        let base = json!({"coverage": 80.0, "bugs": "4", "removed": 1});
+        let head = json!({"coverage": 82.5, "bugs": 1, "added": 7});
+
+        let deltas = diff_measures(&base, &head);
+
+        let metrics: Vec<&str> = deltas.iter().map(|d| d.metric.as_str()).collect();
+        assert_eq!(metrics, ["added", "bugs", "coverage", "removed"]);
+        assert_eq!(deltas[0].base, None);
+        assert_eq!(deltas[0].head, Some(7.0));
+        assert_eq!(deltas[0].delta, None);
+        assert_eq!(deltas[1].delta, Some(-3.0));
+        assert_eq!(deltas[2].delta, Some(2.5));
+        assert_eq!(deltas[3].head, None);
+        assert_eq!(deltas[3].delta, None);
+    }
+
+    #[test]
+    fn measures_ignore_values_that_are_not_numbers() {
+        let deltas = diff_measures(&json!({"alert_status": "OK"}), &json!({"alert_status": "ERROR"}));
+
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].base, None);
+        assert_eq!(deltas[0].delta, None);
+    }
+
+    #[test]
+    fn issue_count_flags_search_limit() {
+        assert!(!IssueCount::new(120, 120).truncated);
+        assert!(IssueCount::new(12_500, 10_000).truncated);
+    }
+}
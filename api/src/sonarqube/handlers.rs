@@ -1,15 +1,51 @@
-use actix_web::{web, HttpResponse, Result};
-use crate::database::service::{CreateProjectRequest, ScanProjectRequest, ProjectService, CreateAdminTokenRequest};
-use crate::sonarqube::client::SonarQubeClient;
-use std::env;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use crate::audit::Audit;
+use crate::auth::permissions::{Access, Permission};
+use crate::config::AppConfig;
+use crate::database::service::{CreateProjectRequest, ScanProjectRequest, ProjectResponse, ProjectService, CreateAdminTokenRequest, SetProjectTeamRequest};
+use crate::database::notification_service::NotificationService;
+use crate::database::snapshot_service::{SnapshotIssue, SnapshotService};
+use crate::database::team_service::TeamService;
+use crate::database::webhook_service::{NewWebhookEvent, WebhookEventService};
+use crate::git;
+use crate::sonarqube::client::{ComponentTreeOptions, CoverageResponse, SonarQubeClient};
+use crate::sonarqube::admin_client::{admin_client, project_client};
+use crate::sonarqube::{ce, command, diff, history, snapshot, webhook};
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 
 pub async fn create_project(
     req: web::Json<CreateProjectRequest>,
+    access: Access,
     project_service: web::Data<ProjectService>,
+    team_service: web::Data<TeamService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    // Projects without a team are only visible to users with a global role
+    let allowed = match req.team_id {
+        Some(team_id) => access.require_team(team_id, Permission::ManageProjects),
+        None => access.require_global(Permission::ManageProjects),
+    };
+    if let Err(response) = allowed {
+        return Ok(response);
+    }
+    if let Some(team_id) = req.team_id {
+        match team_service.get_team_by_id(team_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Team not found"
+                })));
+            }
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                })));
+            }
+        }
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
     
     // Get USER_TOKEN for admin operations (create/delete projects)
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
@@ -64,14 +100,32 @@ pub async fn create_project(
         }
     }
 
+    // Register the analysis-completed webhook when SonarCute is reachable from SonarQube
+    if let Some(webhook_url) = &config.sonar.webhook_url
+        && let Err(e) = sonar_client.create_webhook("SonarCute", webhook_url, &project_response.project_key, config.sonar.webhook_secret.as_deref()).await
+    {
+        warn!("Failed to register webhook for {}: {}", project_response.project_key, e);
+    }
+
     Ok(HttpResponse::Ok().json(project_response))
 }
 
+/// Projects visible to the caller: all of them with a global role, otherwise their teams' projects.
 pub async fn get_all_projects(
+    access: Access,
     project_service: web::Data<ProjectService>,
 ) -> Result<HttpResponse> {
-    match project_service.get_all_projects().await {
-        Ok(projects) => Ok(HttpResponse::Ok().json(projects)),
+    let projects = if access.sees_all_projects() {
+        project_service.get_all_projects().await
+    } else {
+        project_service.get_projects_for_teams(access.team_ids()).await
+    };
+
+    match projects {
+        Ok(projects) => {
+            let projects: Vec<ProjectResponse> = projects.into_iter().map(|project| access.redact(project)).collect();
+            Ok(HttpResponse::Ok().json(projects))
+        }
         Err(e) => {
             Ok(HttpResponse::InternalServerError().json(serde_json::json!({
                 "error": format!("Database error: {}", e)
@@ -82,8 +136,13 @@ pub async fn get_all_projects(
 
 pub async fn create_admin_token(
     req: web::Json<CreateAdminTokenRequest>,
+    access: Access,
     project_service: web::Data<ProjectService>,
 ) -> Result<HttpResponse> {
+    if let Err(response) = access.require_global(Permission::RotateTokens) {
+        return Ok(response);
+    }
+
     let sonar_client = SonarQubeClient::new(req.sonar_host_url.clone(), String::new());
 
     // Validate token_type
@@ -130,7 +189,9 @@ pub async fn create_admin_token(
 
 pub async fn get_project_results(
     req: web::Json<ScanProjectRequest>,
+    access: Access,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
     // Find project by path
     let project = match project_service.get_project_by_path(&req.project_path).await {
@@ -147,27 +208,17 @@ pub async fn get_project_results(
         }
     };
 
-    // Get results from SonarQube
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
-    
-    // Get GLOBAL_ANALYSIS_TOKEN for fetching issues, coverage, etc.
-    let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "GLOBAL_ANALYSIS_TOKEN").await {
-        Ok(Some(token)) => token,
-        Ok(None) => {
-            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                "error": "No GLOBAL_ANALYSIS_TOKEN found for this SonarQube instance. Please create a GLOBAL_ANALYSIS_TOKEN first.",
-                "suggestion": "Use POST /api/admin-token with token_type: 'GLOBAL_ANALYSIS_TOKEN'"
-            })));
-        }
-        Err(e) => {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Database error: {}", e)
-            })));
-        }
+    if let Err(response) = access.require(&project, Permission::ViewResults) {
+        return Ok(response);
+    }
+    let project = access.redact(project);
+
+    // Get results from SonarQube with the GLOBAL_ANALYSIS_TOKEN
+    let sonar_client = match admin_client(&project_service, &config.sonar.host_url).await {
+        Ok(client) => client,
+        Err(e) => return Ok(e.response()),
     };
     
-    let sonar_client = SonarQubeClient::new(sonar_host_url, admin_token);
-    
     // Fetch issues, coverage, and quality gate in parallel
     let issues_result = sonar_client.get_project_issues(&project.project_key).await;
     let coverage_result = sonar_client.get_project_coverage(&project.project_key).await;
@@ -240,9 +291,620 @@ pub async fn get_project_results(
     Ok(HttpResponse::Ok().json(response_data))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ComponentTreeQuery {
+    pub metrics: Option<String>,
+    pub path: Option<String>,       // Directory to drill into, relative to the project root
+    pub strategy: Option<String>,   // "leaves", "children" or "all"
+    pub qualifiers: Option<String>, // "DIR", "FIL", "UTS", comma separated
+    pub sort: Option<String>,       // "name", "path", "qualifier" or a metric key
+    pub asc: Option<bool>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub q: Option<String>,
+}
+
+const DEFAULT_COMPONENT_TREE_METRICS: &str =
+    "coverage,uncovered_lines,lines_to_cover,bugs,vulnerabilities,code_smells,violations,complexity,ncloc";
+
+pub async fn get_project_components(
+    path: web::Path<i32>,
+    query: web::Query<ComponentTreeQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::ViewResults, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let project = access.redact(project);
+
+    // Directory components are keyed as "<project_key>:<path>" in SonarQube
+    let dir_path = query.path.as_deref().map(|p| p.trim_matches('/')).filter(|p| !p.is_empty());
+    let component = match dir_path {
+        Some(dir) => format!("{}:{}", project.project_key, dir),
+        None => project.project_key.clone(),
+    };
+
+    // Browsing a directory lists its direct children, otherwise list every file in the project
+    let strategy = query.strategy.clone().unwrap_or_else(|| {
+        if dir_path.is_some() { "children".to_string() } else { "leaves".to_string() }
+    });
+    let qualifiers = query.qualifiers.clone().unwrap_or_else(|| {
+        if strategy == "leaves" { "FIL,UTS".to_string() } else { "DIR,FIL,UTS".to_string() }
+    });
+
+    let options = ComponentTreeOptions {
+        component,
+        metric_keys: query.metrics.clone().unwrap_or_else(|| DEFAULT_COMPONENT_TREE_METRICS.to_string()),
+        strategy,
+        qualifiers,
+        sort: query.sort.clone(),
+        asc: query.asc.unwrap_or(true),
+        page: query.page.unwrap_or(1).max(1),
+        page_size: query.page_size.unwrap_or(100).clamp(1, 500),
+        query: query.q.clone(),
+    };
+
+    match sonar_client.get_component_tree(&options).await {
+        Ok(tree) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "project": project,
+            "base_component": tree.base_component,
+            "components": tree.components,
+            "paging": tree.paging
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to fetch component tree: {}", e)
+        }))),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricsHistoryQuery {
+    pub metrics: Option<String>,
+    pub from: Option<String>, // "YYYY-MM-DD" or ISO 8601 datetime
+    pub to: Option<String>,
+}
+
+// sqale_index is the technical debt in minutes
+const DEFAULT_HISTORY_METRICS: &str = "coverage,bugs,vulnerabilities,code_smells,sqale_index";
+
+pub async fn get_project_history(
+    path: web::Path<i32>,
+    query: web::Query<MetricsHistoryQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::ViewResults, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let project = access.redact(project);
+
+    let metrics = query.metrics.clone().unwrap_or_else(|| DEFAULT_HISTORY_METRICS.to_string());
+    let from = query.from.as_deref();
+    let to = query.to.as_deref();
+
+    let history = match sonar_client.get_measures_history(&project.project_key, &metrics, from, to).await {
+        Ok(history) => history,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to fetch measures history: {}", e)
+            })));
+        }
+    };
+
+    let series = history::series(history);
+
+    let mut response_data = serde_json::json!({
+        "project": project,
+        "from": query.from,
+        "to": query.to,
+        "series": series,
+    });
+
+    // Analyses carry version and quality gate events used to annotate the charts
+    match sonar_client.get_project_analyses(&project.project_key, from, to).await {
+        Ok(analyses) => {
+            response_data["analyses"] = serde_json::to_value(&analyses).unwrap_or(serde_json::Value::Null);
+        }
+        Err(e) => {
+            response_data["analyses_error"] = serde_json::json!({
+                "error": format!("Failed to fetch project analyses: {}", e)
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(response_data))
+}
+
+pub async fn capture_project_snapshot(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+    notification_service: web::Data<NotificationService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::RunAnalysis, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match snapshot::capture_snapshot(&sonar_client, &snapshot_service, &notification_service, &project, "MANUAL").await {
+        Ok(snapshot) => Ok(HttpResponse::Ok().json(snapshot)),
+        Err(e) => Ok(HttpResponse::BadGateway().json(serde_json::json!({
+            "error": format!("Failed to capture snapshot: {}", e)
+        }))),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WaitForAnalysisQuery {
+    pub task_id: Option<String>, // ceTaskId from report-task.txt; defaults to the project's queue
+    pub timeout: Option<u64>,    // seconds, capped at ce::MAX_WAIT_SECS
+}
+
+/// Long-polls the Compute Engine until the analysis is processed. A successful analysis is captured
+/// as a snapshot and returned with its quality gate; a task still queued at the timeout yields 202.
+pub async fn wait_for_analysis(
+    path: web::Path<i32>,
+    query: web::Query<WaitForAnalysisQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+    notification_service: web::Data<NotificationService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project, sonar_client) = match project_client(path.into_inner(), Permission::RunAnalysis, &access, &project_service, &config).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+    let timeout = ce::wait_timeout(query.timeout);
+
+    let task = match &query.task_id {
+        Some(task_id) => ce::wait_for_task(&sonar_client, task_id, timeout).await.map(Some),
+        None => ce::wait_for_project(&sonar_client, &project.project_key, timeout).await,
+    };
+    let task = match task {
+        Ok(Some(task)) => task,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "No analysis has been submitted for this project"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to fetch Compute Engine task: {}", e)
+            })));
+        }
+    };
+
+    if task.component_key.as_deref().is_some_and(|key| key != project.project_key) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Task {} belongs to {}, not {}", task.id, task.component_key.unwrap_or_default(), project.project_key)
+        })));
+    }
+
+    let task = match ce::TaskState::from(task) {
+        ce::TaskState::Succeeded(task) => task,
+        ce::TaskState::Pending(task) => {
+            return Ok(HttpResponse::Accepted().json(serde_json::json!({
+                "status": task.status,
+                "task": task
+            })));
+        }
+        ce::TaskState::Failed(task) => {
+            return Ok(HttpResponse::Ok().json(serde_json::json!({
+                "status": task.status,
+                "error": task.error_message.clone().unwrap_or_else(|| format!("Analysis {}", task.status.to_lowercase())),
+                "task": task
+            })));
+        }
+    };
+
+    match snapshot::capture_snapshot(&sonar_client, &snapshot_service, &notification_service, &project, "CE_TASK").await {
+        Ok(snapshot) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "status": task.status,
+            "task": task,
+            "quality_gate_status": snapshot.quality_gate_status,
+            "snapshot": snapshot
+        }))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(serde_json::json!({
+            "error": format!("Failed to capture snapshot: {}", e)
+        }))),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotListQuery {
+    pub limit: Option<u64>,
+}
+
+pub async fn get_project_snapshots(
+    path: web::Path<i32>,
+    query: web::Query<SnapshotListQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+) -> Result<HttpResponse> {
+    let project_id = path.into_inner();
+    if let Err(response) = access.require_project(&project_service, project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+    match snapshot_service.get_snapshots(project_id, limit).await {
+        Ok(snapshots) => Ok(HttpResponse::Ok().json(snapshots)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_latest_project_snapshot(
+    path: web::Path<i32>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+) -> Result<HttpResponse> {
+    let project_id = path.into_inner();
+    if let Err(response) = access.require_project(&project_service, project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    match snapshot_service.get_latest_snapshot(project_id).await {
+        Ok(Some(snapshot)) => Ok(HttpResponse::Ok().json(snapshot)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "No snapshot stored for this project"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+pub async fn get_project_snapshot(
+    path: web::Path<(i32, i32)>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+) -> Result<HttpResponse> {
+    let (project_id, snapshot_id) = path.into_inner();
+    if let Err(response) = access.require_project(&project_service, project_id, Permission::ViewResults).await {
+        return Ok(response);
+    }
+
+    match snapshot_service.get_snapshot_by_id(project_id, snapshot_id).await {
+        Ok(Some(snapshot)) => Ok(HttpResponse::Ok().json(snapshot)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Snapshot not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareQuery {
+    pub base: Option<String>,        // Analysis key to compare from
+    pub head: Option<String>,        // Analysis key to compare to, defaults to the latest snapshot
+    pub branch: Option<String>,      // Compare a branch live against base_branch instead of two analyses
+    pub base_branch: Option<String>, // Defaults to the main branch
+}
+
+pub async fn compare_project_results(
+    path: web::Path<i32>,
+    query: web::Query<CompareQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let project = match project_service.get_project_by_id(path.into_inner()).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Project not found"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    if let Err(response) = access.require(&project, Permission::ViewResults) {
+        return Ok(response);
+    }
+    let project = access.redact(project);
+
+    if let Some(branch) = &query.branch {
+        return compare_branches(&project, branch, query.base_branch.as_deref(), &project_service, &config).await;
+    }
+
+    let Some(base_key) = &query.base else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Either 'base' (analysis key) or 'branch' is required"
+        })));
+    };
+
+    let base = match snapshot_service.get_snapshot_by_analysis(project.id, base_key).await {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("No snapshot stored for analysis {}", base_key),
+                "suggestion": format!("Capture one with POST /api/projects/{}/snapshots after that analysis", project.id)
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    let head = match &query.head {
+        Some(head_key) => snapshot_service.get_snapshot_by_analysis(project.id, head_key).await,
+        None => snapshot_service.get_latest_snapshot(project.id).await,
+    };
+    let head = match head {
+        Ok(Some(snapshot)) => snapshot,
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": format!("No snapshot stored for analysis {}", query.head.as_deref().unwrap_or("latest"))
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    let (base_issues, head_issues) = match (
+        snapshot_service.get_snapshot_issues(base.id).await,
+        snapshot_service.get_snapshot_issues(head.id).await,
+    ) {
+        (Ok(base_issues), Ok(head_issues)) => (base_issues, head_issues),
+        (Err(e), _) | (_, Err(e)) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    // Snapshots store at most the 10,000 issues SonarQube returned at capture time
+    let base_count = diff::IssueCount::new(base.issues_total, base_issues.len());
+    let head_count = diff::IssueCount::new(head.issues_total, head_issues.len());
+
+    let issue_diff = diff::diff_issues(base_issues, head_issues);
+    let metric_deltas = diff::diff_measures(&base.measures, &head.measures);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "project": project,
+        "truncated": base_count.truncated || head_count.truncated,
+        "base": {
+            "analysis_key": base.analysis_key,
+            "analysis_date": base.analysis_date,
+            "quality_gate_status": base.quality_gate_status,
+            "issues": base_count
+        },
+        "head": {
+            "analysis_key": head.analysis_key,
+            "analysis_date": head.analysis_date,
+            "quality_gate_status": head.quality_gate_status,
+            "issues": head_count
+        },
+        "summary": {
+            "new": issue_diff.new_issues.len(),
+            "fixed": issue_diff.fixed_issues.len(),
+            "severity_changed": issue_diff.severity_changes.len(),
+            "unchanged": issue_diff.unchanged
+        },
+        "new_issues": issue_diff.new_issues,
+        "fixed_issues": issue_diff.fixed_issues,
+        "severity_changes": issue_diff.severity_changes,
+        "metric_deltas": metric_deltas
+    })))
+}
+
+async fn compare_branches(
+    project: &ProjectResponse,
+    branch: &str,
+    base_branch: Option<&str>,
+    project_service: &ProjectService,
+    config: &AppConfig,
+) -> Result<HttpResponse> {
+    let sonar_client = match admin_client(project_service, &config.sonar.host_url).await {
+        Ok(client) => client,
+        Err(e) => return Ok(e.response()),
+    };
+
+    let (base_issues, head_issues) = match (
+        sonar_client.get_all_issues(&project.project_key, base_branch).await,
+        sonar_client.get_all_issues(&project.project_key, Some(branch)).await,
+    ) {
+        (Ok(base_issues), Ok(head_issues)) => (base_issues, head_issues),
+        (Err(e), _) | (_, Err(e)) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to fetch branch issues: {}", e)
+            })));
+        }
+    };
+
+    let measures_for = |response: anyhow::Result<CoverageResponse>| -> serde_json::Value {
+        let measures: serde_json::Map<String, serde_json::Value> = response
+            .map(|r| r.component.measures.into_iter().map(|m| (m.metric, serde_json::json!(m.value))).collect())
+            .unwrap_or_default();
+        serde_json::Value::Object(measures)
+    };
+    let base_measures = measures_for(sonar_client.get_project_measures(&project.project_key, snapshot::SNAPSHOT_METRICS, base_branch).await);
+    let head_measures = measures_for(sonar_client.get_project_measures(&project.project_key, snapshot::SNAPSHOT_METRICS, Some(branch)).await);
+
+    let base_count = diff::IssueCount::new(base_issues.total, base_issues.issues.len());
+    let head_count = diff::IssueCount::new(head_issues.total, head_issues.issues.len());
+
+    let issue_diff = diff::diff_issues(
+        base_issues.issues.into_iter().map(SnapshotIssue::from).collect(),
+        head_issues.issues.into_iter().map(SnapshotIssue::from).collect(),
+    );
+    let metric_deltas = diff::diff_measures(&base_measures, &head_measures);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "project": project,
+        "truncated": base_count.truncated || head_count.truncated,
+        "base": { "branch": base_branch, "issues": base_count },
+        "head": { "branch": branch, "issues": head_count },
+        "summary": {
+            "new": issue_diff.new_issues.len(),
+            "fixed": issue_diff.fixed_issues.len(),
+            "severity_changed": issue_diff.severity_changes.len(),
+            "unchanged": issue_diff.unchanged
+        },
+        "new_issues": issue_diff.new_issues,
+        "fixed_issues": issue_diff.fixed_issues,
+        "severity_changes": issue_diff.severity_changes,
+        "metric_deltas": metric_deltas
+    })))
+}
+
+pub async fn receive_sonarqube_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    project_service: web::Data<ProjectService>,
+    snapshot_service: web::Data<SnapshotService>,
+    notification_service: web::Data<NotificationService>,
+    webhook_service: web::Data<WebhookEventService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let signature = req.headers().get(webhook::SIGNATURE_HEADER).and_then(|v| v.to_str().ok());
+    match webhook::authenticate(config.sonar.webhook_secret.as_deref(), &body, signature) {
+        Ok(()) => {}
+        Err(webhook::SignatureError::NoSecret) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Webhook deliveries are rejected because no webhook secret is configured",
+                "suggestion": "Set sonar.webhook_secret (SONAR_WEBHOOK_SECRET) and configure the same secret on the SonarQube webhook"
+            })));
+        }
+        Err(_) => {
+            return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Missing or invalid webhook signature"
+            })));
+        }
+    }
+
+    let (payload, raw_payload) = match (
+        serde_json::from_slice::<webhook::WebhookPayload>(&body),
+        serde_json::from_slice::<serde_json::Value>(&body),
+    ) {
+        (Ok(payload), Ok(raw_payload)) => (payload, raw_payload),
+        (Err(e), _) | (_, Err(e)) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": format!("Invalid webhook payload: {}", e)
+            })));
+        }
+    };
+
+    let project = match project_service.get_project_by_key(&payload.project.key).await {
+        Ok(project) => project,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    let new_event = NewWebhookEvent {
+        project_id: project.as_ref().map(|p| p.id),
+        project_key: payload.project.key.clone(),
+        task_id: payload.task_id.clone(),
+        task_status: payload.status.clone(),
+        branch: payload.branch.as_ref().map(|b| b.name.clone()),
+        is_main_branch: payload.branch.as_ref().is_none_or(|b| b.is_main),
+        quality_gate_status: payload.quality_gate.as_ref().map(|qg| qg.status.clone()),
+        analysed_at: payload.analysed_at.as_deref().and_then(snapshot::parse_sonar_datetime),
+        payload: raw_payload,
+    };
+
+    let event = match webhook_service.create_event(new_event).await {
+        Ok(event) => event,
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Failed to store webhook event: {}", e)
+            })));
+        }
+    };
+
+    let sonar_client = admin_client(&project_service, &config.sonar.host_url).await.ok();
+
+    // SonarQube times out deliveries after 10 seconds, so downstream work runs in the background
+    let event_id = event.id;
+    actix_web::rt::spawn(webhook::on_analysis_completed(
+        event,
+        project,
+        sonar_client,
+        snapshot_service.get_ref().clone(),
+        notification_service.get_ref().clone(),
+    ));
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "message": "Webhook received",
+        "event_id": event_id
+    })))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookEventsQuery {
+    pub project_key: Option<String>,
+    pub limit: Option<u64>,
+}
+
+pub async fn get_webhook_events(
+    query: web::Query<WebhookEventsQuery>,
+    access: Access,
+    project_service: web::Data<ProjectService>,
+    webhook_service: web::Data<WebhookEventService>,
+) -> Result<HttpResponse> {
+    // Events of one project are visible to its viewers, all events only with a global role
+    let allowed = match query.project_key.as_deref() {
+        Some(project_key) => match project_service.get_project_by_key(project_key).await {
+            Ok(Some(project)) => access.require(&project, Permission::ViewResults),
+            Ok(None) => access.require_global(Permission::ViewResults),
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                })));
+            }
+        },
+        None => access.require_global(Permission::ViewResults),
+    };
+    if let Err(response) = allowed {
+        return Ok(response);
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+
+    match webhook_service.get_events(query.project_key.as_deref(), limit).await {
+        Ok(events) => Ok(HttpResponse::Ok().json(events)),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
 pub async fn generate_sonar_command(
     req: web::Json<ScanProjectRequest>,
+    access: Access,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
     // Find project by path
     let project = match project_service.get_project_by_path(&req.project_path).await {
@@ -259,38 +921,30 @@ pub async fn generate_sonar_command(
         }
     };
 
-    // Generate the sonar command
-    let mut command = format!(
-        "./gradlew test sonar -Dsonar.token={} -Dsonar.host.url={} -Dsonar.projectKey={} -Dsonar.projectName={}",
-        project.sonar_token,
-        project.sonar_host_url,
-        project.project_key,
-        project.project_name
-    );
-
-    // Add coverage report path if available
-    if let Some(coverage_path) = &project.coverage_report_path {
-        command = format!("{} -Dsonar.coverage.jacoco.xmlReportPaths={}", command, coverage_path);
+    if let Err(response) = access.require(&project, Permission::RunAnalysis) {
+        return Ok(response);
     }
 
-    // Add language
-    command = format!("{} -Dsonar.language={}", command, project.language);
-
-    // Add sources and tests paths
-    command = format!("{} -Dsonar.sources={}", command, project.sources_path);
-    command = format!("{} -Dsonar.tests={}", command, project.tests_path);
+    let git = git::lookup(&project.project_path).await;
+    let command = command::scan_command(&project, git.as_ref(), config.sonar.branch_analysis);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "command": command,
-        "project_path": project.project_path
+        "project_path": project.project_path,
+        "git_branch": git.as_ref().and_then(|g| g.branch.clone()),
+        "git_commit": git.as_ref().and_then(|g| g.commit.clone()),
+        "git_dirty": git.as_ref().map(|g| g.dirty)
     })))
 }
 
 pub async fn delete_project(
     req: web::Json<ScanProjectRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    let sonar_host_url = config.sonar.host_url.clone();
     
     // Find project by path
     let project = match project_service.get_project_by_path(&req.project_path).await {
@@ -307,6 +961,12 @@ pub async fn delete_project(
         }
     };
 
+    audit.target(project.id);
+    if let Err(response) = access.require(&project, Permission::DeleteProjects) {
+        return Ok(response);
+    }
+    audit.before(&project);
+
     // Get USER_TOKEN for admin operations (create/delete projects)
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -391,6 +1051,131 @@ pub async fn delete_project(
     }
 }
 
+/// Moves a project to another team, or out of any team with `team_id: null`. Needs the right to
+/// manage projects in both the current and the new team.
+pub async fn set_project_team(
+    path: web::Path<i32>,
+    req: web::Json<SetProjectTeamRequest>,
+    access: Access,
+    audit: Audit,
+    project_service: web::Data<ProjectService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let project = match access.require_project(&project_service, path.into_inner(), Permission::ManageProjects).await {
+        Ok(project) => project,
+        Err(response) => return Ok(response),
+    };
+    audit.before(&project);
+
+    let allowed = match req.team_id {
+        Some(team_id) => access.require_team(team_id, Permission::ManageProjects),
+        None => access.require_global(Permission::ManageProjects),
+    };
+    if let Err(response) = allowed {
+        return Ok(response);
+    }
+    if let Some(team_id) = req.team_id {
+        match team_service.get_team_by_id(team_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                    "error": "Team not found"
+                })));
+            }
+            Err(e) => {
+                return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                    "error": format!("Database error: {}", e)
+                })));
+            }
+        }
+    }
+
+    match project_service.update_project_team(project.id, req.team_id).await {
+        Ok(Some(project)) => Ok(HttpResponse::Ok().json(access.redact(project))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Project not found"
+        }))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Database error: {}", e)
+        }))),
+    }
+}
+
+/// Issue workflow transitions of SonarQube's `api/issues/do_transition`.
+const ISSUE_TRANSITIONS: &[&str] = &["confirm", "unconfirm", "reopen", "resolve", "falsepositive", "wontfix", "accept"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IssueTransitionRequest {
+    pub transition: String,
+    pub comment: Option<String>,
+}
+
+/// Confirms, resolves or reopens an issue of the project in SonarQube.
+pub async fn transition_issue(
+    path: web::Path<(i32, String)>,
+    req: web::Json<IssueTransitionRequest>,
+    access: Access,
+    audit: Audit,
+    project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    let (project_id, issue_key) = path.into_inner();
+    let project = match access.require_project(&project_service, project_id, Permission::TriageIssues).await {
+        Ok(project) => project,
+        Err(response) => return Ok(response),
+    };
+
+    if !ISSUE_TRANSITIONS.contains(&req.transition.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": format!("Unknown transition {}", req.transition),
+            "suggestion": format!("Use one of {}", ISSUE_TRANSITIONS.join(", "))
+        })));
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
+
+    // Transitions need the Administer Issues permission, which analysis tokens lack
+    let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
+        Ok(Some(token)) => token,
+        Ok(None) => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "error": "No USER_TOKEN found for this SonarQube instance. Please create a USER_TOKEN first.",
+                "suggestion": "Use POST /api/admin-token with token_type: 'USER_TOKEN' (must be created with a user that has admin privileges)"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+
+    let sonar_client = SonarQubeClient::new(sonar_host_url, admin_token);
+
+    // The issue key comes from the caller, so make sure it belongs to a project they may triage
+    match sonar_client.get_issue(&issue_key).await {
+        Ok(Some(issue)) if issue.project == project.project_key => audit.before(&issue),
+        Ok(_) => {
+            return Ok(HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Issue not found in this project"
+            })));
+        }
+        Err(e) => {
+            return Ok(HttpResponse::BadGateway().json(serde_json::json!({
+                "error": format!("Failed to fetch issue: {}", e)
+            })));
+        }
+    }
+
+    let comment = req.comment.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    match sonar_client.transition_issue(&issue_key, &req.transition, comment).await {
+        Ok(issue) => Ok(HttpResponse::Ok().json(issue)),
+        Err(e) => Ok(HttpResponse::BadGateway().json(serde_json::json!({
+            "error": e.to_string()
+        }))),
+    }
+}
+
 // Quality Gate DTOs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateQualityGateRequest {
@@ -430,9 +1215,17 @@ pub struct QualityGateConditionInput {
 
 pub async fn create_quality_gate(
     req: web::Json<CreateQualityGateRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    audit.target(&req.name);
+    if let Err(response) = access.require_global(Permission::EditQualityGates) {
+        return Ok(response);
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
 
     // Need USER_TOKEN for admin operations
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
@@ -459,12 +1252,12 @@ pub async fn create_quality_gate(
     }
 
     // Optionally add a condition to the newly created gate
-    if let (Some(metric), Some(op), Some(error)) = (&req.condition_metric, &req.condition_op, &req.condition_error) {
-        if let Err(e) = sonar_client.add_quality_gate_condition(&req.name, metric, op, error).await {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Quality gate created, but failed to add condition: {}", e)
-            })));
-        }
+    if let (Some(metric), Some(op), Some(error)) = (&req.condition_metric, &req.condition_op, &req.condition_error)
+        && let Err(e) = sonar_client.add_quality_gate_condition(&req.name, metric, op, error).await
+    {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Quality gate created, but failed to add condition: {}", e)
+        })));
     }
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
@@ -475,9 +1268,17 @@ pub async fn create_quality_gate(
 
 pub async fn update_quality_gate(
     req: web::Json<UpdateQualityGateRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    audit.target(&req.name);
+    if let Err(response) = access.require_global(Permission::EditQualityGates) {
+        return Ok(response);
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -496,21 +1297,26 @@ pub async fn update_quality_gate(
 
     let sonar_client = SonarQubeClient::new(sonar_host_url.clone(), admin_token);
 
-    if let Some(new_name) = &req.new_name {
-        if let Err(e) = sonar_client.rename_quality_gate(&req.name, new_name).await {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to rename quality gate: {}", e)
-            })));
-        }
+    // Renames and condition deletions cannot be undone from the response alone
+    if let Ok(details) = sonar_client.get_quality_gate_details(&req.name).await {
+        audit.before(&details);
+    }
+
+    if let Some(new_name) = &req.new_name
+        && let Err(e) = sonar_client.rename_quality_gate(&req.name, new_name).await
+    {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to rename quality gate: {}", e)
+        })));
     }
 
     // Backward-compatible single condition add
-    if let (Some(metric), Some(op), Some(error)) = (&req.condition_metric, &req.condition_op, &req.condition_error) {
-        if let Err(e) = sonar_client.add_quality_gate_condition(req.new_name.as_ref().unwrap_or(&req.name), metric, op, error).await {
-            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to add condition: {}", e)
-            })));
-        }
+    if let (Some(metric), Some(op), Some(error)) = (&req.condition_metric, &req.condition_op, &req.condition_error)
+        && let Err(e) = sonar_client.add_quality_gate_condition(req.new_name.as_ref().unwrap_or(&req.name), metric, op, error).await
+    {
+        return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+            "error": format!("Failed to add condition: {}", e)
+        })));
     }
 
     // Multiple deletes by condition id
@@ -536,6 +1342,10 @@ pub async fn update_quality_gate(
         }
     }
 
+    if let Ok(details) = sonar_client.get_quality_gate_details(req.new_name.as_ref().unwrap_or(&req.name)).await {
+        audit.after(&details);
+    }
+
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "message": "Quality gate updated successfully",
         "name": req.new_name.as_ref().unwrap_or(&req.name)
@@ -544,9 +1354,17 @@ pub async fn update_quality_gate(
 
 pub async fn delete_quality_gate(
     req: web::Json<DeleteQualityGateRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    audit.target(&req.name);
+    if let Err(response) = access.require_global(Permission::EditQualityGates) {
+        return Ok(response);
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -565,6 +1383,10 @@ pub async fn delete_quality_gate(
 
     let sonar_client = SonarQubeClient::new(sonar_host_url.clone(), admin_token);
 
+    if let Ok(details) = sonar_client.get_quality_gate_details(&req.name).await {
+        audit.before(&details);
+    }
+
     if let Err(e) = sonar_client.delete_quality_gate(&req.name).await {
         return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
             "error": format!("Failed to delete quality gate: {}", e)
@@ -579,9 +1401,17 @@ pub async fn delete_quality_gate(
 
 pub async fn set_default_quality_gate(
     req: web::Json<SetDefaultQualityGateRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    audit.target(&req.name);
+    if let Err(response) = access.require_global(Permission::EditQualityGates) {
+        return Ok(response);
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -614,8 +1444,9 @@ pub async fn set_default_quality_gate(
 
 pub async fn get_quality_gates(
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -652,8 +1483,9 @@ pub struct QualityGateDetailsQuery {
 pub async fn get_quality_gate_details(
     query: web::Query<QualityGateDetailsQuery>,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
@@ -688,9 +1520,32 @@ pub struct AssignQualityGateRequest {
 
 pub async fn assign_quality_gate_to_project(
     req: web::Json<AssignQualityGateRequest>,
+    access: Access,
+    audit: Audit,
     project_service: web::Data<ProjectService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    let sonar_host_url = env::var("SONAR_HOST_URL").unwrap_or_else(|_| "http://localhost:9000".to_string());
+    // Gates of projects SonarCute does not manage can only be changed with a global role
+    let allowed = match project_service.get_project_by_key(&req.project_key).await {
+        Ok(Some(project)) => {
+            audit.target(project.id);
+            access.require(&project, Permission::EditQualityGates)
+        }
+        Ok(None) => {
+            audit.target(&req.project_key);
+            access.require_global(Permission::EditQualityGates)
+        }
+        Err(e) => {
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": format!("Database error: {}", e)
+            })));
+        }
+    };
+    if let Err(response) = allowed {
+        return Ok(response);
+    }
+
+    let sonar_host_url = config.sonar.host_url.clone();
 
     let admin_token = match project_service.get_admin_token_by_type(&sonar_host_url, "USER_TOKEN").await {
         Ok(Some(token)) => token,
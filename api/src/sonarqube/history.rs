@@ -0,0 +1,143 @@
+use crate::sonarqube::client::{MeasuresHistoryResponse, Paging};
+use serde::{Deserialize, Serialize};
+
+/// Points fetched per `search_history` page, the maximum SonarQube accepts.
+pub const PAGE_SIZE: i32 = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricSeries {
+    pub metric: String,
+    pub points: Vec<MetricPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetricPoint {
+    pub date: String,
+    pub value: Option<f64>,
+}
+
+/// Query of one page of `api/measures/search_history`; `from` and `to` are only sent when set.
+pub fn search_params(project_key: &str, metrics: &str, from: Option<&str>, to: Option<&str>, page: i32) -> Vec<(&'static str, String)> {
+    let mut params = vec![
+        ("component", project_key.to_string()),
+        ("metrics", metrics.to_string()),
+        ("p", page.to_string()),
+        ("ps", PAGE_SIZE.to_string()),
+    ];
+    if let Some(from) = from {
+        params.push(("from", from.to_string()));
+    }
+    if let Some(to) = to {
+        params.push(("to", to.to_string()));
+    }
+    params
+}
+
+/// Whether pages follow the one described by `paging`.
+pub fn has_more(paging: &Paging) -> bool {
+    paging.page_index * paging.page_size < paging.total
+}
+
+/// Adds a page to the history fetched so far, appending its points to the metric they belong to.
+pub fn merge_page(history: &mut Option<MeasuresHistoryResponse>, page: MeasuresHistoryResponse) {
+    match history.as_mut() {
+        None => *history = Some(page),
+        Some(merged) => {
+            for metric_page in page.measures {
+                match merged.measures.iter_mut().find(|m| m.metric == metric_page.metric) {
+                    Some(existing) => existing.history.extend(metric_page.history),
+                    None => merged.measures.push(metric_page),
+                }
+            }
+            merged.paging = page.paging;
+        }
+    }
+}
+
+/// Chart series of the history. Values come back as strings; charts need numbers, and values that
+/// are missing or not numeric become gaps.
+pub fn series(history: MeasuresHistoryResponse) -> Vec<MetricSeries> {
+    history.measures.into_iter().map(|m| MetricSeries {
+        metric: m.metric,
+        points: m.history.into_iter().map(|point| MetricPoint {
+            value: point.value.and_then(|v| v.parse::<f64>().ok()),
+            date: point.date,
+        }).collect(),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sonarqube::client::{HistoryPoint, MetricHistory};
+
+    type Points<'a> = &'a [(&'a str, Option<&'a str>)];
+
+    fn page(page_index: i32, total: i32, measures: &[(&str, Points)]) -> MeasuresHistoryResponse {
+        MeasuresHistoryResponse {
+            paging: Paging { page_index, page_size: PAGE_SIZE, total },
+            measures: measures.iter().map(|(metric, points)| MetricHistory {
+                metric: metric.to_string(),
+                history: points.iter().map(|(date, value)| HistoryPoint {
+                    date: date.to_string(),
+                    value: value.map(str::to_string),
+                }).collect(),
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn sends_the_date_range_only_when_set() {
+        assert_eq!(search_params("demo", "coverage,bugs", None, None, 1), vec![
+            ("component", "demo".to_string()),
+            ("metrics", "coverage,bugs".to_string()),
+            ("p", "1".to_string()),
+            ("ps", "1000".to_string()),
+        ]);
+
+        let params = search_params("demo", "coverage", Some("2024-10-01"), Some("2024-11-01"), 3);
+        assert!(params.contains(&("p", "3".to_string())));
+        assert!(params.contains(&("from", "2024-10-01".to_string())));
+        assert!(params.contains(&("to", "2024-11-01".to_string())));
+    }
+
+    #[test]
+    fn pages_until_the_total_is_reached() {
+        assert!(!has_more(&Paging { page_index: 1, page_size: PAGE_SIZE, total: 0 }));
+        assert!(!has_more(&Paging { page_index: 1, page_size: PAGE_SIZE, total: 1000 }));
+        assert!(has_more(&Paging { page_index: 1, page_size: PAGE_SIZE, total: 1001 }));
+        assert!(!has_more(&Paging { page_index: 2, page_size: PAGE_SIZE, total: 1001 }));
+    }
+
+    #[test]
+    fn merges_points_per_metric_across_pages() {
+        let mut history = None;
+        merge_page(&mut history, page(1, 1500, &[("coverage", &[("2024-10-01", Some("80.0"))])]));
+        merge_page(&mut history, page(2, 1500, &[
+            ("coverage", &[("2024-10-02", Some("81.5"))]),
+            ("bugs", &[("2024-10-02", Some("3"))]),
+        ]));
+
+        let history = history.unwrap();
+        assert_eq!(history.paging.page_index, 2);
+        let dates: Vec<Vec<&str>> = history.measures.iter()
+            .map(|m| m.history.iter().map(|p| p.date.as_str()).collect())
+            .collect();
+        assert_eq!(history.measures[0].metric, "coverage");
+        assert_eq!(history.measures[1].metric, "bugs");
+        assert_eq!(dates, vec![vec!["2024-10-01", "2024-10-02"], vec!["2024-10-02"]]);
+    }
+
+    #[test]
+    fn turns_values_into_numbers_and_gaps() {
+        let series = series(page(1, 3, &[("coverage", &[
+            ("2024-10-01", Some("80.5")),
+            ("2024-10-02", None),
+            ("2024-10-03", Some("OK")),
+        ])]));
+
+        let values: Vec<Option<f64>> = series[0].points.iter().map(|p| p.value).collect();
+        assert_eq!(series[0].metric, "coverage");
+        assert_eq!(values, vec![Some(80.5), None, None]);
+    }
+}
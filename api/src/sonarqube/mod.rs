@@ -1,2 +1,9 @@
+pub mod admin_client;
+pub mod ce;
 pub mod client;
+pub mod command;
+pub mod diff;
 pub mod handlers;
+pub mod history;
+pub mod snapshot;
+pub mod webhook;
@@ -0,0 +1,237 @@
+use crate::database::notification_service::NotificationService;
+use crate::database::service::{ProjectResponse, ProjectService};
+use crate::database::snapshot_service::{NewSnapshot, SnapshotIssue, SnapshotResponse, SnapshotService};
+use crate::git;
+use crate::notification;
+use crate::sonarqube::admin_client::admin_client;
+use crate::sonarqube::client::{Facet, Issue, SonarQubeClient};
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime};
+use tracing::{info, warn};
+
+pub const SNAPSHOT_METRICS: &str = "coverage,line_coverage,branch_coverage,lines_to_cover,uncovered_lines,\
+bugs,vulnerabilities,code_smells,security_hotspots,sqale_index,duplicated_lines_density,ncloc,\
+reliability_rating,security_rating,sqale_rating";
+
+/// Parses SonarQube timestamps such as `2024-10-16T10:03:10+0000` into UTC.
+pub fn parse_sonar_datetime(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|dt| dt.naive_utc())
+}
+
+impl From<Issue> for SnapshotIssue {
+    fn from(issue: Issue) -> Self {
+        Self {
+            key: issue.key,
+            hash: issue.hash,
+            rule: issue.rule,
+            severity: issue.severity,
+            issue_type: issue.issue_type,
+            component: issue.component,
+            line: issue.line,
+            message: issue.message,
+        }
+    }
+}
+
+fn facet_counts(facets: &[Facet], property: &str) -> serde_json::Value {
+    let counts: serde_json::Map<String, serde_json::Value> = facets
+        .iter()
+        .find(|f| f.property == property)
+        .map(|f| f.values.iter().map(|v| (v.val.clone(), serde_json::json!(v.count))).collect())
+        .unwrap_or_default();
+    serde_json::Value::Object(counts)
+}
+
+/// Fetches the current issues, measures and quality gate of a project and stores them.
+/// Capturing the same analysis twice, or a project without analysis whose quality gate did not
+/// change, returns the existing snapshot. A new snapshot whose quality gate turned ERROR triggers
+/// the project's notification channels.
+pub async fn capture_snapshot(
+    sonar_client: &SonarQubeClient,
+    snapshot_service: &SnapshotService,
+    notification_service: &NotificationService,
+    project: &ProjectResponse,
+    source: &str,
+) -> Result<SnapshotResponse> {
+    let analysis = sonar_client.get_latest_analysis(&project.project_key).await?;
+
+    if let Some(analysis) = &analysis
+        && let Some(existing) = snapshot_service.get_snapshot_by_analysis(project.id, &analysis.key).await?
+    {
+        return Ok(existing);
+    }
+
+    let facets = sonar_client.get_issue_facets(&project.project_key, "severities,types").await?;
+    let search = sonar_client.get_all_issues(&project.project_key, None).await?;
+    if search.truncated() {
+        warn!("Storing {} of {} issues for {}: SonarQube stops searches at 10,000 results", search.issues.len(), search.total, project.project_key);
+    }
+    let issues: Vec<SnapshotIssue> = search.issues.into_iter().map(SnapshotIssue::from).collect();
+
+    // Measures and quality gate are missing until the first analysis has been processed
+    let measures: serde_json::Map<String, serde_json::Value> = match sonar_client.get_project_measures(&project.project_key, SNAPSHOT_METRICS, None).await {
+        Ok(response) => response.component.measures.into_iter().map(|m| {
+            let value = m.value.parse::<f64>().map(|n| serde_json::json!(n)).unwrap_or(serde_json::json!(m.value));
+            (m.metric, value)
+        }).collect(),
+        Err(e) => {
+            warn!("No measures for {}: {}", project.project_key, e);
+            serde_json::Map::new()
+        }
+    };
+
+    let (quality_gate_status, quality_gate_conditions) = match sonar_client.get_project_quality_gate(&project.project_key).await {
+        Ok(response) => (
+            response.project_status.status,
+            serde_json::to_value(&response.project_status.conditions).unwrap_or(serde_json::json!([])),
+        ),
+        Err(e) => {
+            warn!("No quality gate status for {}: {}", project.project_key, e);
+            ("UNKNOWN".to_string(), serde_json::json!([]))
+        }
+    };
+
+    // SonarQube records the analysed revision; the checkout only tells its branch while it still has that commit
+    let git_commit = analysis.as_ref().and_then(|a| a.revision.clone());
+    let checkout = match &git_commit {
+        Some(commit) => git::lookup(&project.project_path).await.filter(|git| git.commit.as_ref() == Some(commit)),
+        None => None,
+    };
+
+    let analysis_key = analysis.as_ref().map(|a| a.key.clone());
+    let new_snapshot = NewSnapshot {
+        project_id: project.id,
+        analysis_key: analysis_key.clone(),
+        analysis_date: analysis.as_ref().and_then(|a| parse_sonar_datetime(&a.date)),
+        quality_gate_status,
+        quality_gate_conditions,
+        issues_total: facets.total,
+        issues_by_severity: facet_counts(&facets.facets, "severities"),
+        issues_by_type: facet_counts(&facets.facets, "types"),
+        measures: serde_json::Value::Object(measures),
+        source: source.to_string(),
+        git_branch: checkout.as_ref().and_then(|git| git.branch.clone()),
+        git_commit,
+        git_dirty: checkout.as_ref().map(|git| git.dirty),
+    };
+
+    // Without an analysis there is no key to deduplicate on, so skip captures that change nothing
+    let previous = snapshot_service.get_latest_snapshot(project.id).await?;
+    if let Some(previous) = &previous
+        && repeats_without_analysis(previous, &new_snapshot)
+    {
+        return Ok(previous.clone());
+    }
+
+    match snapshot_service.create_snapshot(new_snapshot, issues).await {
+        Ok(snapshot) => {
+            if let Err(e) = notification::notify_quality_gate_change(notification_service, project, previous.as_ref(), &snapshot).await {
+                warn!("Failed to queue notifications for {}: {}", project.project_key, e);
+            }
+            Ok(snapshot)
+        }
+        Err(e) => {
+            // A concurrent capture of the same analysis wins the unique constraint
+            if let Some(key) = &analysis_key
+                && let Some(existing) = snapshot_service.get_snapshot_by_analysis(project.id, key).await?
+            {
+                return Ok(existing);
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// Whether `snapshot` would store the same state as `previous` when neither belongs to an analysis.
+fn repeats_without_analysis(previous: &SnapshotResponse, snapshot: &NewSnapshot) -> bool {
+    previous.analysis_key.is_none()
+        && snapshot.analysis_key.is_none()
+        && previous.quality_gate_status == snapshot.quality_gate_status
+}
+
+/// Captures a snapshot for every registered project, logging failures per project.
+pub async fn capture_all_projects(
+    sonar_host_url: &str,
+    project_service: &ProjectService,
+    snapshot_service: &SnapshotService,
+    notification_service: &NotificationService,
+    source: &str,
+) -> Result<usize> {
+    let sonar_client = admin_client(project_service, sonar_host_url).await?;
+
+    let mut captured = 0;
+    for project in project_service.get_all_projects().await? {
+        match capture_snapshot(&sonar_client, snapshot_service, notification_service, &project, source).await {
+            Ok(_) => captured += 1,
+            Err(e) => warn!("Failed to capture snapshot for {}: {}", project.project_key, e),
+        }
+    }
+
+    info!("Captured snapshots for {} project(s)", captured);
+    Ok(captured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn previous(analysis_key: Option<&str>, quality_gate_status: &str) -> SnapshotResponse {
+        SnapshotResponse {
+            id: 1,
+            project_id: 1,
+            analysis_key: analysis_key.map(str::to_string),
+            analysis_date: None,
+            quality_gate_status: quality_gate_status.to_string(),
+            quality_gate_conditions: serde_json::json!([]),
+            issues_total: 0,
+            issues_by_severity: serde_json::json!({}),
+            issues_by_type: serde_json::json!({}),
+            measures: serde_json::json!({}),
+            source: "SCHEDULED".to_string(),
+            git_branch: None,
+            git_commit: None,
+            git_dirty: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    fn snapshot(analysis_key: Option<&str>, quality_gate_status: &str) -> NewSnapshot {
+        NewSnapshot {
+            project_id: 1,
+            analysis_key: analysis_key.map(str::to_string),
+            analysis_date: None,
+            quality_gate_status: quality_gate_status.to_string(),
+            quality_gate_conditions: serde_json::json!([]),
+            issues_total: 0,
+            issues_by_severity: serde_json::json!({}),
+            issues_by_type: serde_json::json!({}),
+            measures: serde_json::json!({}),
+            source: "SCHEDULED".to_string(),
+            git_branch: None,
+            git_commit: None,
+            git_dirty: None,
+        }
+    }
+
+    #[test]
+    fn skips_repeated_captures_without_analysis() {
+        assert!(repeats_without_analysis(&previous(None, "NONE"), &snapshot(None, "NONE")));
+    }
+
+    #[test]
+    fn stores_changed_or_analysed_captures() {
+        assert!(!repeats_without_analysis(&previous(None, "NONE"), &snapshot(None, "UNKNOWN")));
+        assert!(!repeats_without_analysis(&previous(None, "NONE"), &snapshot(Some("AYx1"), "OK")));
+        assert!(!repeats_without_analysis(&previous(Some("AYx1"), "OK"), &snapshot(None, "OK")));
+    }
+
+    #[test]
+    fn parses_sonar_timestamps() {
+        let parsed = parse_sonar_datetime("2024-10-16T12:03:10+0200").unwrap();
+        assert_eq!(parsed.to_string(), "2024-10-16 10:03:10");
+        assert!(parse_sonar_datetime("yesterday").is_none());
+    }
+}
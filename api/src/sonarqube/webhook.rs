@@ -0,0 +1,159 @@
+use crate::database::service::ProjectResponse;
+use crate::database::notification_service::NotificationService;
+use crate::database::snapshot_service::SnapshotService;
+use crate::database::webhook_service::WebhookEventResponse;
+use crate::sonarqube::client::SonarQubeClient;
+use crate::sonarqube::snapshot;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::{info, warn};
+
+pub const SIGNATURE_HEADER: &str = "X-Sonar-Webhook-HMAC-SHA256";
+
+// Payload sent by SonarQube when a background task (analysis report processing) completes
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    #[serde(rename = "serverUrl")]
+    pub server_url: Option<String>,
+    #[serde(rename = "taskId")]
+    pub task_id: Option<String>,
+    pub status: String, // "SUCCESS", "FAILED", "CANCELED"
+    #[serde(rename = "analysedAt")]
+    pub analysed_at: Option<String>,
+    pub revision: Option<String>,
+    pub project: WebhookProject,
+    pub branch: Option<WebhookBranch>,
+    #[serde(rename = "qualityGate")]
+    pub quality_gate: Option<WebhookQualityGate>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookProject {
+    pub key: String,
+    pub name: String,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookBranch {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub branch_type: String, // "BRANCH" or "PULL_REQUEST"
+    #[serde(rename = "isMain")]
+    pub is_main: bool,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookQualityGate {
+    pub name: String,
+    pub status: String, // "OK" or "ERROR"
+    #[serde(default)]
+    pub conditions: Vec<WebhookCondition>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookCondition {
+    pub metric: String,
+    pub operator: String,
+    pub value: Option<String>,
+    pub status: String,
+    #[serde(rename = "errorThreshold")]
+    pub error_threshold: Option<String>,
+}
+
+/// Checks the hex encoded HMAC-SHA256 of the raw request body against the shared secret.
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Ok(expected) = hex::decode(signature.trim()) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SignatureError {
+    NoSecret,
+    Missing,
+    Invalid,
+}
+
+/// Authenticates a delivery by its signature header. The webhook route needs no credentials,
+/// so without a configured secret every delivery is rejected.
+pub fn authenticate(secret: Option<&str>, body: &[u8], signature: Option<&str>) -> Result<(), SignatureError> {
+    let secret = secret.ok_or(SignatureError::NoSecret)?;
+    let signature = signature.ok_or(SignatureError::Missing)?;
+    if verify_signature(secret, body, signature) {
+        Ok(())
+    } else {
+        Err(SignatureError::Invalid)
+    }
+}
+
+/// Follow-up work for a stored event. Only successful main branch analyses of registered
+/// projects refresh the local snapshot.
+pub async fn on_analysis_completed(
+    event: WebhookEventResponse,
+    project: Option<ProjectResponse>,
+    sonar_client: Option<SonarQubeClient>,
+    snapshot_service: SnapshotService,
+    notification_service: NotificationService,
+) {
+    let (Some(project), Some(sonar_client)) = (project, sonar_client) else {
+        info!("Webhook event {} for unregistered project {}", event.id, event.project_key);
+        return;
+    };
+
+    if event.task_status != "SUCCESS" || !event.is_main_branch {
+        return;
+    }
+
+    match snapshot::capture_snapshot(&sonar_client, &snapshot_service, &notification_service, &project, "WEBHOOK").await {
+        Ok(snapshot) => info!("Captured snapshot {} for {} from webhook event {}", snapshot.id, project.project_key, event.id),
+        Err(e) => warn!("Failed to capture snapshot for {} from webhook event {}: {}", project.project_key, event.id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "change-me";
+    const BODY: &[u8] = br#"{"status":"SUCCESS"}"#;
+    // printf '{"status":"SUCCESS"}' | openssl dgst -sha256 -hmac change-me
+    const SIGNATURE: &str = "739ffd644a17083a402d7c7d437b8604b667c384465f80ba9de343f0f4288834";
+
+    #[test]
+    fn accepts_valid_signature() {
+        assert!(verify_signature(SECRET, BODY, SIGNATURE));
+        assert!(verify_signature(SECRET, BODY, &format!(" {} ", SIGNATURE.to_uppercase())));
+        assert_eq!(authenticate(Some(SECRET), BODY, Some(SIGNATURE)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wrong_signature() {
+        let mut wrong = SIGNATURE.to_string();
+        wrong.replace_range(..2, "00");
+        assert!(!verify_signature(SECRET, BODY, &wrong));
+        assert!(!verify_signature("other-secret", BODY, SIGNATURE));
+        assert!(!verify_signature(SECRET, br#"{"status":"FAILED"}"#, SIGNATURE));
+        assert!(!verify_signature(SECRET, BODY, "not hex"));
+        assert!(!verify_signature(SECRET, BODY, ""));
+        assert_eq!(authenticate(Some(SECRET), BODY, Some(&wrong)), Err(SignatureError::Invalid));
+    }
+
+    #[test]
+    fn rejects_missing_header() {
+        assert_eq!(authenticate(Some(SECRET), BODY, None), Err(SignatureError::Missing));
+    }
+
+    #[test]
+    fn rejects_every_delivery_without_a_secret() {
+        assert_eq!(authenticate(None, BODY, Some(SIGNATURE)), Err(SignatureError::NoSecret));
+        assert_eq!(authenticate(None, BODY, None), Err(SignatureError::NoSecret));
+    }
+}
@@ -0,0 +1,9 @@
+use actix_web::{HttpResponse, Result};
+
+/// Liveness check for load balancers and container health checks; reachable without credentials.
+pub async fn health() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION")
+    })))
+}
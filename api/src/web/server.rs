@@ -1,44 +1,164 @@
 use actix_cors::Cors;
+use actix_web::dev::ServiceRequest;
 use actix_web::middleware;
 use actix_web::web;
 use actix_web::App;
 use actix_web::HttpServer;
 use listenfd::ListenFd;
-use std::env;
+use std::sync::Arc;
 use tracing::info;
-use crate::config::logger;
-use crate::database::{connect, service::ProjectService};
+use crate::audit;
+use crate::auth::{self, AuthConfig};
+use crate::config::{self, logger, AppConfig, ServerArgs};
+use crate::database::{connect, audit_service::AuditService, auth_service::AuthService, job_service::JobService, notification_service::NotificationService, scan_service::ScanService, service::ProjectService, snapshot_service::SnapshotService, team_service::TeamService, webhook_service::WebhookEventService};
+use crate::coverage;
+use crate::detect;
+use crate::git;
+use crate::export;
+use crate::notification;
+use crate::report;
+use crate::scanner::{self, ScanRunner};
+use crate::scheduler::{self, Scheduler};
 use crate::sonarqube::handlers;
+use crate::web::health;
 
+/// The format of `Logger::default`, with the request line from [`request_line`].
+const ACCESS_LOG_FORMAT: &str = r#"%a "%{request_line}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#;
 
-pub async fn start() -> std::io::Result<()> {
+/// The request line for the access log, with the values of `token` query parameters, which open
+/// scan event streams, masked.
+fn request_line(req: &ServiceRequest) -> String {
+    let query: Vec<&str> = req
+        .query_string()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| if pair.starts_with("token=") { "token=****" } else { pair })
+        .collect();
+    if query.is_empty() {
+        format!("{} {} {:?}", req.method(), req.path(), req.version())
+    } else {
+        format!("{} {}?{} {:?}", req.method(), req.path(), query.join("&"), req.version())
+    }
+}
+
+pub async fn start(args: ServerArgs) -> std::io::Result<()> {
     dotenvy::dotenv().ok();
 
-    logger::init();
+    // Logging is configured too, so problems are printed before the logger exists
+    let config = match AppConfig::load(&args) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let log_filter = web::Data::new(logger::init(&config.log));
+    if let Some(path) = &args.config {
+        info!("Loaded configuration from {}", path.display());
+    }
+    if args.check_config {
+        info!("Configuration is valid");
+        return Ok(());
+    }
+
+    let server_url = format!("{}:{}", config.server.host, config.server.port);
+
+    let db = connect(&config.database.url).await.expect("Failed to connect to database");
+    let project_service = ProjectService::new(db.clone());
+    let job_service = JobService::new(db.clone());
+    let webhook_service = WebhookEventService::new(db.clone());
+    let notification_service = NotificationService::new(db.clone(), config.smtp.clone());
+    let scan_service = ScanService::new(db.clone());
+    let snapshot_service = SnapshotService::new(db.clone());
+    let team_service = TeamService::new(db.clone());
+    let audit_service = AuditService::new(db.clone());
+    let auth_service = AuthService::new(db);
+    let auth_config = web::Data::new(AuthConfig::from_config(&config));
 
-    let server_host = env::var("SERVER_HOST").expect("HOST not set in .env file");
-    let server_port = env::var("SERVER_PORT").expect("PORT not set in .env file");
-    let server_url = format!("{server_host}:{server_port}");
+    auth::bootstrap_admin(&auth_service, &config.auth).await;
 
-    let db = connect().await.expect("Failed to connect to database");
-    let project_service = ProjectService::new(db);
+    if config.scheduler.enabled {
+        Scheduler::new(
+            job_service.clone(),
+            project_service.clone(),
+            snapshot_service.clone(),
+            notification_service.clone(),
+            audit_service.clone(),
+            config.clone(),
+        ).start();
+    }
+
+    let scan_runner = ScanRunner::new(
+        scan_service.clone(),
+        project_service.clone(),
+        snapshot_service.clone(),
+        notification_service.clone(),
+        config.clone(),
+    );
+    scan_runner.start();
+
+    // Browsers send the bearer token from script, so CORS only decides which sites may call the API.
+    // Without allowed origins no other site may. Mismatched origins are answered without CORS
+    // headers rather than rejected, so same-origin requests carrying an Origin header still work.
+    let allowed_origins = config.server.cors_allowed_origins.clone();
+    let app_config = web::Data::from(config);
 
     let mut server = HttpServer::new(move || {
-        let cors = Cors::default()
+        let mut cors = Cors::default()
             .allow_any_method()
-            .allow_any_origin()
-            .allow_any_header();
+            .allow_any_header()
+            .expose_headers(["Content-Disposition", "X-Truncated", "X-Total-Count"])
+            .block_on_origin_mismatch(false)
+            .max_age(3600);
+        for origin in &allowed_origins {
+            cors = cors.allowed_origin(origin);
+        }
 
         App::new()
             .app_data(web::Data::new(project_service.clone()))
-            .wrap(middleware::Logger::default())
+            .app_data(web::Data::new(snapshot_service.clone()))
+            .app_data(web::Data::new(job_service.clone()))
+            .app_data(web::Data::new(webhook_service.clone()))
+            .app_data(web::Data::new(notification_service.clone()))
+            .app_data(web::Data::new(scan_service.clone()))
+            .app_data(web::Data::new(scan_runner.clone()))
+            .app_data(web::Data::new(auth_service.clone()))
+            .app_data(web::Data::new(team_service.clone()))
+            .app_data(web::Data::new(audit_service.clone()))
+            .app_data(auth_config.clone())
+            .app_data(app_config.clone())
+            .app_data(log_filter.clone())
+            .wrap(middleware::Logger::new(ACCESS_LOG_FORMAT).custom_request_replace("request_line", request_line))
             .wrap(cors)
             .service(
                 web::scope("/api")
+                    .wrap(middleware::from_fn(auth::middleware::require_auth))
+                    .wrap(middleware::from_fn(audit::record))
+                    .route("/health", web::get().to(health::health))
                     .route("/admin-token", web::post().to(handlers::create_admin_token))
                     .route("/projects", web::get().to(handlers::get_all_projects))
                     .route("/projects", web::post().to(handlers::create_project))
                     .route("/projects", web::delete().to(handlers::delete_project))
+                    .route("/projects/detect", web::post().to(detect::handlers::detect_project_settings))
+                    .route("/projects/{id}/team", web::put().to(handlers::set_project_team))
+                    .route("/projects/{id}/issues/{key}/transition", web::post().to(handlers::transition_issue))
+                    .route("/projects/{id}/components", web::get().to(handlers::get_project_components))
+                    .route("/projects/{id}/coverage/validate", web::get().to(coverage::handlers::validate_coverage_report))
+                    .route("/projects/{id}/git", web::get().to(git::handlers::get_project_git_metadata))
+                    .route("/projects/{id}/history", web::get().to(handlers::get_project_history))
+                    .route("/projects/{id}/snapshots", web::get().to(handlers::get_project_snapshots))
+                    .route("/projects/{id}/snapshots", web::post().to(handlers::capture_project_snapshot))
+                    .route("/projects/{id}/scans", web::get().to(scanner::handlers::get_project_scans))
+                    .route("/projects/{id}/scans", web::post().to(scanner::handlers::start_scan))
+                    .route("/projects/{id}/analysis/wait", web::post().to(handlers::wait_for_analysis))
+                    .route("/projects/{id}/snapshots/latest", web::get().to(handlers::get_latest_project_snapshot))
+                    .route("/projects/{id}/snapshots/{snapshot_id}", web::get().to(handlers::get_project_snapshot))
+                    .route("/projects/{id}/compare", web::get().to(handlers::compare_project_results))
+                    .route("/projects/{id}/report.pdf", web::get().to(report::handlers::get_project_report_pdf))
+                    .route("/projects/{id}/report.html", web::get().to(report::handlers::get_project_report_html))
+                    .route("/projects/{id}/report.md", web::get().to(report::handlers::get_project_report_markdown))
+                    .route("/projects/{id}/issues/export", web::get().to(export::handlers::export_project_issues))
+                    .route("/projects/{id}/junit.xml", web::get().to(export::handlers::export_quality_gate_junit))
                     .route("/results", web::post().to(handlers::get_project_results))
                     .route("/generate-command", web::post().to(handlers::generate_sonar_command))
                     .route("/quality-gates", web::get().to(handlers::get_quality_gates))
@@ -49,6 +169,57 @@ pub async fn start() -> std::io::Result<()> {
                     .route("/quality-gates", web::delete().to(handlers::delete_quality_gate))
                     .route("/quality-gates/set-default", web::post().to(handlers::set_default_quality_gate))
                     .route("/quality-gates/assign", web::post().to(handlers::assign_quality_gate_to_project))
+                    // SonarQube webhooks
+                    .route("/webhooks/sonarqube", web::post().to(handlers::receive_sonarqube_webhook))
+                    .route("/webhooks/events", web::get().to(handlers::get_webhook_events))
+                    // Notifications
+                    .route("/notification-channels", web::get().to(notification::handlers::get_channels))
+                    .route("/notification-channels", web::post().to(notification::handlers::create_channel))
+                    .route("/notification-channels/{id}", web::put().to(notification::handlers::update_channel))
+                    .route("/notification-channels/{id}", web::delete().to(notification::handlers::delete_channel))
+                    .route("/notification-channels/{id}/test", web::post().to(notification::handlers::test_channel))
+                    .route("/notifications/deliveries", web::get().to(notification::handlers::get_deliveries))
+                    // Background jobs
+                    .route("/jobs", web::get().to(scheduler::handlers::get_jobs))
+                    .route("/jobs/{name}", web::get().to(scheduler::handlers::get_job))
+                    .route("/jobs/{name}", web::put().to(scheduler::handlers::update_job))
+                    .route("/jobs/{name}/run", web::post().to(scheduler::handlers::run_job))
+                    // Scan runner
+                    .route("/scans/{id}", web::get().to(scanner::handlers::get_scan))
+                    .route("/scans/{id}/cancel", web::post().to(scanner::handlers::cancel_scan))
+                    .route("/scans/{id}/events", web::get().to(scanner::handlers::stream_scan_events))
+                    .route("/scans/{id}/events/token", web::post().to(scanner::handlers::create_scan_events_token))
+                    // Authentication
+                    .route("/auth/providers", web::get().to(auth::handlers::get_auth_providers))
+                    .route("/auth/login", web::post().to(auth::handlers::login))
+                    .route("/auth/logout", web::post().to(auth::handlers::logout))
+                    .route("/auth/me", web::get().to(auth::handlers::get_current_user))
+                    .route("/auth/oidc/login", web::get().to(auth::handlers::start_oidc_login))
+                    .route("/auth/oidc/callback", web::get().to(auth::handlers::complete_oidc_login))
+                    // Users
+                    .route("/users", web::get().to(auth::handlers::get_users))
+                    .route("/users", web::post().to(auth::handlers::create_user))
+                    .route("/users/{id}", web::put().to(auth::handlers::update_user))
+                    .route("/users/{id}", web::delete().to(auth::handlers::delete_user))
+                    .route("/users/{id}/role", web::put().to(auth::handlers::set_user_role))
+                    // Teams
+                    .route("/teams", web::get().to(auth::teams::get_teams))
+                    .route("/teams", web::post().to(auth::teams::create_team))
+                    .route("/teams/{id}", web::get().to(auth::teams::get_team))
+                    .route("/teams/{id}", web::put().to(auth::teams::update_team))
+                    .route("/teams/{id}", web::delete().to(auth::teams::delete_team))
+                    .route("/teams/{id}/members", web::get().to(auth::teams::get_team_members))
+                    .route("/teams/{id}/members/{user_id}", web::put().to(auth::teams::set_team_member))
+                    .route("/teams/{id}/members/{user_id}", web::delete().to(auth::teams::remove_team_member))
+                    // API keys
+                    .route("/api-keys", web::get().to(auth::handlers::get_api_keys))
+                    .route("/api-keys", web::post().to(auth::handlers::create_api_key))
+                    .route("/api-keys/{id}", web::delete().to(auth::handlers::revoke_api_key))
+                    // Audit log
+                    .route("/audit", web::get().to(audit::handlers::get_audit_events))
+                    // Logging
+                    .route("/log-level", web::get().to(config::handlers::get_log_level))
+                    .route("/log-level", web::put().to(config::handlers::set_log_level))
             )
     });
 
@@ -63,3 +234,18 @@ pub async fn start() -> std::io::Result<()> {
     server.run().await
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn masks_tokens_in_the_access_log() {
+        let req = TestRequest::with_uri("/api/scans/12/events?token=secret&last_event_id=4").to_srv_request();
+        assert_eq!(request_line(&req), "GET /api/scans/12/events?token=****&last_event_id=4 HTTP/1.1");
+
+        let req = TestRequest::with_uri("/api/health").to_srv_request();
+        assert_eq!(request_line(&req), "GET /api/health HTTP/1.1");
+    }
+}
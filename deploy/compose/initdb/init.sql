@@ -49,3 +49,275 @@ CREATE INDEX IF NOT EXISTS idx_admin_tokens_token_type ON admin_tokens(token_typ
 UPDATE admin_tokens SET token_type = 'USER_TOKEN' WHERE token_type IS NULL OR token_type = '';
 
 
+
+-- Create result_snapshots table
+CREATE TABLE IF NOT EXISTS result_snapshots (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    analysis_key VARCHAR(255),
+    analysis_date TIMESTAMP,
+    quality_gate_status VARCHAR(20) NOT NULL,
+    quality_gate_conditions JSONB NOT NULL DEFAULT '[]',
+    issues_total INTEGER NOT NULL DEFAULT 0,
+    issues_by_severity JSONB NOT NULL DEFAULT '{}',
+    issues_by_type JSONB NOT NULL DEFAULT '{}',
+    measures JSONB NOT NULL DEFAULT '{}',
+    source VARCHAR(20) NOT NULL DEFAULT 'MANUAL',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (project_id, analysis_key)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_result_snapshots_project_id ON result_snapshots(project_id);
+CREATE INDEX IF NOT EXISTS idx_result_snapshots_created_at ON result_snapshots(created_at);
+
+
+-- Create snapshot_issues table
+CREATE TABLE IF NOT EXISTS snapshot_issues (
+    id SERIAL PRIMARY KEY,
+    snapshot_id INTEGER NOT NULL REFERENCES result_snapshots(id) ON DELETE CASCADE,
+    issue_key VARCHAR(255) NOT NULL,
+    hash VARCHAR(255),
+    rule VARCHAR(255) NOT NULL,
+    severity VARCHAR(20) NOT NULL,
+    issue_type VARCHAR(30) NOT NULL,
+    component VARCHAR(1000) NOT NULL,
+    line INTEGER,
+    message TEXT NOT NULL
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_snapshot_issues_snapshot_id ON snapshot_issues(snapshot_id);
+
+
+-- Create scheduled_jobs table
+CREATE TABLE IF NOT EXISTS scheduled_jobs (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(100) NOT NULL UNIQUE,
+    job_type VARCHAR(50) NOT NULL,
+    cron_expression VARCHAR(100) NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMP,
+    last_run_at TIMESTAMP,
+    last_status VARCHAR(20),
+    last_message TEXT,
+    last_result JSONB,
+    last_duration_ms BIGINT,
+    locked_by VARCHAR(255),
+    locked_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Seed default jobs (cron expressions include seconds)
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('refresh-results', 'REFRESH_RESULTS', '0 0 * * * *'),
+    ('check-tokens', 'CHECK_TOKENS', '0 30 6 * * *'),
+    ('reconcile-projects', 'RECONCILE_PROJECTS', '0 0 7 * * *')
+ON CONFLICT (name) DO NOTHING;
+
+
+-- Create webhook_events table
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES projects(id) ON DELETE SET NULL,
+    project_key VARCHAR(255) NOT NULL,
+    task_id VARCHAR(255),
+    task_status VARCHAR(20) NOT NULL,
+    branch VARCHAR(255),
+    is_main_branch BOOLEAN NOT NULL DEFAULT TRUE,
+    quality_gate_status VARCHAR(20),
+    analysed_at TIMESTAMP,
+    payload JSONB NOT NULL,
+    received_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_webhook_events_project_key ON webhook_events(project_key);
+CREATE INDEX IF NOT EXISTS idx_webhook_events_received_at ON webhook_events(received_at);
+
+
+-- Create notification_channels table
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    project_id INTEGER REFERENCES projects(id) ON DELETE CASCADE,
+    channel_type VARCHAR(20) NOT NULL,
+    target TEXT NOT NULL,
+    template TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create notification_deliveries table
+CREATE TABLE IF NOT EXISTS notification_deliveries (
+    id SERIAL PRIMARY KEY,
+    channel_id INTEGER NOT NULL REFERENCES notification_channels(id) ON DELETE CASCADE,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    event_key VARCHAR(255) NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'PENDING',
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    sent_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (channel_id, event_key)
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_notification_channels_project_id ON notification_channels(project_id);
+CREATE INDEX IF NOT EXISTS idx_notification_deliveries_status ON notification_deliveries(status, next_attempt_at);
+
+-- Retry pending deliveries every minute
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('deliver-notifications', 'DELIVER_NOTIFICATIONS', '0 * * * * *')
+ON CONFLICT (name) DO NOTHING;
+
+-- Create scan_runs table
+CREATE TABLE IF NOT EXISTS scan_runs (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'QUEUED',
+    command TEXT NOT NULL,
+    exit_code INTEGER,
+    error TEXT,
+    stdout TEXT NOT NULL DEFAULT '',
+    stderr TEXT NOT NULL DEFAULT '',
+    cancel_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    runner_id VARCHAR(255),
+    heartbeat_at TIMESTAMP,
+    queued_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP,
+    finished_at TIMESTAMP,
+    duration_ms BIGINT
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_scan_runs_project_id ON scan_runs(project_id);
+CREATE INDEX IF NOT EXISTS idx_scan_runs_status ON scan_runs(status);
+
+-- A project has at most one queued or running scan
+CREATE UNIQUE INDEX IF NOT EXISTS idx_scan_runs_active_project ON scan_runs(project_id) WHERE status IN ('QUEUED', 'RUNNING');
+
+-- Track the phase of running scans and the Compute Engine task they submitted
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS phase VARCHAR(20);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS ce_task_id VARCHAR(64);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS quality_gate_status VARCHAR(20);
+
+-- Record the git branch and commit that scans ran on and snapshots were analysed from
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_branch VARCHAR(255);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_commit VARCHAR(64);
+ALTER TABLE scan_runs ADD COLUMN IF NOT EXISTS git_dirty BOOLEAN;
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_branch VARCHAR(255);
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_commit VARCHAR(64);
+ALTER TABLE result_snapshots ADD COLUMN IF NOT EXISTS git_dirty BOOLEAN;
+
+-- Create users table
+CREATE TABLE IF NOT EXISTS users (
+    id SERIAL PRIMARY KEY,
+    username VARCHAR(255) NOT NULL UNIQUE,
+    display_name VARCHAR(255),
+    email VARCHAR(255),
+    password_hash VARCHAR(255),
+    auth_provider VARCHAR(20) NOT NULL DEFAULT 'LOCAL',
+    external_id VARCHAR(255),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    last_login_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (auth_provider, external_id)
+);
+
+-- Create user_sessions table
+CREATE TABLE IF NOT EXISTS user_sessions (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    auth_provider VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    revoked_at TIMESTAMP
+);
+
+-- Create api_keys table
+CREATE TABLE IF NOT EXISTS api_keys (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    key_prefix VARCHAR(16) NOT NULL,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMP,
+    last_used_at TIMESTAMP,
+    revoked_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_user_sessions_user_id ON user_sessions(user_id);
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
+
+-- Role of a user on every project: VIEWER, DEVELOPER, MAINTAINER or ADMIN, or NULL for team access only.
+-- Users created before roles existed could do everything, so they start as ADMIN.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS global_role VARCHAR(20) DEFAULT 'ADMIN';
+ALTER TABLE users ALTER COLUMN global_role DROP DEFAULT;
+
+-- Create teams table
+CREATE TABLE IF NOT EXISTS teams (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE,
+    description TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create team_members table
+CREATE TABLE IF NOT EXISTS team_members (
+    id SERIAL PRIMARY KEY,
+    team_id INTEGER NOT NULL REFERENCES teams(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (team_id, user_id)
+);
+
+-- Owning team of a project; projects without a team are only visible to users with a global role
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS team_id INTEGER REFERENCES teams(id) ON DELETE SET NULL;
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_team_members_user_id ON team_members(user_id);
+CREATE INDEX IF NOT EXISTS idx_projects_team_id ON projects(team_id);
+
+-- Create audit_events table
+CREATE TABLE IF NOT EXISTS audit_events (
+    id BIGSERIAL PRIMARY KEY,
+    request_id VARCHAR(64) NOT NULL,
+    actor_user_id INTEGER,
+    actor_username VARCHAR(255),
+    auth_method VARCHAR(20),
+    action VARCHAR(100) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id VARCHAR(255),
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(1000) NOT NULL,
+    outcome VARCHAR(20) NOT NULL,
+    status_code INTEGER NOT NULL,
+    error TEXT,
+    before JSONB,
+    after JSONB,
+    client_ip VARCHAR(100),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for better performance
+CREATE INDEX IF NOT EXISTS idx_audit_events_created_at ON audit_events(created_at);
+CREATE INDEX IF NOT EXISTS idx_audit_events_actor_user_id ON audit_events(actor_user_id);
+CREATE INDEX IF NOT EXISTS idx_audit_events_target ON audit_events(target_type, target_id);
+
+-- Drop events older than AUDIT_RETENTION_DAYS once a day
+INSERT INTO scheduled_jobs (name, job_type, cron_expression) VALUES
+    ('purge-audit-events', 'PURGE_AUDIT_EVENTS', '0 15 3 * * *')
+ON CONFLICT (name) DO NOTHING;
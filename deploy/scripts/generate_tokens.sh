@@ -11,10 +11,22 @@ USERNAME="admin"
 PASSWORD="admin"
 SONAR_HOST_URL="http://sonarqube:9000"
 
+# SonarCute user, created by the API from AUTH_ADMIN_USERNAME and AUTH_ADMIN_PASSWORD
+SONARCUTE_USERNAME="${SONARCUTE_USERNAME:-admin}"
+SONARCUTE_PASSWORD="${SONARCUTE_PASSWORD:?SONARCUTE_PASSWORD is required}"
+
+# --- Sign in to SonarCute ---
+echo " Signing in to SonarCute as $SONARCUTE_USERNAME..."
+SESSION_TOKEN=$(curl -sS --fail --location "http://sonarcuteapi:8080/api/auth/login" \
+  --header 'Content-Type: application/json' \
+  --data "$(jq -n --arg u "$SONARCUTE_USERNAME" --arg p "$SONARCUTE_PASSWORD" '{username: $u, password: $p}')" \
+  | jq -r '.token') || { echo " Sign-in failed."; exit 1; }
+
 # --- First call: GLOBAL_ANALYSIS_TOKEN ---
 echo " Generating GLOBAL_ANALYSIS_TOKEN..."
 curl --location "$API_URL" \
   --header 'Content-Type: application/json' \
+  --header "Authorization: Bearer $SESSION_TOKEN" \
   --data "{
     \"username\": \"$USERNAME\",
     \"password\": \"$PASSWORD\",
@@ -29,6 +41,7 @@ echo -e "\n GLOBAL_ANALYSIS_TOKEN request completed.\n"
 echo " Generating USER_TOKEN..."
 curl --location "$API_URL" \
   --header 'Content-Type: application/json' \
+  --header "Authorization: Bearer $SESSION_TOKEN" \
   --data "{
     \"username\": \"$USERNAME\",
     \"password\": \"$PASSWORD\",
@@ -5,6 +5,8 @@ set -euo pipefail
 HOST="http://sonarcuteapi:8080"
 API_BASE="$HOST/api"
 GATE_NAME="Kiosk Gate"
+SONARCUTE_USERNAME="${SONARCUTE_USERNAME:-admin}"
+SONARCUTE_PASSWORD="${SONARCUTE_PASSWORD:?SONARCUTE_PASSWORD is required}"
 
 require() {
   command -v "$1" >/dev/null 2>&1 || { echo "Error: $1 is required but not installed." >&2; exit 1; }
@@ -13,15 +15,24 @@ require() {
 require curl
 require jq
 
+echo "[0/5] Signing in to SonarCute as $SONARCUTE_USERNAME"
+login_payload=$(jq -n --arg u "$SONARCUTE_USERNAME" --arg p "$SONARCUTE_PASSWORD" '{username: $u, password: $p}')
+session_token=$(curl -sS --fail --location "$API_BASE/auth/login" \
+  --header 'Content-Type: application/json' \
+  --data "$login_payload" | jq -r '.token')
+auth_header="Authorization: Bearer $session_token"
+
 echo "[1/5] Creating quality gate: $GATE_NAME"
 create_payload=$(jq -n --arg name "$GATE_NAME" '{name: $name}')
 curl -sS --fail --location "$API_BASE/quality-gates" \
   --header 'Content-Type: application/json' \
+  --header "$auth_header" \
   --data "$create_payload" >/dev/null
 
 echo "[2/5] Fetching quality gate details"
 encoded_name=${GATE_NAME// /%20}
-details_json=$(curl -sS --fail --location "$API_BASE/quality-gates/details?name=$encoded_name")
+details_json=$(curl -sS --fail --location "$API_BASE/quality-gates/details?name=$encoded_name" \
+  --header "$auth_header")
 
 echo "[info] Raw details received:" >&2
 echo "$details_json" | jq '.' >&2 || true
@@ -55,6 +66,7 @@ update_payload=$(jq -n \
 
 curl -sS --fail --location --request PUT "$API_BASE/quality-gates" \
   --header 'Content-Type: application/json' \
+  --header "$auth_header" \
   --data "$update_payload" | jq '.'
 
 echo "[5/5] Updating quality gate: Add custom quality gate rule conditions"
@@ -92,6 +104,7 @@ update_payload=$(jq -n \
 # Make the request and capture both stdout and stderr
 response=$(curl -sS -w "\nHTTP_STATUS:%{http_code}" --location --request PUT "$API_BASE/quality-gates" \
   --header 'Content-Type: application/json' \
+  --header "$auth_header" \
   --data "$update_payload" 2>&1)
 
 # Extract HTTP status code and body
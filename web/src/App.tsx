@@ -1,5 +1,7 @@
 import { BrowserRouter as Router, Routes, Route } from 'react-router-dom';
 import { ProjectListPage } from './pages/ProjectListPage';
+import { LoginPage } from './pages/LoginPage';
+import { ProtectedRoute } from './components/ProtectedRoute';
 import './App.css';
 
 function App() {
@@ -7,7 +9,8 @@ function App() {
     <Router>
       <div className="app">
         <Routes>
-          <Route path="/" element={<ProjectListPage />} />
+          <Route path="/login" element={<LoginPage />} />
+          <Route path="/" element={<ProtectedRoute><ProjectListPage /></ProtectedRoute>} />
         </Routes>
       </div>
     </Router>
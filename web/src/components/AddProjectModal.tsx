@@ -1,7 +1,7 @@
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
 import { X, FolderOpen, AlertCircle } from 'lucide-react';
-import type {CreateProjectRequest, Project} from '../types/api';
-import { projectApi, qualityGateApi } from '../services/api';
+import type {CreateProjectRequest, Project, Team} from '../types/api';
+import { projectApi, qualityGateApi, teamApi } from '../services/api';
 
 interface AddProjectModalProps {
   isOpen: boolean;
@@ -18,6 +18,20 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
   const [coveragePath, setCoveragePath] = useState('build/reports/jacoco/test/jacocoTestReport.xml');
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
+  const [detectionWarnings, setDetectionWarnings] = useState<string[]>([]);
+  const [teams, setTeams] = useState<Team[]>([]);
+  const [teamId, setTeamId] = useState<number | null>(null);
+
+  useEffect(() => {
+    if (!isOpen) return;
+    teamApi.getTeams()
+      .then((loaded) => {
+        setTeams(loaded);
+        // Users without a global role can only create projects in one of their teams
+        setTeamId((current) => current ?? loaded[0]?.id ?? null);
+      })
+      .catch(() => setTeams([]));
+  }, [isOpen]);
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
@@ -41,6 +55,7 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
         sources_path: sourcesPath,
         tests_path: testsPath,
         coverage_report_path: coveragePath,
+        team_id: teamId,
       };
 
       const newProject = await projectApi.createProject(projectData);
@@ -64,6 +79,7 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
       setSourcesPath('src/main/java');
       setTestsPath('src/test/java');
       setCoveragePath('build/reports/jacoco/test/jacocoTestReport.xml');
+      setDetectionWarnings([]);
     } catch (err: any) {
       setError(err.response?.data?.error || 'Failed to create project');
     } finally {
@@ -71,6 +87,25 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
     }
   };
 
+  // Prefill the form from the build files at a full path; the browser folder picker only gives a name
+  const detectSettings = async (path: string) => {
+    if (!path) return;
+    try {
+      const detection = await projectApi.detectProject(path);
+      if (!projectName || projectName === path.split(/[/\\]/).pop()) {
+        setProjectName(detection.project_name);
+      }
+      if (detection.language) setLanguage(detection.language);
+      if (detection.sources_path) setSourcesPath(detection.sources_path);
+      if (detection.tests_path) setTestsPath(detection.tests_path);
+      if (detection.coverage_report_path) setCoveragePath(detection.coverage_report_path);
+      setDetectionWarnings(detection.warnings);
+    } catch {
+      // Not a directory on the server; keep the settings entered by hand
+      setDetectionWarnings([]);
+    }
+  };
+
   const handleFolderSelect = async () => {
     try {
       if ('showDirectoryPicker' in window) {
@@ -95,6 +130,7 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
             const folderName = fullPath.trim().split(/[/\\]/).pop() || fullPath.trim();
             setProjectName(folderName);
           }
+          await detectSettings(fullPath.trim());
         }
       }
     } catch (error: any) {
@@ -148,6 +184,7 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
                   type="text"
                   value={selectedPath}
                   onChange={(e) => setSelectedPath(e.target.value)}
+                  onBlur={(e) => detectSettings(e.target.value.trim())}
                   className="flex-1 px-3 py-2 border border-gray-300 rounded-l-md bg-white text-sm"
                   placeholder="Select project folder or enter full path..."
                 />
@@ -160,6 +197,9 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
                   <FolderOpen className="h-4 w-4" />
                 </button>
               </div>
+              {detectionWarnings.map((warning) => (
+                <p key={warning} className="mt-1 text-xs text-amber-700">{warning}</p>
+              ))}
             </div>
 
             {/* Project Name */}
@@ -177,6 +217,25 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
               />
             </div>
 
+            {/* Team */}
+            {teams.length > 0 && (
+              <div>
+                <label className="block text-sm font-medium text-gray-700 mb-1">
+                  Team
+                </label>
+                <select
+                  value={teamId ?? ''}
+                  onChange={(e) => setTeamId(e.target.value ? Number(e.target.value) : null)}
+                  className="w-full px-3 py-2 border border-gray-300 rounded-md text-sm"
+                >
+                  <option value="">No team (global roles only)</option>
+                  {teams.map((team) => (
+                    <option key={team.id} value={team.id}>{team.name}</option>
+                  ))}
+                </select>
+              </div>
+            )}
+
             {/* Language */}
             <div>
               <label className="block text-sm font-medium text-gray-700 mb-1">
@@ -188,11 +247,14 @@ export const AddProjectModal = ({ isOpen, onClose, onProjectAdded }: AddProjectM
                 className="w-full px-3 py-2 border border-gray-300 rounded-md text-sm"
               >
                 <option value="java">Java</option>
+                <option value="kotlin">Kotlin</option>
                 <option value="javascript">JavaScript</option>
                 <option value="typescript">TypeScript</option>
                 <option value="python">Python</option>
                 <option value="csharp">C#</option>
                 <option value="cpp">C++</option>
+                <option value="go">Go</option>
+                <option value="rust">Rust</option>
               </select>
             </div>
 
@@ -0,0 +1,28 @@
+import { useState, useEffect } from 'react';
+import { Navigate } from 'react-router-dom';
+import { authApi } from '../services/api';
+import { isAuthenticated } from '../services/auth';
+
+// Sends visitors without a session to /login, unless the server has authentication disabled
+export const ProtectedRoute = ({ children }: { children: React.ReactNode }) => {
+  const [authRequired, setAuthRequired] = useState<boolean | null>(isAuthenticated() ? false : null);
+
+  useEffect(() => {
+    if (authRequired !== null) {
+      return;
+    }
+    authApi.getProviders()
+      .then((providers) => setAuthRequired(providers.enabled))
+      .catch(() => setAuthRequired(true));
+  }, [authRequired]);
+
+  if (authRequired === null) {
+    return (
+      <div className="flex justify-center items-center h-64">
+        <div className="animate-spin rounded-full h-12 w-12 border-b-2 border-blue-600"></div>
+      </div>
+    );
+  }
+
+  return authRequired ? <Navigate to="/login" replace /> : <>{children}</>;
+};
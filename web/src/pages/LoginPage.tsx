@@ -0,0 +1,128 @@
+import { useState, useEffect } from 'react';
+import { useNavigate } from 'react-router-dom';
+import { AlertCircle, LogIn } from 'lucide-react';
+import type { AuthProviders } from '../types/api';
+import { authApi } from '../services/api';
+import { setToken } from '../services/auth';
+import Logo from '../assets/logo.png';
+
+export const LoginPage = () => {
+  const navigate = useNavigate();
+  const [providers, setProviders] = useState<AuthProviders | null>(null);
+  const [username, setUsername] = useState('');
+  const [password, setPassword] = useState('');
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    // Returning from the identity provider: #token=...&expires_at=... or #error=...
+    const fragment = new URLSearchParams(window.location.hash.slice(1));
+    window.history.replaceState(null, '', window.location.pathname);
+    const token = fragment.get('token');
+    if (token) {
+      setToken(token, Number(fragment.get('expires_at')));
+      navigate('/', { replace: true });
+      return;
+    }
+    if (fragment.get('error')) {
+      setError(fragment.get('error'));
+    }
+
+    authApi.getProviders()
+      .then((data) => {
+        if (!data.enabled) {
+          navigate('/', { replace: true });
+        }
+        setProviders(data);
+      })
+      .catch((err) => {
+        setError('Failed to reach the server. Please check if the backend is running.');
+        console.error('Error fetching auth providers:', err);
+      });
+  }, [navigate]);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    try {
+      setLoading(true);
+      setError(null);
+      await authApi.login(username, password);
+      navigate('/', { replace: true });
+    } catch (err: any) {
+      setError(err.response?.data?.error || 'Sign-in failed');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div className="min-h-screen bg-gray-50 flex items-center justify-center px-4">
+      <div className="w-full max-w-sm p-6 border shadow-lg rounded-md bg-white">
+        <div className="flex items-center mb-6">
+          <img src={Logo} alt="SonarCute Logo" className="h-8 w-8 mr-3" />
+          <h1 className="text-2xl font-bold text-gray-900">Sign in to SonarCute</h1>
+        </div>
+
+        {error && (
+          <div className="mb-4 bg-red-50 border border-red-200 rounded-md p-3">
+            <div className="flex">
+              <AlertCircle className="h-5 w-5 text-red-400" />
+              <div className="ml-3">
+                <p className="text-sm text-red-800">{error}</p>
+              </div>
+            </div>
+          </div>
+        )}
+
+        <form onSubmit={handleSubmit} className="space-y-4">
+          <div>
+            <label className="block text-sm font-medium text-gray-700 mb-1">
+              Username
+            </label>
+            <input
+              type="text"
+              value={username}
+              onChange={(e) => setUsername(e.target.value)}
+              autoComplete="username"
+              className="w-full px-3 py-2 border border-gray-300 rounded-md text-sm"
+              required
+            />
+          </div>
+          <div>
+            <label className="block text-sm font-medium text-gray-700 mb-1">
+              Password
+            </label>
+            <input
+              type="password"
+              value={password}
+              onChange={(e) => setPassword(e.target.value)}
+              autoComplete="current-password"
+              className="w-full px-3 py-2 border border-gray-300 rounded-md text-sm"
+              required
+            />
+          </div>
+          <button
+            type="submit"
+            disabled={loading}
+            className="w-full inline-flex justify-center items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 disabled:opacity-50"
+          >
+            <LogIn className="h-4 w-4 mr-2" />
+            {loading ? 'Signing in...' : 'Sign in'}
+          </button>
+        </form>
+
+        {providers?.oidc && (
+          <div className="mt-4">
+            <div className="text-center text-xs text-gray-500 mb-4">or</div>
+            <a
+              href={authApi.oidcLoginUrl(providers.oidc.login_url)}
+              className="w-full inline-flex justify-center items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md text-gray-700 bg-white hover:bg-gray-50"
+            >
+              Sign in with {providers.oidc.name}
+            </a>
+          </div>
+        )}
+      </div>
+    </div>
+  );
+};
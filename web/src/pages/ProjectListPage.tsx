@@ -1,7 +1,9 @@
 import { useState, useEffect } from 'react';
-import { Plus, FolderOpen } from 'lucide-react';
+import { Plus, FolderOpen, LogOut } from 'lucide-react';
+import { useNavigate } from 'react-router-dom';
 import type {Project} from '../types/api';
-import { projectApi } from '../services/api';
+import { projectApi, authApi } from '../services/api';
+import { isAuthenticated } from '../services/auth';
 import { AddProjectModal } from '../components/AddProjectModal';
 import { ProjectCard } from '../components/ProjectCard';
 import { TourButton } from '../components/TourButton';
@@ -10,6 +12,7 @@ import { getMockProjects, isTourCurrentlyActive, injectMockDataForTour } from '.
 import Logo from '../assets/logo.png';
 
 export const ProjectListPage = () => {
+  const navigate = useNavigate();
   const [projects, setProjects] = useState<Project[]>([]);
   const [isAddModalOpen, setIsAddModalOpen] = useState(false);
   const [loading, setLoading] = useState(true);
@@ -74,6 +77,15 @@ export const ProjectListPage = () => {
     );
   };
 
+  const handleLogout = async () => {
+    try {
+      await authApi.logout();
+    } catch (err) {
+      console.error('Error signing out:', err);
+    }
+    navigate('/login');
+  };
+
   const handleProjectDeleted = () => {
     // Refresh the project list after deletion
     fetchProjects();
@@ -90,14 +102,25 @@ export const ProjectListPage = () => {
                 SonarCute Projects
               </h1>
             </div>
-            <button
-              onClick={() => setIsAddModalOpen(true)}
-              data-tour="add-project"
-              className="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500"
-            >
-              <Plus className="h-4 w-4 mr-2" />
-              Add Project
-            </button>
+            <div className="flex items-center space-x-3">
+              <button
+                onClick={() => setIsAddModalOpen(true)}
+                data-tour="add-project"
+                className="inline-flex items-center px-4 py-2 border border-transparent text-sm font-medium rounded-md shadow-sm text-white bg-blue-600 hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-offset-2 focus:ring-blue-500"
+              >
+                <Plus className="h-4 w-4 mr-2" />
+                Add Project
+              </button>
+              {isAuthenticated() && (
+                <button
+                  onClick={handleLogout}
+                  className="inline-flex items-center px-4 py-2 border border-gray-300 text-sm font-medium rounded-md text-gray-700 bg-white hover:bg-gray-50"
+                >
+                  <LogOut className="h-4 w-4 mr-2" />
+                  Sign out
+                </button>
+              )}
+            </div>
           </div>
         </div>
       </header>
@@ -6,10 +6,17 @@ import type {
     CreateAdminTokenRequest,
     ScanCommandResponse,
     ProjectResults,
-    AssignQualityGateRequest
+    AssignQualityGateRequest,
+    ProjectDetection,
+    AuthProviders,
+    LoginResponse,
+    CurrentUser,
+    Team
 } from '../types/api';
+import { getToken, setToken, clearToken } from './auth';
 
 const API_BASE_URL = 'http://localhost:8888/api';
+const SERVER_URL = API_BASE_URL.replace(/\/api$/, '');
 
 const api = axios.create({
   baseURL: API_BASE_URL,
@@ -18,6 +25,58 @@ const api = axios.create({
   },
 });
 
+api.interceptors.request.use((config) => {
+  const token = getToken();
+  if (token) {
+    config.headers.Authorization = `Bearer ${token}`;
+  }
+  return config;
+});
+
+// An ended session sends the user back to the login page
+api.interceptors.response.use(
+  (response) => response,
+  (error) => {
+    if (error.response?.status === 401 && !error.config?.url?.startsWith('/auth/login')) {
+      clearToken();
+      if (window.location.pathname !== '/login') {
+        window.location.assign('/login');
+      }
+    }
+    return Promise.reject(error);
+  }
+);
+
+export const authApi = {
+  getProviders: async (): Promise<AuthProviders> => {
+    const response = await api.get('/auth/providers');
+    return response.data;
+  },
+
+  login: async (username: string, password: string): Promise<LoginResponse> => {
+    const response = await api.post('/auth/login', { username, password });
+    const data: LoginResponse = response.data;
+    setToken(data.token, Math.floor(new Date(`${data.expires_at}Z`).getTime() / 1000));
+    return data;
+  },
+
+  logout: async (): Promise<void> => {
+    try {
+      await api.post('/auth/logout');
+    } finally {
+      clearToken();
+    }
+  },
+
+  getCurrentUser: async (): Promise<CurrentUser> => {
+    const response = await api.get('/auth/me');
+    return response.data;
+  },
+
+  // The server redirects back to /login with the session token in the URL fragment
+  oidcLoginUrl: (loginUrl: string): string => `${SERVER_URL}${loginUrl}`,
+};
+
 export const projectApi = {
   getAllProjects: async (): Promise<Project[]> => {
     const response = await api.get('/projects');
@@ -29,6 +88,11 @@ export const projectApi = {
     return response.data;
   },
 
+  detectProject: async (projectPath: string): Promise<ProjectDetection> => {
+    const response = await api.post('/projects/detect', { project_path: projectPath });
+    return response.data;
+  },
+
   generateCommand: async (projectPath: string): Promise<ScanCommandResponse> => {
     const response = await api.post('/generate-command', { project_path: projectPath });
     return response.data;
@@ -44,6 +108,14 @@ export const projectApi = {
   },
 };
 
+export const teamApi = {
+  // Every team for users with a global role, otherwise the caller's teams
+  getTeams: async (): Promise<Team[]> => {
+    const response = await api.get('/teams');
+    return response.data;
+  },
+};
+
 export const adminTokenApi = {
   createAdminToken: async (data: CreateAdminTokenRequest): Promise<AdminToken> => {
     const response = await api.post('/admin-token', data);
@@ -0,0 +1,24 @@
+const TOKEN_KEY = 'sonarcute-token';
+const EXPIRES_KEY = 'sonarcute-token-expires';
+
+export const getToken = (): string | null => {
+  const token = localStorage.getItem(TOKEN_KEY);
+  const expiresAt = Number(localStorage.getItem(EXPIRES_KEY));
+  if (!token || (expiresAt && expiresAt * 1000 <= Date.now())) {
+    return null;
+  }
+  return token;
+};
+
+// expiresAt is in seconds since the epoch
+export const setToken = (token: string, expiresAt: number) => {
+  localStorage.setItem(TOKEN_KEY, token);
+  localStorage.setItem(EXPIRES_KEY, String(expiresAt));
+};
+
+export const clearToken = () => {
+  localStorage.removeItem(TOKEN_KEY);
+  localStorage.removeItem(EXPIRES_KEY);
+};
+
+export const isAuthenticated = (): boolean => getToken() !== null;
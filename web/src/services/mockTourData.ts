@@ -146,7 +146,7 @@ export const mockProjectResults: ProjectResults = {
   }
 };
 
-export const mockSonarCommand = `./gradlew sonar -Dsonar.token=mock-token-for-tour -Dsonar.host.url=http://localhost:9000 -Dsonar.projectKey=sample-project-key -Dsonar.projectName=Sample Java Project -Dsonar.coverage.jacoco.xmlReportPaths=target/site/jacoco/jacoco.xml -Dsonar.language=java -Dsonar.sources=src/main/java -Dsonar.tests=src/test/java`;
+export const mockSonarCommand = `SONAR_TOKEN=mock-token-for-tour ./gradlew sonar -Dsonar.host.url=http://localhost:9000 -Dsonar.projectKey=sample-project-key -Dsonar.projectName=Sample Java Project -Dsonar.coverage.jacoco.xmlReportPaths=target/site/jacoco/jacoco.xml -Dsonar.language=java -Dsonar.sources=src/main/java -Dsonar.tests=src/test/java`;
 
 let isTourActive = false;
 
@@ -9,6 +9,7 @@ export interface Project {
   sources_path: string;
   tests_path: string;
   coverage_report_path?: string;
+  team_id: number | null;
   created_at: string;
   updated_at: string;
 }
@@ -21,6 +22,32 @@ export interface CreateProjectRequest {
   sources_path: string;
   tests_path: string;
   coverage_report_path?: string;
+  team_id?: number | null;
+}
+
+export interface DetectedBuild {
+  build_tool: string;
+  manifest: string;
+  language: string;
+  project_name?: string;
+  sources_path: string;
+  tests_path: string;
+  coverage_report_path: string;
+  coverage_report_exists: boolean;
+}
+
+export interface ProjectDetection {
+  project_path: string;
+  project_key: string;
+  project_name: string;
+  build_tool?: string;
+  language?: string;
+  languages: string[];
+  sources_path?: string;
+  tests_path?: string;
+  coverage_report_path?: string;
+  detected: DetectedBuild[];
+  warnings: string[];
 }
 
 export interface AdminToken {
@@ -97,4 +124,48 @@ export interface ProjectResults {
 export interface AssignQualityGateRequest {
   project_key: string;
   gate_name: string;
-}
\ No newline at end of file
+}
+export interface AuthProviders {
+  enabled: boolean;
+  local: boolean;
+  oidc: {
+    name: string;
+    login_url: string;
+  } | null;
+}
+
+export interface User {
+  id: number;
+  username: string;
+  display_name: string | null;
+  email: string | null;
+  auth_provider: string; // "LOCAL" or "OIDC"
+  active: boolean;
+  global_role: Role | null;
+  last_login_at: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface LoginResponse {
+  token: string;
+  token_type: string;
+  expires_at: string;
+  user: User;
+}
+
+export interface CurrentUser {
+  user: User;
+  auth_method: string; // "SESSION" or "API_KEY"
+  teams: { id: number; name: string; role: Role }[];
+}
+
+export type Role = 'VIEWER' | 'DEVELOPER' | 'MAINTAINER' | 'ADMIN';
+
+export interface Team {
+  id: number;
+  name: string;
+  description: string | null;
+  created_at: string;
+  updated_at: string;
+}